@@ -0,0 +1,438 @@
+//! # MCP Rust Starter - Audit Log
+//!
+//! An append-only record of every `tools/call`, written as JSON Lines:
+//!
+//! ```json
+//! {"timestamp":"2026-01-01T12:00:00.123456Z","session_id":"3f2a…","principal":"alice",
+//!  "tool":"ask_llm","arguments_sha256":"9b1c…","arguments":{"prompt":"[redacted]","maxTokens":10},
+//!  "status":"ok","duration_ms":1.7}
+//! ```
+//!
+//! `arguments_sha256` hashes the arguments as received, so a record can be
+//! matched against a known input without storing it; `arguments` is the
+//! redacted copy also used in logs (see [`crate::telemetry::redact`]).
+//! `status` is `ok`, `tool_error` (an `isError` result, with the tool error
+//! code in `error_code`) or `error` (a JSON-RPC error, with its code).
+//!
+//! When the file reaches its size limit it is rotated: `audit.jsonl` becomes
+//! `audit.jsonl.1`, `audit.jsonl.1` becomes `audit.jsonl.2`, and so on, and
+//! the oldest file beyond the limit is deleted. A failed write is logged and
+//! does not fail the tool call. Writes run on tokio's blocking thread pool.
+//!
+//! The `mcp-rust-starter-audit` binary filters and summarises these files.
+//!
+//! ## Configuration
+//!
+//! | variable              | default |
+//! |-----------------------|---------|
+//! | `MCP_AUDIT_LOG`       | unset (no audit log); the path to write |
+//! | `MCP_AUDIT_MAX_BYTES` | `10485760` (10 MiB) per file |
+//! | `MCP_AUDIT_MAX_FILES` | `5` rotated files kept, at least `1` |
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rmcp::model::{CallToolResult, JsonObject};
+use rmcp::ErrorData as McpError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Default size at which the log is rotated.
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Default number of rotated files kept.
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+/// How a tool call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    ToolError,
+    Error,
+}
+
+impl Status {
+    /// The status as written to the log.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::ToolError => "tool_error",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for Status {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ok" => Ok(Self::Ok),
+            "tool_error" => Ok(Self::ToolError),
+            "error" => Ok(Self::Error),
+            other => Err(format!(
+                "unknown status '{other}' (expected ok, tool_error or error)"
+            )),
+        }
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// When the call finished.
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub principal: Option<String>,
+    pub tool: String,
+    pub arguments_sha256: String,
+    pub arguments: Value,
+    pub status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    pub duration_ms: f64,
+}
+
+impl Record {
+    /// A record for a call that just finished.
+    #[must_use]
+    pub fn new(
+        tool: &str,
+        arguments: &JsonObject,
+        redacted: Value,
+        result: &Result<CallToolResult, McpError>,
+        duration: Duration,
+    ) -> Self {
+        let (status, error_code) = match result {
+            Ok(result) if result.is_error == Some(true) => (
+                Status::ToolError,
+                result
+                    .structured_content
+                    .as_ref()
+                    .and_then(|content| content.get("code"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            ),
+            Ok(_) => (Status::Ok, None),
+            Err(e) => (Status::Error, Some(e.code.0.to_string())),
+        };
+        Self {
+            timestamp: Utc::now(),
+            session_id: None,
+            principal: None,
+            tool: tool.to_string(),
+            arguments_sha256: hash_arguments(arguments),
+            arguments: redacted,
+            status,
+            error_code,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+        }
+    }
+
+    /// Attribute the record to a session and principal.
+    #[must_use]
+    pub fn by(mut self, session_id: Option<&str>, principal: Option<&str>) -> Self {
+        self.session_id = session_id.map(str::to_string);
+        self.principal = principal.map(str::to_string);
+        self
+    }
+}
+
+/// Hex SHA-256 of the arguments' JSON (object keys are serialized sorted).
+#[must_use]
+pub fn hash_arguments(arguments: &JsonObject) -> String {
+    let json = serde_json::to_vec(arguments).unwrap_or_default();
+    format!("{:x}", Sha256::digest(json))
+}
+
+struct Sink {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+/// The audit log writer. The default writes nothing.
+#[derive(Default)]
+pub struct AuditLog {
+    sink: Option<Arc<Mutex<Sink>>>,
+}
+
+impl AuditLog {
+    /// Append to the file at `path`, rotating it at `max_bytes` and keeping
+    /// `max_files` rotated files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `max_files` is 0, which would leave nowhere to
+    /// rotate to, or if the file cannot be opened.
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if max_files == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "at least one rotated file must be kept",
+            ));
+        }
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            sink: Some(Arc::new(Mutex::new(Sink {
+                path,
+                file,
+                size,
+                max_bytes,
+                max_files,
+            }))),
+        })
+    }
+
+    /// Configure from `MCP_AUDIT_LOG`, `MCP_AUDIT_MAX_BYTES` and
+    /// `MCP_AUDIT_MAX_FILES`; disabled when `MCP_AUDIT_LOG` is unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of an invalid setting or an unopenable file.
+    pub fn from_env() -> Result<Self, String> {
+        let Some(path) = std::env::var("MCP_AUDIT_LOG")
+            .ok()
+            .filter(|path| !path.is_empty())
+        else {
+            return Ok(Self::default());
+        };
+        let max_bytes = std::env::var("MCP_AUDIT_MAX_BYTES")
+            .map_or(Ok(DEFAULT_MAX_BYTES), |value| value.parse())
+            .map_err(|e| format!("MCP_AUDIT_MAX_BYTES: {e}"))?;
+        let max_files = std::env::var("MCP_AUDIT_MAX_FILES")
+            .map_or(Ok(DEFAULT_MAX_FILES), |value| value.parse())
+            .map_err(|e| format!("MCP_AUDIT_MAX_FILES: {e}"))?;
+        if max_files == 0 {
+            return Err("MCP_AUDIT_MAX_FILES: must be at least 1".to_string());
+        }
+        Self::open(&path, max_bytes, max_files).map_err(|e| format!("MCP_AUDIT_LOG {path}: {e}"))
+    }
+
+    /// Whether records are written anywhere.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Append a record, rotating first if it would overflow the file. The
+    /// write runs on the blocking thread pool, so a slow disk holds up only
+    /// the call being recorded, not the async workers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file lock was poisoned.
+    pub async fn record(&self, record: &Record) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::error!("Failed to serialize audit record: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let sink = Arc::clone(sink);
+        let written = tokio::task::spawn_blocking(move || {
+            sink.lock().expect("audit log lock").append(&line);
+        })
+        .await;
+        if let Err(e) = written {
+            tracing::error!("Failed to write audit record: {}", e);
+        }
+    }
+}
+
+impl Sink {
+    fn append(&mut self, line: &[u8]) {
+        let len = line.len() as u64;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            if let Err(e) = self.rotate() {
+                tracing::error!("Failed to rotate audit log: {}", e);
+            }
+        }
+        match self.file.write_all(line).and_then(|()| self.file.flush()) {
+            Ok(()) => self.size += len,
+            Err(e) => tracing::error!("Failed to write audit record: {}", e),
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..self.max_files).rev() {
+            let from = rotated(&self.path, n);
+            if from.exists() {
+                fs::rename(from, rotated(&self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(&self.path, 1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+/// The log at `path` and its rotated files that exist, oldest first.
+#[must_use]
+pub fn log_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut n = 1;
+    while rotated(path, n).exists() {
+        files.push(rotated(path, n));
+        n += 1;
+    }
+    files.reverse();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+    files
+}
+
+/// The records read from an audit log.
+#[derive(Debug, Clone, Default)]
+pub struct Contents {
+    /// Every valid record, in order.
+    pub records: Vec<Record>,
+    /// Where and why each line that is not a record (e.g. a line cut short
+    /// by a crash) was skipped, as `file:line: error`.
+    pub skipped: Vec<String>,
+}
+
+/// Read every record in `files`, in order, noting the lines that are not
+/// records rather than failing on them.
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read.
+pub fn read(files: &[PathBuf]) -> io::Result<Contents> {
+    let mut contents = Contents::default();
+    for file in files {
+        for (number, line) in BufReader::new(File::open(file)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => contents.records.push(record),
+                Err(e) => contents
+                    .skipped
+                    .push(format!("{}:{}: {e}", file.display(), number + 1)),
+            }
+        }
+    }
+    Ok(contents)
+}
+
+/// Criteria for selecting records; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub tool: Option<String>,
+    pub session_id: Option<String>,
+    pub principal: Option<String>,
+    pub status: Option<Status>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Filter {
+    /// Whether `record` meets every criterion.
+    #[must_use]
+    pub fn matches(&self, record: &Record) -> bool {
+        self.tool.as_ref().is_none_or(|tool| *tool == record.tool)
+            && self
+                .session_id
+                .as_ref()
+                .is_none_or(|id| record.session_id.as_ref() == Some(id))
+            && self
+                .principal
+                .as_ref()
+                .is_none_or(|principal| record.principal.as_ref() == Some(principal))
+            && self.status.is_none_or(|status| status == record.status)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp < until)
+    }
+}
+
+/// Per-tool totals over a set of records.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolSummary {
+    pub calls: u64,
+    pub ok: u64,
+    pub tool_errors: u64,
+    pub errors: u64,
+    pub mean_duration_ms: f64,
+    pub max_duration_ms: f64,
+}
+
+/// Totals over a set of records, by tool.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Summary {
+    pub calls: u64,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    pub tools: BTreeMap<String, ToolSummary>,
+}
+
+impl Summary {
+    /// Summarise `records`.
+    #[must_use]
+    pub fn of<'a>(records: impl IntoIterator<Item = &'a Record>) -> Self {
+        let mut summary = Self::default();
+        let mut total_ms = BTreeMap::<&str, f64>::new();
+        for record in records {
+            summary.calls += 1;
+            summary.first = Some(
+                summary
+                    .first
+                    .map_or(record.timestamp, |first| first.min(record.timestamp)),
+            );
+            summary.last = summary.last.max(Some(record.timestamp));
+            let tool = summary.tools.entry(record.tool.clone()).or_default();
+            tool.calls += 1;
+            match record.status {
+                Status::Ok => tool.ok += 1,
+                Status::ToolError => tool.tool_errors += 1,
+                Status::Error => tool.errors += 1,
+            }
+            tool.max_duration_ms = tool.max_duration_ms.max(record.duration_ms);
+            *total_ms.entry(&record.tool).or_default() += record.duration_ms;
+        }
+        for (name, tool) in &mut summary.tools {
+            #[allow(clippy::cast_precision_loss)]
+            let calls = tool.calls as f64;
+            tool.mean_duration_ms = total_ms[name.as_str()] / calls;
+        }
+        summary
+    }
+}
+
+/// Format a timestamp the way the log does.
+#[must_use]
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
@@ -0,0 +1,178 @@
+//! # MCP Rust Starter - Audit Log Reader
+//!
+//! Filters and summarises the audit log written when `MCP_AUDIT_LOG` is set.
+//!
+//! ## Usage
+//!
+//! ```sh
+//! # Every failed call to long_task today, as JSON Lines
+//! cargo run --bin mcp-rust-starter-audit -- audit.jsonl \
+//!     --tool long_task --status tool_error --since 2026-01-01T00:00:00Z
+//!
+//! # Per-tool call counts, outcomes and durations
+//! cargo run --bin mcp-rust-starter-audit -- audit.jsonl --summary
+//! ```
+//!
+//! Rotated files (`audit.jsonl.1`, `audit.jsonl.2`, …) are read too, oldest
+//! first. Lines that are not records are reported on stderr and skipped,
+//! and the exit status is then 1, so a damaged log does not pass unnoticed.
+
+use std::{io::Write, path::PathBuf, process::ExitCode};
+
+use chrono::{DateTime, Utc};
+use mcp_rust_starter::audit::{self, Filter, Summary};
+
+const USAGE: &str = "\
+Usage: mcp-rust-starter-audit [FILE] [OPTIONS]
+
+FILE defaults to $MCP_AUDIT_LOG.
+
+Options:
+  --tool NAME          only calls to this tool
+  --session ID         only calls in this session
+  --principal NAME     only calls by this principal
+  --status STATUS      only calls that ended ok, tool_error or error
+  --since TIME         only calls at or after this RFC 3339 time
+  --until TIME         only calls before this RFC 3339 time
+  --summary            print per-tool totals instead of records
+  -h, --help           print this help";
+
+struct Args {
+    file: PathBuf,
+    filter: Filter,
+    summary: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut file = None;
+    let mut filter = Filter::default();
+    let mut summary = false;
+    let time = |value: String| {
+        DateTime::parse_from_rfc3339(&value)
+            .map(|time| time.with_timezone(&Utc))
+            .map_err(|e| format!("invalid time '{value}': {e}"))
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--tool" => filter.tool = Some(value()?),
+            "--session" => filter.session_id = Some(value()?),
+            "--principal" => filter.principal = Some(value()?),
+            "--status" => filter.status = Some(value()?.parse()?),
+            "--since" => filter.since = Some(time(value()?)?),
+            "--until" => filter.until = Some(time(value()?)?),
+            "--summary" => summary = true,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {flag}")),
+            path if file.is_none() => file = Some(PathBuf::from(path)),
+            extra => return Err(format!("unexpected argument {extra}")),
+        }
+    }
+
+    let file = file
+        .or_else(|| std::env::var_os("MCP_AUDIT_LOG").map(PathBuf::from))
+        .ok_or("no audit log given and MCP_AUDIT_LOG is not set")?;
+    Ok(Some(Args {
+        file,
+        filter,
+        summary,
+    }))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let files = audit::log_files(&args.file);
+    if files.is_empty() {
+        eprintln!("error: {} does not exist", args.file.display());
+        return ExitCode::FAILURE;
+    }
+    let contents = match audit::read(&files) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    for skipped in &contents.skipped {
+        eprintln!("warning: skipped invalid record at {skipped}");
+    }
+    let mut matching = contents
+        .records
+        .iter()
+        .filter(|record| args.filter.matches(record));
+
+    let mut out = std::io::stdout().lock();
+    let written = if args.summary {
+        print_summary(&mut out, &Summary::of(matching))
+    } else {
+        matching.try_for_each(|record| {
+            serde_json::to_writer(&mut out, record)?;
+            writeln!(out)
+        })
+    };
+    match written {
+        Ok(()) if !contents.skipped.is_empty() => {
+            eprintln!(
+                "error: skipped {} invalid record(s)",
+                contents.skipped.len()
+            );
+            ExitCode::FAILURE
+        }
+        Ok(()) => ExitCode::SUCCESS,
+        // Stop quietly when piped into something like `head`
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_summary(out: &mut impl Write, summary: &Summary) -> std::io::Result<()> {
+    let range = summary
+        .first
+        .zip(summary.last)
+        .map(|(first, last)| {
+            format!(
+                " from {} to {}",
+                audit::format_timestamp(&first),
+                audit::format_timestamp(&last)
+            )
+        })
+        .unwrap_or_default();
+    writeln!(out, "{} calls{range}", summary.calls)?;
+    if summary.tools.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        out,
+        "\n{:<20} {:>7} {:>7} {:>11} {:>7} {:>10} {:>10}",
+        "tool", "calls", "ok", "tool_error", "error", "mean_ms", "max_ms"
+    )?;
+    for (name, tool) in &summary.tools {
+        writeln!(
+            out,
+            "{:<20} {:>7} {:>7} {:>11} {:>7} {:>10.1} {:>10.1}",
+            name,
+            tool.calls,
+            tool.ok,
+            tool.tool_errors,
+            tool.errors,
+            tool.mean_duration_ms,
+            tool.max_duration_ms
+        )?;
+    }
+    Ok(())
+}
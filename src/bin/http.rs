@@ -9,7 +9,62 @@
 //! cargo run --bin mcp-rust-starter-http
 //! ```
 //!
-//! The server will listen on `http://localhost:3000/mcp`
+//! The server will listen on `http://localhost:3000/mcp`, with Prometheus
+//! metrics at `http://localhost:3000/metrics` and liveness/readiness probes
+//! at `/health/live` and `/health/ready`.
+//!
+//! Tool calls are rate limited per session, per principal and per tool, and
+//! the number of concurrent tool calls is capped; see the `limits` module for
+//! the `MCP_RATE_LIMIT_*` settings. Requests over a limit get `429` with a
+//! `Retry-After` header.
+//!
+//! On Ctrl+C or SIGTERM the server starts draining: `/health/ready` reports
+//! `draining` for `MCP_DRAIN_SECONDS` (default 5) so load balancers stop
+//! sending traffic, then open MCP streams are closed and the server exits
+//! once in-flight requests complete.
+//!
+//! Set `MCP_ICON_BASE_URL` (e.g. `http://localhost:3000/icons`) to advertise
+//! icons by URL, served from `/icons/{file}`, instead of inlining them as
+//! `data:` URIs in every list response.
+//!
+//! Set `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g. `http://localhost:4318`) to export
+//! a trace span per MCP request to an OpenTelemetry collector. A W3C
+//! `traceparent` header on the HTTP request is used as the span's parent.
+//!
+//! Set `MCP_POLICY` to deny or require confirmation for tools by their
+//! annotations (e.g. `deny destructive unauthenticated`), and `MCP_OFFLINE=true`
+//! to refuse open-world tools; see the `policy` module.
+//!
+//! Set `MCP_AUDIT_LOG` to a file path to keep an append-only JSON Lines
+//! audit log of every tool call; read it with `mcp-rust-starter-audit`.
+//!
+//! Set `MCP_TOOL_MANIFEST` to a TOML or YAML file to add declarative tools,
+//! prompts and resources; it is watched and reloaded when it changes. See the
+//! `manifest` and `reload` modules.
+//!
+//! Set `MCP_RESOURCE_DIR` to a directory to expose its files as `file://`
+//! resources; see the `files` module for include/exclude globs. Resources
+//! over `MCP_RESOURCE_MAX_INLINE_BYTES` (1 MiB) are returned as links.
+//!
+//! Set `MCP_PLUGIN_DIR` to a directory of WebAssembly plugin tools; it is
+//! watched for plugins being added, changed and removed. See the `plugin` module.
+//!
+//! Set `MCP_HTTP_STATELESS=true` to serve every request with a fresh server
+//! and no session, so instances can sit behind a load balancer without
+//! sticky sessions; server-initiated features are then off. See the
+//! `sessions` module.
+//!
+//! Stateful sessions are stored in `MCP_SESSION_STORE` (default `memory`;
+//! a database file path to keep them across restarts) and restored when a
+//! client reconnects with its `Mcp-Session-Id` after a restart. See the
+//! `store` module.
+//!
+//! Clients whose SSE stream drops can reconnect with `Last-Event-ID` and have
+//! what they missed replayed from the session's event log, which keeps
+//! `MCP_EVENT_LOG_SIZE` events (default 256) for `MCP_EVENT_LOG_TTL_SECS`
+//! (default 300). See the `events` module.
+//!
+//! Set `MCP_LOG_FORMAT=json` for structured JSON logs.
 //!
 //! ## Documentation
 //!
@@ -18,34 +73,82 @@
 
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
-use axum::{routing::get, Router};
-use mcp_rust_starter::McpServer;
-use rmcp::transport::{
-    streamable_http_server::session::local::LocalSessionManager, StreamableHttpServerConfig,
-    StreamableHttpService,
+use axum::{
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use mcp_rust_starter::{
+    events::Retention,
+    health::{self, Health},
+    icons, limits,
+    metrics::Metrics,
+    plugin, reload,
+    sessions::{self, PersistentSessionManager},
+    store::Sessions,
+    telemetry::{self, LogFormat},
+    ServerState,
 };
 use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 #[tokio::main]
 async fn main() {
-    // Initialize logging
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .init();
+    // Initialize logging and trace export
+    let format = LogFormat::from_env().unwrap_or_else(|e| {
+        // Logging is not set up yet, so report straight to stderr
+        eprintln!("Invalid log configuration: {e}");
+        std::process::exit(1);
+    });
+    let _telemetry = telemetry::init(format, std::io::stdout);
 
     let addr: SocketAddr = "0.0.0.0:3000".parse().expect("Invalid address");
     tracing::info!("MCP Rust Starter HTTP server starting on {}", addr);
 
+    // Serve icons by URL instead of inlining them, if configured
+    if let Ok(base_url) = std::env::var("MCP_ICON_BASE_URL") {
+        tracing::info!("Serving icons from {}", base_url);
+        icons::serve_from(base_url);
+    }
+
     // Configure the MCP HTTP service
-    let config = StreamableHttpServerConfig::default();
-    let session_manager = Arc::new(LocalSessionManager::default());
+    let mode = sessions::Mode::from_env().unwrap_or_else(|e| {
+        tracing::error!("Invalid session configuration: {}", e);
+        std::process::exit(1);
+    });
+    if mode == sessions::Mode::Stateless {
+        tracing::info!("Serving statelessly: no sessions, no server-initiated requests");
+    }
+    let config = mode.config();
+    let streams = config.cancellation_token.clone();
+    let state = state_from_env(mode);
+    plugin::watch_from_env(&state);
+    reload::watch_from_env(&state);
+    let health = state.health.clone();
+    let drain_period = drain_from_env();
+    let limited_state = state.clone();
+    let retention = Retention::from_env().unwrap_or_else(|e| {
+        tracing::error!("Invalid event log configuration: {}", e);
+        std::process::exit(1);
+    });
+    let pruned = state.sessions.prune().await;
+    if pruned > 0 {
+        tracing::info!(pruned, "Forgot expired sessions");
+    }
+    let session_manager =
+        Arc::new(PersistentSessionManager::new(state.clone()).with_retention(retention));
+    let app_state = AppState {
+        metrics: state.metrics.clone(),
+        sessions: session_manager.clone(),
+    };
 
     // Create the MCP service that spawns a new server instance per session
-    let mcp_service =
-        StreamableHttpService::new(move || Ok(McpServer::new()), session_manager, config);
+    // (or per request when stateless), all sharing the same process state
+    let mcp_service = sessions::service(session_manager, config);
 
     // Build the router with health check and MCP endpoint
     let cors = CorsLayer::new()
@@ -55,19 +158,142 @@ async fn main() {
 
     let app = Router::new()
         .route("/health", get(health_check))
-        .nest_service("/mcp", mcp_service)
-        .layer(cors);
+        .merge(health::routes(health.clone()))
+        .route("/icons/{file}", get(icon))
+        .route("/metrics", get(metrics))
+        .merge(Router::new().nest_service("/mcp", mcp_service).layer(
+            middleware::from_fn_with_state(limited_state, limits::enforce),
+        ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            track_responses,
+        ))
+        .layer(cors)
+        .with_state(app_state);
 
     tracing::info!("Server ready at http://{}/mcp", addr);
-    tracing::info!("Health check at http://{}/health", addr);
+    tracing::info!("Readiness at http://{}/health/ready", addr);
+    tracing::info!("Metrics at http://{}/metrics", addr);
 
     // Start the server
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .expect("Failed to bind");
-    axum::serve(listener, app).await.expect("Server error");
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            drain(&health, drain_period).await;
+            // End long-lived SSE streams so graceful shutdown can complete
+            streams.cancel();
+        })
+        .await
+        .expect("Server error");
+    tracing::info!("Server shut down");
 }
 
+/// The shared server state, configured from the environment. Exits on
+/// invalid configuration.
+fn state_from_env(mode: sessions::Mode) -> ServerState {
+    let mut state = ServerState::from_env().unwrap_or_else(|e| {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    });
+    // Stateless requests have no session to store
+    if mode == sessions::Mode::Stateful {
+        let sessions = Sessions::from_env().unwrap_or_else(|e| {
+            tracing::error!("Invalid session store configuration: {}", e);
+            std::process::exit(1);
+        });
+        state.sessions = Arc::new(sessions);
+        // Check the store just configured
+        state.health = Arc::new(Health::for_state(&state));
+    }
+    state
+}
+
+/// Resolves on Ctrl+C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+}
+
+/// Read `MCP_DRAIN_SECONDS`. Exits on an invalid value.
+fn drain_from_env() -> Duration {
+    let seconds = std::env::var("MCP_DRAIN_SECONDS")
+        .map_or(Ok(5), |value| value.parse())
+        .unwrap_or_else(|e| {
+            tracing::error!("Invalid MCP_DRAIN_SECONDS: {}", e);
+            std::process::exit(1);
+        });
+    Duration::from_secs(seconds)
+}
+
+/// Report `draining` on `/health/ready` for `period`.
+async fn drain(health: &Health, period: Duration) {
+    tracing::info!("Draining for {}s before shutting down", period.as_secs());
+    health.start_draining();
+    tokio::time::sleep(period).await;
+}
+
+/// State available to the HTTP routes around the MCP service.
+#[derive(Clone)]
+struct AppState {
+    metrics: Arc<Metrics>,
+    sessions: Arc<PersistentSessionManager>,
+}
+
+/// Serves Prometheus metrics, refreshing the active session count first.
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let sessions = state.sessions.live().await;
+    state.metrics.set_active_sessions(sessions);
+    mcp_rust_starter::metrics::handler(State(state.metrics)).await
+}
+
+/// Counts every HTTP response by method and status code.
+async fn track_responses(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let response = next.run(request).await;
+    state
+        .metrics
+        .observe_http_response(method.as_str(), response.status().as_u16());
+    response
+}
+
+/// Legacy liveness check; prefer `/health/live` and `/health/ready`.
 async fn health_check() -> &'static str {
     "OK"
 }
+
+/// Serves an embedded icon asset by file name.
+async fn icon(Path(file): Path<String>) -> Response {
+    icons::file(&file).map_or_else(
+        || StatusCode::NOT_FOUND.into_response(),
+        |asset| {
+            (
+                [
+                    (header::CONTENT_TYPE, asset.mime_type),
+                    (header::CACHE_CONTROL, "public, max-age=86400"),
+                ],
+                asset.bytes,
+            )
+                .into_response()
+        },
+    )
+}
@@ -9,27 +9,89 @@
 //! cargo run --bin mcp-rust-starter-stdio
 //! ```
 //!
+//! Set `MCP_METRICS_ADDR` (e.g. `127.0.0.1:9464`) to expose Prometheus
+//! metrics at `/metrics` on a side port.
+//!
+//! Set `OTEL_EXPORTER_OTLP_ENDPOINT` (e.g. `http://localhost:4318`) to export
+//! a trace span per MCP request to an OpenTelemetry collector.
+//!
+//! Tool calls are rate limited and capped in concurrency as configured by
+//! the `MCP_RATE_LIMIT_*` variables; rejected calls return a tool error.
+//!
+//! Set `MCP_POLICY` to deny or require confirmation for tools by their
+//! annotations (e.g. `deny destructive unauthenticated`), and `MCP_OFFLINE=true`
+//! to refuse open-world tools; see the `policy` module.
+//!
+//! Set `MCP_AUDIT_LOG` to a file path to keep an append-only JSON Lines
+//! audit log of every tool call; read it with `mcp-rust-starter-audit`.
+//!
+//! Set `MCP_TOOL_MANIFEST` to a TOML or YAML file to add declarative tools,
+//! prompts and resources; it is watched and reloaded when it changes. See the
+//! `manifest` and `reload` modules.
+//!
+//! Set `MCP_RESOURCE_DIR` to a directory to expose its files as `file://`
+//! resources; see the `files` module for include/exclude globs. Resources
+//! over `MCP_RESOURCE_MAX_INLINE_BYTES` (1 MiB) are returned as links.
+//!
+//! Set `MCP_PLUGIN_DIR` to a directory of WebAssembly plugin tools; it is
+//! watched for plugins being added, changed and removed. See the `plugin` module.
+//!
+//! Set `MCP_LOG_FORMAT=json` for structured JSON logs.
+//!
 //! ## Documentation
 //!
 //! - [MCP Transports](https://modelcontextprotocol.io/docs/develop/transports#stdio)
 //! - [rmcp SDK](https://github.com/anthropics/rust-mcp-sdk)
 
-use mcp_rust_starter::McpServer;
+use std::net::SocketAddr;
+
+use mcp_rust_starter::{
+    metrics, plugin, reload,
+    telemetry::{self, LogFormat},
+    McpServer, ServerState,
+};
 use rmcp::ServiceExt;
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 #[tokio::main]
 async fn main() {
-    // Initialize logging to stderr (don't interfere with stdio protocol)
-    tracing_subscriber::registry()
-        .with(fmt::layer().with_writer(std::io::stderr))
-        .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .init();
+    // Initialize logging to stderr (don't interfere with stdio protocol) and trace export
+    let format = LogFormat::from_env().unwrap_or_else(|e| {
+        // Logging is not set up yet, so report straight to stderr
+        eprintln!("Invalid log configuration: {e}");
+        std::process::exit(1);
+    });
+    let _telemetry = telemetry::init(format, std::io::stderr);
 
     tracing::info!("MCP Rust Starter running on stdio");
 
+    let state = ServerState::from_env().unwrap_or_else(|e| {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    });
+    plugin::watch_from_env(&state);
+    reload::watch_from_env(&state);
+
+    // Optionally expose metrics on a side port
+    if let Ok(addr) = std::env::var("MCP_METRICS_ADDR") {
+        match addr.parse::<SocketAddr>() {
+            Ok(addr) => {
+                tracing::info!("Metrics at http://{}/metrics", addr);
+                let metrics = state.metrics.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = metrics::serve(addr, metrics).await {
+                        tracing::error!("Metrics server error: {:?}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                tracing::error!("Invalid MCP_METRICS_ADDR '{}': {}", addr, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Create the server and serve via stdio
-    let server = McpServer::new();
+    let server = telemetry::Traced::new(McpServer::with_state(state.clone()));
 
     // The serve_stdio method handles all the stdio transport details
     let service = server.serve(rmcp::transport::stdio()).await;
@@ -37,6 +99,8 @@ async fn main() {
     match service {
         Ok(running) => {
             tracing::info!("Server started successfully");
+            // A stdio process serves exactly one session
+            state.metrics.set_active_sessions(1);
             // Wait for the server to complete
             if let Err(e) = running.waiting().await {
                 tracing::error!("Server error: {:?}", e);
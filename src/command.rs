@@ -0,0 +1,383 @@
+//! # MCP Rust Starter - Command Backend
+//!
+//! Runs a configured local executable for a `type = "command"` manifest tool.
+//!
+//! - **Arguments** are rendered from the validated JSON input. An argument
+//!   that is exactly one `{{field}}` placeholder becomes one `argv` entry
+//!   per element if the field is an array, and is dropped if the field is
+//!   missing or null. Nothing goes through a shell.
+//! - **Options**: a value that would begin an argument with `-` is refused,
+//!   so input cannot pass the executable an option such as
+//!   `--output=/etc/passwd`, unless its field is listed in `allow_options`
+//!   or it comes after a literal `--` in `args`.
+//! - **Argument values are not sandboxed**: only the working directory is
+//!   confined. A path given as an argument can name any file the server's
+//!   user can reach, so constrain such fields with a `pattern` in the input
+//!   schema.
+//! - **Environment** is cleared apart from `PATH` and the tool's own `env`.
+//! - **Working directory** (`working_dir`, which may use placeholders) must be
+//!   inside one of the allowed directories; relative paths are resolved
+//!   against the first of them. If the client declared roots, it must be
+//!   inside one of those too (see the `roots` module).
+//! - **Output** on stdout and stderr is kept up to `max_output_bytes` each;
+//!   the rest is read and discarded, and `truncated` is set.
+//! - **Progress**: if the caller sent a progress token, every stdout line is
+//!   also sent as a progress notification.
+//! - **Timeout**: the tool's `timeout_ms`; the process is killed when the
+//!   call is dropped.
+//!
+//! The result's structured content is `{ exitCode, stdout, stderr, truncated }`;
+//! a non-zero exit is a `command_failed` tool error with the same fields.
+//!
+//! ## Configuration
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_COMMAND_DIRS` | Directories commands may run in, separated like `PATH` | the server's working directory |
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    process::Stdio,
+};
+
+use rmcp::{
+    model::{CallToolResult, Content, JsonObject, ProgressNotificationParam, ProgressToken},
+    Peer, RoleServer,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{
+    manifest::render,
+    roots::Roots,
+    tools::{ToolError, ToolErrorCode},
+};
+
+/// Default cap on captured stdout and on captured stderr: 1 MiB each.
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Longest stdout line sent as a progress message; longer lines are cut.
+const MAX_PROGRESS_LINE: usize = 1024;
+
+/// How to run a command tool, as declared in the manifest.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommandSpec {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Fields whose values may begin an argument with `-`.
+    #[serde(default)]
+    pub allow_options: BTreeSet<String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+}
+
+const fn default_max_output_bytes() -> usize {
+    DEFAULT_MAX_OUTPUT_BYTES
+}
+
+impl CommandSpec {
+    /// Resolve a fixed (placeholder-free) working directory now, so a
+    /// mistake is reported at startup rather than on the first call.
+    pub(crate) fn check(&self, sandbox: &Sandbox) -> Result<(), String> {
+        match &self.working_dir {
+            Some(dir) if !dir.contains("{{") => sandbox
+                .working_dir(Some(dir), &Roots::unrestricted())
+                .map(|_| ())
+                .map_err(|e| e.message),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Structured content of a command tool result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandOutput {
+    /// Exit status, or `None` if the process was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether stdout or stderr was longer than `max_output_bytes`.
+    pub truncated: bool,
+}
+
+/// The directories commands are allowed to run in.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    allowed_dirs: Vec<PathBuf>,
+}
+
+impl Default for Sandbox {
+    /// Only the server's working directory.
+    fn default() -> Self {
+        Self {
+            allowed_dirs: std::env::current_dir().into_iter().collect(),
+        }
+    }
+}
+
+impl Sandbox {
+    /// Allow commands to run in `dirs` and their subdirectories.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first directory that does not exist.
+    pub fn new(dirs: impl IntoIterator<Item = PathBuf>) -> Result<Self, String> {
+        let allowed_dirs = dirs
+            .into_iter()
+            .map(|dir| {
+                dir.canonicalize()
+                    .map_err(|e| format!("allowed directory {}: {e}", dir.display()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if allowed_dirs.is_empty() {
+            return Err("no allowed directories".into());
+        }
+        Ok(Self { allowed_dirs })
+    }
+
+    /// Read `MCP_COMMAND_DIRS`, falling back to [`Sandbox::default`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first directory that does not exist.
+    pub fn from_env() -> Result<Self, String> {
+        std::env::var_os("MCP_COMMAND_DIRS")
+            .filter(|dirs| !dirs.is_empty())
+            .map_or_else(
+                || Ok(Self::default()),
+                |dirs| Self::new(std::env::split_paths(&dirs)),
+            )
+    }
+
+    /// Resolve `dir` against the first allowed directory and check it is
+    /// inside one of them, and inside one of the client's `roots`.
+    fn working_dir(&self, dir: Option<&str>, roots: &Roots) -> Result<PathBuf, ToolError> {
+        let base = &self.allowed_dirs[0];
+        let shown = dir.unwrap_or(".");
+        let denied = |reason: &str| {
+            ToolError::new(
+                ToolErrorCode::PolicyDenied,
+                format!("Working directory '{shown}' is not in {reason}"),
+            )
+            .with_detail("workingDir", shown)
+        };
+        // Canonicalizing resolves `..` and symlinks before the prefix check
+        let resolved = match dir {
+            Some(dir) => base
+                .join(dir)
+                .canonicalize()
+                .map_err(|_| denied("an allowed directory"))?,
+            None => base.clone(),
+        };
+        if !self
+            .allowed_dirs
+            .iter()
+            .any(|allowed| resolved.starts_with(allowed))
+        {
+            return Err(denied("an allowed directory"));
+        }
+        if !roots.allows(&resolved) {
+            return Err(denied("the client's roots"));
+        }
+        Ok(resolved)
+    }
+}
+
+/// Where to send stdout lines as progress notifications.
+pub struct Progress {
+    pub peer: Peer<RoleServer>,
+    pub token: ProgressToken,
+}
+
+/// Render the `argv` of `spec` from `arguments`.
+///
+/// # Errors
+///
+/// `invalid_input` if a value would begin an argument with `-` and its
+/// field is not in `allow_options`.
+pub fn argv(spec: &CommandSpec, arguments: &JsonObject) -> Result<Vec<String>, ToolError> {
+    let mut argv = Vec::with_capacity(spec.args.len());
+    let mut options_ended = false;
+    for arg in &spec.args {
+        let rendered = match placeholder(arg).map(|field| arguments.get(field)) {
+            Some(None | Some(Value::Null)) => vec![],
+            Some(Some(Value::Array(items))) => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                })
+                .collect(),
+            _ => vec![render(arg, arguments)],
+        };
+        options_ended |= arg == "--";
+        if let (Some(field), false) = (leading_field(arg), options_ended) {
+            let option = rendered.iter().find(|value| value.starts_with('-'));
+            if let (Some(option), false) = (option, spec.allow_options.contains(field)) {
+                return Err(ToolError::new(
+                    ToolErrorCode::InvalidInput,
+                    format!("Argument '{field}' may not start with '-': '{option}'"),
+                )
+                .with_detail("field", field));
+            }
+        }
+        argv.extend(rendered);
+    }
+    Ok(argv)
+}
+
+/// The field name if `arg` is a single `{{field}}` placeholder.
+fn placeholder(arg: &str) -> Option<&str> {
+    let field = arg.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    (!field.contains("{{") && !field.contains("}}")).then(|| field.trim())
+}
+
+/// The field of the placeholder `arg` starts with, whose value therefore
+/// starts the rendered argument.
+fn leading_field(arg: &str) -> Option<&str> {
+    let (field, _) = arg.strip_prefix("{{")?.split_once("}}")?;
+    Some(field.trim())
+}
+
+/// Run the command declared by `spec` with already-validated `arguments`.
+///
+/// # Errors
+///
+/// `invalid_input` if a value would pass the command an option it does not
+/// allow, `unavailable` if the command cannot be started, `policy_denied` if the
+/// working directory is not allowed or outside `roots`, `command_failed` on
+/// a non-zero exit.
+///
+/// # Panics
+///
+/// Never in practice: stdout and stderr are always piped.
+pub async fn run(
+    spec: &CommandSpec,
+    arguments: &JsonObject,
+    sandbox: &Sandbox,
+    roots: &Roots,
+    progress: Option<Progress>,
+) -> Result<CallToolResult, ToolError> {
+    let working_dir = spec
+        .working_dir
+        .as_deref()
+        .map(|dir| render(dir, arguments));
+    let working_dir = sandbox.working_dir(working_dir.as_deref(), roots)?;
+
+    let mut command = tokio::process::Command::new(&spec.command);
+    command
+        .args(argv(spec, arguments)?)
+        .current_dir(&working_dir)
+        .env_clear()
+        .envs(std::env::var_os("PATH").map(|path| ("PATH", path)))
+        .envs(&spec.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Dropping the call on timeout kills the process
+        .kill_on_drop(true);
+    let mut child = command
+        .spawn()
+        .map_err(|e| ToolError::unavailable(format!("Failed to run '{}': {e}", spec.command)))?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let (stdout, stderr, status) = tokio::join!(
+        capture(stdout, spec.max_output_bytes, progress),
+        capture(stderr, spec.max_output_bytes, None),
+        child.wait()
+    );
+    let (stdout, stdout_truncated) =
+        stdout.map_err(|e| ToolError::internal(format!("Failed to read stdout: {e}")))?;
+    let (stderr, stderr_truncated) =
+        stderr.map_err(|e| ToolError::internal(format!("Failed to read stderr: {e}")))?;
+    let status = status
+        .map_err(|e| ToolError::internal(format!("Failed to wait for '{}': {e}", spec.command)))?;
+
+    let output = CommandOutput {
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        truncated: stdout_truncated || stderr_truncated,
+    };
+    if status.success() {
+        let mut result = CallToolResult::success(vec![Content::text(output.stdout.clone())]);
+        result.structured_content = Some(serde_json::to_value(&output)?);
+        Ok(result)
+    } else {
+        Err(ToolError::new(
+            ToolErrorCode::CommandFailed,
+            format!(
+                "'{}' exited with {status}: {}",
+                spec.command,
+                output.stderr.trim()
+            ),
+        )
+        .with_detail("exitCode", output.exit_code)
+        .with_detail("stdout", output.stdout)
+        .with_detail("stderr", output.stderr)
+        .with_detail("truncated", output.truncated))
+    }
+}
+
+/// Read `stream` to the end, keeping the first `limit` bytes and sending
+/// each line as a progress notification if asked to.
+async fn capture(
+    mut stream: impl AsyncRead + Unpin,
+    limit: usize,
+    progress: Option<Progress>,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut line = Vec::new();
+    let mut lines = 0_u32;
+    let mut chunk = vec![0; 8192];
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &chunk[..read];
+        let room = limit - kept.len();
+        kept.extend_from_slice(&chunk[..read.min(room)]);
+        truncated |= read > room;
+
+        let Some(progress) = &progress else {
+            continue;
+        };
+        for &byte in chunk {
+            if byte == b'\n' {
+                lines += 1;
+                notify(progress, lines, &line).await;
+                line.clear();
+            } else if line.len() < MAX_PROGRESS_LINE {
+                line.push(byte);
+            }
+        }
+    }
+    if let Some(progress) = &progress {
+        if !line.is_empty() {
+            notify(progress, lines + 1, &line).await;
+        }
+    }
+    Ok((kept, truncated))
+}
+
+async fn notify(progress: &Progress, lines: u32, line: &[u8]) {
+    let params = ProgressNotificationParam::new(progress.token.clone(), f64::from(lines))
+        .with_message(String::from_utf8_lossy(line).trim_end_matches('\r'));
+    // Progress is best-effort; a client that went away must not fail the call
+    if let Err(e) = progress.peer.notify_progress(params).await {
+        tracing::debug!(error = %e, "Failed to send command progress");
+    }
+}
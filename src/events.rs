@@ -0,0 +1,349 @@
+//! # MCP Rust Starter - Resumable Streams
+//!
+//! Every message a stateful HTTP session streams to its client is numbered
+//! and kept in the session's [`EventLog`], so a client whose SSE connection
+//! drops (say, halfway through the progress of a `long_task`) can reconnect
+//! with a `GET` carrying the `Last-Event-ID` it last saw and have the
+//! messages it missed replayed, followed by the rest of the stream if it is
+//! still running.
+//!
+//! Event IDs are `<stream>-<seq>`: the stream (one per POST response, plus
+//! the session's standalone GET stream) and the message's position in it.
+//! Each stream begins with an event `<stream>-0` carrying no message, so a
+//! client can resume even before the first message arrives. The log keeps
+//! the latest `MCP_EVENT_LOG_SIZE` events of each session, none older than
+//! `MCP_EVENT_LOG_TTL_SECS`; a client resuming from an event that has since
+//! been dropped gets only the events still kept. A `Last-Event-ID` the log
+//! does not know opens the standalone stream instead.
+//!
+//! The log lives in memory: streams are not resumable after a restart or on
+//! another replica, even where the session itself is restored.
+//!
+//! ## Configuration
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_EVENT_LOG_SIZE` | Events kept per session for replay | `256` |
+//! | `MCP_EVENT_LOG_TTL_SECS` | How long an event is kept for replay | `300` |
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{Stream, StreamExt};
+use rmcp::transport::streamable_http_server::session::ServerSseMessage;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Default number of events kept per session.
+pub const DEFAULT_MAX_EVENTS: usize = 256;
+
+/// Default time an event is kept.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_mins(5);
+
+/// Messages buffered for a connected client before it is cut off, to
+/// reconnect and catch up from the log.
+const CAPACITY: usize = 64;
+
+/// The messages sent to one connected client.
+pub type EventStream = ReceiverStream<ServerSseMessage>;
+
+/// How much of a session's stream is kept for replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// The most events kept per session; older ones are dropped first.
+    pub max_events: usize,
+    /// How long an event is kept.
+    pub max_age: Duration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_events: DEFAULT_MAX_EVENTS,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+impl Retention {
+    /// Read `MCP_EVENT_LOG_SIZE` and `MCP_EVENT_LOG_TTL_SECS`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of a value that is not a whole number.
+    pub fn from_env() -> Result<Self, String> {
+        let max_events = std::env::var("MCP_EVENT_LOG_SIZE")
+            .map_or(Ok(DEFAULT_MAX_EVENTS), |value| value.parse())
+            .map_err(|e| format!("MCP_EVENT_LOG_SIZE: {e}"))?;
+        let max_age = std::env::var("MCP_EVENT_LOG_TTL_SECS")
+            .map_or(Ok(DEFAULT_MAX_AGE), |value| {
+                value.parse().map(Duration::from_secs)
+            })
+            .map_err(|e| format!("MCP_EVENT_LOG_TTL_SECS: {e}"))?;
+        Ok(Self {
+            max_events,
+            max_age,
+        })
+    }
+}
+
+/// Where an event sits in a session's streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventId {
+    pub stream: u64,
+    pub seq: u64,
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.stream, self.seq)
+    }
+}
+
+impl FromStr for EventId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (stream, seq) = s
+            .split_once('-')
+            .ok_or_else(|| format!("expected <stream>-<seq>, got '{s}'"))?;
+        Ok(Self {
+            stream: stream
+                .parse()
+                .map_err(|e| format!("stream of '{s}': {e}"))?,
+            seq: seq.parse().map_err(|e| format!("seq of '{s}': {e}"))?,
+        })
+    }
+}
+
+/// One session's streams and the events kept from them.
+pub struct EventLog {
+    retention: Retention,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_stream: u64,
+    /// Kept events of every stream, oldest first.
+    events: VecDeque<Event>,
+    streams: HashMap<u64, StreamState>,
+    /// The stream of the session's standalone GET, if one was opened.
+    standalone: Option<u64>,
+}
+
+struct Event {
+    id: EventId,
+    at: Instant,
+    message: ServerSseMessage,
+}
+
+#[derive(Default)]
+struct StreamState {
+    /// The seq of the latest message, 0 before the first.
+    last_seq: u64,
+    /// The connected client, if any.
+    subscriber: Option<mpsc::Sender<ServerSseMessage>>,
+    /// Whether the stream has ended.
+    done: bool,
+}
+
+impl EventLog {
+    /// An empty log keeping events as long as `retention` allows.
+    #[must_use]
+    pub fn new(retention: Retention) -> Self {
+        Self {
+            retention,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Number and log the messages of a new stream (the response to a
+    /// POST), returning them for the client that opened it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub fn attach<S>(self: &Arc<Self>, source: S) -> EventStream
+    where
+        S: Stream<Item = ServerSseMessage> + Send + 'static,
+    {
+        self.start(source, false)
+    }
+
+    /// Like [`attach`](Self::attach), for the session's standalone stream,
+    /// which later `GET`s without a `Last-Event-ID` rejoin while it runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub fn attach_standalone<S>(self: &Arc<Self>, source: S) -> EventStream
+    where
+        S: Stream<Item = ServerSseMessage> + Send + 'static,
+    {
+        self.start(source, true)
+    }
+
+    fn start<S>(self: &Arc<Self>, source: S, standalone: bool) -> EventStream
+    where
+        S: Stream<Item = ServerSseMessage> + Send + 'static,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        let stream = inner.next_stream;
+        inner.next_stream += 1;
+        inner.streams.insert(stream, StreamState::default());
+        if standalone {
+            inner.standalone = Some(stream);
+        }
+        let events = self
+            .subscribe(&mut inner, stream, 0, true)
+            .expect("a stream just started");
+        drop(inner);
+        tokio::spawn(self.clone().pump(stream, source));
+        events
+    }
+
+    /// Take over the standalone stream from wherever it has reached, unless
+    /// there is none running.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn rejoin_standalone(&self) -> Option<EventStream> {
+        let mut inner = self.inner.lock().unwrap();
+        let stream = inner.standalone?;
+        let last_seq = inner.streams.get(&stream).filter(|s| !s.done)?.last_seq;
+        let events = self.subscribe(&mut inner, stream, last_seq, true);
+        drop(inner);
+        events
+    }
+
+    /// The events of the stream of `last_event_id` that came after it,
+    /// followed by the rest of the stream if it is still running. `None`
+    /// if the ID is not one of this log's streams.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn resume(&self, last_event_id: &str) -> Option<EventStream> {
+        let id: EventId = last_event_id.parse().ok()?;
+        let mut inner = self.inner.lock().unwrap();
+        self.subscribe(&mut inner, id.stream, id.seq, false)
+    }
+
+    /// Connect a client to `stream`, first sending it the kept events after
+    /// `after` (preceded, if `prime`, by an event marking where it starts).
+    /// Any client already connected to the stream is cut off.
+    fn subscribe(
+        &self,
+        inner: &mut Inner,
+        stream: u64,
+        after: u64,
+        prime: bool,
+    ) -> Option<EventStream> {
+        self.evict(inner);
+        let state = inner.streams.get(&stream)?;
+        let replay: Vec<_> = inner
+            .events
+            .iter()
+            .filter(|event| event.id.stream == stream && event.id.seq > after)
+            .map(|event| event.message.clone())
+            .collect();
+        let (tx, rx) = mpsc::channel(replay.len() + usize::from(prime) + CAPACITY);
+        if prime {
+            let id = EventId { stream, seq: after };
+            let _ = tx.try_send(ServerSseMessage {
+                event_id: Some(id.to_string()),
+                message: None,
+                retry: None,
+            });
+        }
+        for message in replay {
+            let _ = tx.try_send(message);
+        }
+        let done = state.done;
+        let state = inner.streams.get_mut(&stream)?;
+        // A finished stream ends once replayed, by dropping its sender here
+        state.subscriber = (!done).then_some(tx);
+        Some(ReceiverStream::new(rx))
+    }
+
+    /// Number, log and forward every message of `source` until it ends.
+    async fn pump<S>(self: Arc<Self>, stream: u64, source: S)
+    where
+        S: Stream<Item = ServerSseMessage> + Send + 'static,
+    {
+        let mut source = std::pin::pin!(source);
+        while let Some(message) = source.next().await {
+            // The transport's own priming events; the log sends its own
+            if message.message.is_none() {
+                continue;
+            }
+            self.record(stream, message);
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(state) = inner.streams.get_mut(&stream) {
+            state.done = true;
+            state.subscriber = None;
+        }
+        self.evict(&mut inner);
+        drop(inner);
+    }
+
+    fn record(&self, stream: u64, message: ServerSseMessage) {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(state) = inner.streams.get_mut(&stream) else {
+            return;
+        };
+        state.last_seq += 1;
+        let id = EventId {
+            stream,
+            seq: state.last_seq,
+        };
+        let message = ServerSseMessage {
+            event_id: Some(id.to_string()),
+            ..message
+        };
+        if let Some(subscriber) = &state.subscriber {
+            match subscriber.try_send(message.clone()) {
+                Ok(()) => {}
+                // Disconnected, or too slow: either way it resumes from the log
+                Err(TrySendError::Closed(_) | TrySendError::Full(_)) => state.subscriber = None,
+            }
+        }
+        inner.events.push_back(Event {
+            id,
+            at: Instant::now(),
+            message,
+        });
+        self.evict(&mut inner);
+        drop(inner);
+    }
+
+    /// Drop events beyond the retention limits, then finished streams with
+    /// nothing left to replay.
+    fn evict(&self, inner: &mut Inner) {
+        let now = Instant::now();
+        while inner.events.len() > self.retention.max_events
+            || inner
+                .events
+                .front()
+                .is_some_and(|event| now.duration_since(event.at) >= self.retention.max_age)
+        {
+            inner.events.pop_front();
+        }
+        let Inner {
+            events, streams, ..
+        } = inner;
+        streams.retain(|stream, state| {
+            !state.done || events.iter().any(|event| event.id.stream == *stream)
+        });
+    }
+}
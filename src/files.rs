@@ -0,0 +1,279 @@
+//! # MCP Rust Starter - File Resources
+//!
+//! Exposes a directory tree as `file://` resources, so clients can browse
+//! and read real files instead of the hard-coded examples.
+//!
+//! - **Listing** walks the tree in path order and returns one page of files
+//!   per `resources/list`; the cursor is the last path of the previous page.
+//! - **Filtering**: only regular files matching an include glob and no
+//!   exclude glob are listed or readable. Globs match the path relative to
+//!   the directory, with `/` separators (`**/*.md`, `target/**`).
+//! - **Contents** are text if the file is UTF-8 with a textual MIME type, and
+//!   a base64 blob otherwise. MIME types are guessed from the extension,
+//!   and each file is listed with an icon for its kind (text, data, other).
+//!   Files over the inline limit are returned as a link to their own URI
+//!   without being read (see [`crate::resources::contents`]).
+//! - **Confinement**: a URI that resolves outside the directory, through
+//!   `..` or a symlink, is refused. So is one outside the client's roots,
+//!   if it declared any; files outside them are not listed either.
+//!
+//! ## Configuration
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_RESOURCE_DIR` | Directory to expose | disabled |
+//! | `MCP_RESOURCE_INCLUDE` | Comma-separated globs of files to expose | `**` |
+//! | `MCP_RESOURCE_EXCLUDE` | Comma-separated globs of files to hide | `**/.*,**/.*/**` (hidden files) |
+//! | `MCP_RESOURCE_PAGE_SIZE` | Files per `resources/list` page | 100 |
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rmcp::{
+    model::{RawResource, ReadResourceResult, Resource},
+    ErrorData as McpError,
+};
+use url::Url;
+use walkdir::WalkDir;
+
+use crate::{
+    icons,
+    resources::{self, InlineLimit},
+    roots::Roots,
+};
+
+/// Metrics label for every file read.
+pub const LABEL: &str = "file://{path}";
+
+/// Default number of files per listing page.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Files exposed unless `MCP_RESOURCE_INCLUDE` says otherwise.
+pub const DEFAULT_INCLUDE: &str = "**";
+
+/// Files hidden unless `MCP_RESOURCE_EXCLUDE` says otherwise: dotfiles and
+/// anything in a dot-directory, like `.env` and `.git/`.
+pub const DEFAULT_EXCLUDE: &str = "**/.*,**/.*/**";
+
+/// A directory tree served as `file://` resources (disabled by default).
+#[derive(Default)]
+pub struct FileResources {
+    tree: Option<Tree>,
+}
+
+struct Tree {
+    root: PathBuf,
+    include: GlobSet,
+    exclude: GlobSet,
+    page_size: usize,
+}
+
+impl FileResources {
+    /// Expose the files under `root` that match one of `include` and none of
+    /// `exclude`, `page_size` at a time.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of a missing root or an invalid glob.
+    pub fn new(
+        root: &Path,
+        include: &[&str],
+        exclude: &[&str],
+        page_size: usize,
+    ) -> Result<Self, String> {
+        let root = root
+            .canonicalize()
+            .map_err(|e| format!("{}: {e}", root.display()))?;
+        if !root.is_dir() {
+            return Err(format!("{}: not a directory", root.display()));
+        }
+        if page_size == 0 {
+            return Err("page size must be at least 1".into());
+        }
+        Ok(Self {
+            tree: Some(Tree {
+                root,
+                include: globs(include)?,
+                exclude: globs(exclude)?,
+                page_size,
+            }),
+        })
+    }
+
+    /// Read `MCP_RESOURCE_DIR` and the variables that go with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invalid variable.
+    pub fn from_env() -> Result<Self, String> {
+        let Some(root) = std::env::var_os("MCP_RESOURCE_DIR").filter(|dir| !dir.is_empty()) else {
+            return Ok(Self::default());
+        };
+        let include =
+            std::env::var("MCP_RESOURCE_INCLUDE").unwrap_or_else(|_| DEFAULT_INCLUDE.to_string());
+        let exclude =
+            std::env::var("MCP_RESOURCE_EXCLUDE").unwrap_or_else(|_| DEFAULT_EXCLUDE.to_string());
+        let page_size = std::env::var("MCP_RESOURCE_PAGE_SIZE")
+            .map_or(Ok(DEFAULT_PAGE_SIZE), |value| value.parse())
+            .map_err(|e| format!("MCP_RESOURCE_PAGE_SIZE: {e}"))?;
+        Self::new(
+            Path::new(&root),
+            &split(&include),
+            &split(&exclude),
+            page_size,
+        )
+        .map_err(|e| format!("MCP_RESOURCE_DIR: {e}"))
+    }
+
+    /// Whether a directory is exposed at all.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.tree.is_some()
+    }
+
+    /// The page of files within `roots` after `cursor` (from the start if
+    /// `None`), and the cursor of the next page if there is one.
+    #[must_use]
+    pub fn list(&self, cursor: Option<&str>, roots: &Roots) -> (Vec<Resource>, Option<String>) {
+        let Some(tree) = &self.tree else {
+            return (Vec::new(), None);
+        };
+        let after = cursor.map(Path::new);
+        let mut page = Vec::new();
+        let mut last = None;
+        let files = WalkDir::new(&tree.root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file());
+        for entry in files {
+            let Ok(relative) = entry.path().strip_prefix(&tree.root) else {
+                continue;
+            };
+            if after.is_some_and(|after| relative <= after)
+                || !tree.exposes(relative)
+                || !roots.allows(entry.path())
+            {
+                continue;
+            }
+            if page.len() == tree.page_size {
+                // There is at least one more file, so another page
+                return (page, last);
+            }
+            let size = entry.metadata().ok().map(|metadata| metadata.len());
+            if let Some(resource) = resource(entry.path(), relative, size) {
+                page.push(resource);
+                last = Some(relative.to_string_lossy().into_owned());
+            }
+        }
+        (page, None)
+    }
+
+    /// Read the file at a `file://` URI, or `None` if `uri` is not one or no
+    /// directory is exposed.
+    ///
+    /// # Errors
+    ///
+    /// `invalid_params` if the URI points outside the directory or `roots`,
+    /// `resource_not_found` if it names no exposed file.
+    #[must_use]
+    pub fn read(
+        &self,
+        uri: &str,
+        roots: &Roots,
+        limit: InlineLimit,
+    ) -> Option<Result<ReadResourceResult, McpError>> {
+        let tree = self.tree.as_ref()?;
+        let url = Url::parse(uri).ok().filter(|url| url.scheme() == "file")?;
+        Some(tree.read(uri, &url, roots, limit))
+    }
+}
+
+impl Tree {
+    fn exposes(&self, relative: &Path) -> bool {
+        self.include.is_match(relative) && !self.exclude.is_match(relative)
+    }
+
+    fn read(
+        &self,
+        uri: &str,
+        url: &Url,
+        roots: &Roots,
+        limit: InlineLimit,
+    ) -> Result<ReadResourceResult, McpError> {
+        let outside = || {
+            McpError::invalid_params(
+                format!("Resource '{uri}' is outside the resource directory"),
+                None,
+            )
+        };
+        let not_found = || McpError::resource_not_found(format!("Resource not found: {uri}"), None);
+
+        let path = url.to_file_path().map_err(|()| outside())?;
+        if path.components().any(|c| c == Component::ParentDir) || !path.starts_with(&self.root) {
+            return Err(outside());
+        }
+        // Symlinks may still lead out of the directory
+        let path = path.canonicalize().map_err(|_| not_found())?;
+        let relative = path.strip_prefix(&self.root).map_err(|_| outside())?;
+        if !roots.allows(&path) {
+            return Err(McpError::invalid_params(
+                format!("Resource '{uri}' is outside the client's roots"),
+                None,
+            ));
+        }
+        if !path.is_file() || !self.exposes(relative) {
+            return Err(not_found());
+        }
+
+        let failed = |e| McpError::internal_error(format!("Failed to read {uri}: {e}"), None);
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        let size = fs::metadata(&path).map_err(failed)?.len();
+        // Large files are linked without reading them into memory
+        let contents = if limit.inlines(size) {
+            let bytes = fs::read(&path).map_err(failed)?;
+            resources::contents(uri, mime.essence_str(), &bytes, None, limit)
+        } else {
+            resources::link(uri, mime.essence_str(), size, None)
+        };
+        Ok(ReadResourceResult::new(vec![contents]))
+    }
+}
+
+/// The listing entry for the file at `path`.
+fn resource(path: &Path, relative: &Path, size: Option<u64>) -> Option<Resource> {
+    let uri = Url::from_file_path(path).ok()?;
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    Some(Resource::new(
+        RawResource {
+            uri: uri.into(),
+            name: relative.to_string_lossy().into_owned(),
+            title: None,
+            description: None,
+            mime_type: Some(mime.essence_str().to_string()),
+            size: size.and_then(|size| u32::try_from(size).ok()),
+            icons: Some(icons::for_mime_type(mime.essence_str())),
+            meta: None,
+        },
+        None,
+    ))
+}
+
+fn globs(patterns: &[&str]) -> Result<GlobSet, String> {
+    let mut set = GlobSetBuilder::new();
+    for pattern in patterns {
+        set.add(Glob::new(pattern).map_err(|e| format!("glob '{pattern}': {e}"))?);
+    }
+    set.build().map_err(|e| e.to_string())
+}
+
+fn split(patterns: &str) -> Vec<&str> {
+    patterns
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
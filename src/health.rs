@@ -0,0 +1,218 @@
+//! # MCP Rust Starter - Health
+//!
+//! Liveness and readiness reporting for orchestrators and load balancers.
+//!
+//! - `/health/live` answers as long as the process can serve HTTP at all.
+//! - `/health/ready` runs every registered dependency check and reports each
+//!   one's status and latency. It answers `503` when a check fails or while
+//!   the server is draining connections during a graceful shutdown, so new
+//!   traffic is routed elsewhere while in-flight requests finish.
+//!
+//! [`Health::for_state`] checks what can go wrong in a running server: the
+//! tool manifest failing to reload, plugins failing to load, and the session
+//! store becoming unreadable. The compiled tools, prompts and resources are
+//! in-process and cannot fail, so they are not checked; when a real backend
+//! replaces one of them, register a check that probes it.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use serde::Serialize;
+
+use crate::ServerState;
+
+/// How long a single check may take before it counts as failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+type Probe =
+    Box<dyn Fn() -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>> + Send + Sync>;
+
+struct Check {
+    name: &'static str,
+    probe: Probe,
+}
+
+/// Dependency checks plus the draining flag set during shutdown.
+pub struct Health {
+    checks: Vec<Check>,
+    draining: AtomicBool,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Health {
+    /// A health report with no dependency checks.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            checks: Vec::new(),
+            draining: AtomicBool::new(false),
+        }
+    }
+
+    /// Checks on the manifest, plugins and session store of `state`.
+    #[must_use]
+    pub fn for_state(state: &ServerState) -> Self {
+        let (reloads, plugins, sessions) = (
+            state.reloads.clone(),
+            state.plugins.clone(),
+            state.sessions.clone(),
+        );
+        Self::new()
+            .with_check("tool_manifest", move || {
+                let error = reloads.error();
+                async move { error.map_or(Ok(()), |e| Err(format!("reload failed: {e}"))) }
+            })
+            .with_check("plugins", move || {
+                let rejected = plugins.rejected();
+                async move {
+                    if rejected.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(format!("failed to load: {}", rejected.join(", ")))
+                    }
+                }
+            })
+            .with_check("session_store", move || {
+                let sessions = sessions.clone();
+                async move { sessions.check().await.map_err(|e| e.to_string()) }
+            })
+    }
+
+    /// Register a dependency check. It fails by returning an error message,
+    /// or by taking longer than two seconds.
+    #[must_use]
+    pub fn with_check<F, Fut>(mut self, name: &'static str, probe: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.checks.push(Check {
+            name,
+            probe: Box::new(move || Box::pin(probe())),
+        });
+        self
+    }
+
+    /// Report not-ready from now on, because the server is shutting down.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`start_draining`](Self::start_draining) has been called.
+    #[must_use]
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Run every check, one after the other, and summarise the results.
+    pub async fn readiness(&self) -> Readiness {
+        let mut checks = Vec::with_capacity(self.checks.len());
+        for check in &self.checks {
+            let started = Instant::now();
+            let result = tokio::time::timeout(CHECK_TIMEOUT, (check.probe)())
+                .await
+                .unwrap_or_else(|_| Err(format!("timed out after {CHECK_TIMEOUT:?}")));
+            checks.push(CheckResult {
+                name: check.name,
+                status: if result.is_ok() {
+                    CheckStatus::Pass
+                } else {
+                    CheckStatus::Fail
+                },
+                latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+                error: result.err(),
+            });
+        }
+
+        let status = if self.is_draining() {
+            ReadyStatus::Draining
+        } else if checks.iter().all(|check| check.status == CheckStatus::Pass) {
+            ReadyStatus::Ready
+        } else {
+            ReadyStatus::NotReady
+        };
+        Readiness { status, checks }
+    }
+}
+
+/// Overall readiness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadyStatus {
+    /// Every check passed.
+    Ready,
+    /// At least one check failed.
+    NotReady,
+    /// The server is shutting down and should receive no new traffic.
+    Draining,
+}
+
+/// Result of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+}
+
+/// One check's outcome, as reported by `/health/ready`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The `/health/ready` response body.
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub status: ReadyStatus,
+    pub checks: Vec<CheckResult>,
+}
+
+impl Readiness {
+    /// Whether the server should receive traffic.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.status == ReadyStatus::Ready
+    }
+}
+
+/// Axum handler for `/health/live`.
+pub async fn live() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "alive" }))
+}
+
+/// Axum handler for `/health/ready`: `200` when ready, `503` otherwise.
+pub async fn ready(State(health): State<Arc<Health>>) -> impl IntoResponse {
+    let readiness = health.readiness().await;
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+/// Routes for `/health/live` and `/health/ready`.
+pub fn routes<S>(health: Arc<Health>) -> Router<S> {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+        .with_state(health)
+}
@@ -1,71 +1,351 @@
-//! Icon constants for MCP tools
+//! Icon registry for MCP tools
 //! From Microsoft Fluent UI Emoji (MIT License)
+//!
+//! Every icon is embedded from `assets/icons` at compile time with
+//! `include_bytes!`, so a missing file is a build error. PNG dimensions are
+//! read from the image header during constant evaluation, which means a
+//! corrupt or non-PNG asset also fails the build instead of shipping with
+//! made-up `sizes`.
+//!
+//! ## Renditions
+//!
+//! A logical icon (e.g. `waving_hand`) maps to several renditions: PNGs at
+//! multiple pixel sizes, or a scalable SVG. All of them are returned in the
+//! `Vec<Icon>` so clients can pick the best fit.
+//!
+//! rmcp's `Icon` has no `theme` field, so a client could not tell a variant
+//! for dark backgrounds from the light one; only the light SVGs are shipped.
+//!
+//! ## Inline vs. URL icons
+//!
+//! By default icons are inlined as `data:` URIs. A transport that can serve
+//! static files (the HTTP binary) may call [`serve_from`] at startup so icons
+//! are advertised as `{base_url}/{file}` instead, keeping `tools/list`
+//! responses small.
 
+use std::{collections::HashMap, sync::OnceLock};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use rmcp::model::Icon;
 
-/// Create an Icon from a data URI
-fn icon(src: &str) -> Icon {
-    Icon::new(src)
-        .with_mime_type("image/png")
-        .with_sizes(vec!["256x256".to_string()])
+/// An image file embedded into the binary.
+pub struct IconAsset {
+    /// File name relative to `assets/icons`.
+    pub file: &'static str,
+    /// MIME type advertised to clients.
+    pub mime_type: &'static str,
+    /// Pixel dimensions, or `None` for scalable formats such as SVG.
+    pub dimensions: Option<(u32, u32)>,
+    /// Raw file contents.
+    pub bytes: &'static [u8],
+}
+
+impl IconAsset {
+    /// Describe an embedded PNG, reading its size from the IHDR chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics (at compile time when used in a `static`) if `bytes` is not a PNG.
+    #[must_use]
+    pub const fn png(file: &'static str, bytes: &'static [u8]) -> Self {
+        Self {
+            file,
+            mime_type: "image/png",
+            dimensions: Some(png_dimensions(bytes)),
+            bytes,
+        }
+    }
+
+    /// Describe an embedded SVG.
+    #[must_use]
+    pub const fn svg(file: &'static str, bytes: &'static [u8]) -> Self {
+        Self {
+            file,
+            mime_type: "image/svg+xml",
+            dimensions: None,
+            bytes,
+        }
+    }
+
+    /// The `sizes` entry for this asset (`WxH`, or `any` for vector images).
+    #[must_use]
+    pub fn size(&self) -> String {
+        self.dimensions
+            .map_or_else(|| "any".to_string(), |(w, h)| format!("{w}x{h}"))
+    }
+
+    /// A `data:` URI with the base64-encoded asset, computed once per process.
+    #[must_use]
+    pub fn data_uri(&self) -> &'static str {
+        static DATA_URIS: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+        DATA_URIS.get_or_init(|| {
+            REGISTRY
+                .iter()
+                .flat_map(|(_, assets)| assets.iter())
+                .map(|asset| {
+                    let uri = format!(
+                        "data:{};base64,{}",
+                        asset.mime_type,
+                        STANDARD.encode(asset.bytes)
+                    );
+                    (asset.file, uri)
+                })
+                .collect()
+        })[self.file]
+            .as_str()
+    }
+
+    /// The URL this asset is served at, if [`serve_from`] has been configured.
+    #[must_use]
+    pub fn url(&self) -> Option<String> {
+        BASE_URL.get().map(|base| format!("{base}/{}", self.file))
+    }
+
+    /// The `src` advertised for this asset: a URL when [`serve_from`] has
+    /// been configured, otherwise an inline `data:` URI.
+    #[must_use]
+    pub fn src(&self) -> String {
+        self.url().unwrap_or_else(|| self.data_uri().to_string())
+    }
+
+    /// Build the MCP icon descriptor for this asset.
+    #[must_use]
+    pub fn to_icon(&self) -> Icon {
+        Icon::new(self.src())
+            .with_mime_type(self.mime_type)
+            .with_sizes(vec![self.size()])
+    }
+}
+
+/// Read the width and height from a PNG's IHDR chunk.
+///
+/// # Panics
+///
+/// Panics if `bytes` does not start with the PNG signature and an IHDR chunk.
+#[must_use]
+pub const fn png_dimensions(bytes: &[u8]) -> (u32, u32) {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    assert!(bytes.len() >= 24, "icon asset is too short to be a PNG");
+    let mut i = 0;
+    while i < SIGNATURE.len() {
+        assert!(bytes[i] == SIGNATURE[i], "icon asset is not a PNG");
+        i += 1;
+    }
+    assert!(
+        bytes[12] == b'I' && bytes[13] == b'H' && bytes[14] == b'D' && bytes[15] == b'R',
+        "PNG icon asset does not start with an IHDR chunk"
+    );
+
+    let width = u32::from_be_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]);
+    let height = u32::from_be_bytes([bytes[20], bytes[21], bytes[22], bytes[23]]);
+    (width, height)
+}
+
+/// Base URL icons are served from, when not inlined.
+static BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// Advertise icons as `{base_url}/{file}` instead of inline `data:` URIs.
+///
+/// Must be called before any server instance is created, because tool
+/// icons are captured when the tool router is built. Returns `false` if a
+/// base URL was already configured.
+pub fn serve_from(base_url: impl Into<String>) -> bool {
+    let base_url: String = base_url.into();
+    BASE_URL
+        .set(base_url.trim_end_matches('/').to_string())
+        .is_ok()
+}
+
+/// Embed a PNG from `assets/icons`.
+macro_rules! png {
+    ($file:literal) => {
+        IconAsset::png(
+            $file,
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/icons/", $file)),
+        )
+    };
+}
+
+/// Embed an SVG from `assets/icons`.
+macro_rules! svg {
+    ($file:literal) => {
+        IconAsset::svg(
+            $file,
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/icons/", $file)),
+        )
+    };
+}
+
+/// All logical icons known to the server, keyed by name, smallest rendition first.
+static REGISTRY: &[(&str, &[IconAsset])] = &[
+    (
+        "abacus",
+        &[
+            png!("abacus_48.png"),
+            png!("abacus_96.png"),
+            png!("abacus.png"),
+        ],
+    ),
+    (
+        "hourglass",
+        &[
+            png!("hourglass_48.png"),
+            png!("hourglass_96.png"),
+            png!("hourglass.png"),
+        ],
+    ),
+    (
+        "package",
+        &[
+            png!("package_48.png"),
+            png!("package_96.png"),
+            png!("package.png"),
+        ],
+    ),
+    ("question", &[svg!("question.svg")]),
+    (
+        "robot",
+        &[
+            png!("robot_48.png"),
+            png!("robot_96.png"),
+            png!("robot.png"),
+        ],
+    ),
+    ("speech", &[svg!("speech.svg")]),
+    (
+        "sun_behind_cloud",
+        &[
+            png!("sun_behind_cloud_48.png"),
+            png!("sun_behind_cloud_96.png"),
+            png!("sun_behind_cloud.png"),
+        ],
+    ),
+    ("thought_balloon", &[svg!("thought_balloon.svg")]),
+    (
+        "waving_hand",
+        &[
+            png!("waving_hand_48.png"),
+            png!("waving_hand_96.png"),
+            png!("waving_hand.png"),
+        ],
+    ),
+];
+
+/// Names of every icon in the registry.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    REGISTRY.iter().map(|(name, _)| *name)
+}
+
+/// All renditions of a logical icon.
+#[must_use]
+pub fn renditions(name: &str) -> Option<&'static [IconAsset]> {
+    REGISTRY
+        .iter()
+        .find_map(|(key, assets)| (*key == name).then_some(*assets))
+}
+
+/// Every embedded PNG with the name of the icon it renders, in registry order.
+pub fn pngs() -> impl Iterator<Item = (&'static str, &'static IconAsset)> {
+    REGISTRY.iter().flat_map(|(name, assets)| {
+        assets
+            .iter()
+            .filter(|asset| asset.mime_type == "image/png")
+            .map(move |asset| (*name, asset))
+    })
+}
+
+/// Find an embedded asset by file name, e.g. to serve it over HTTP.
+#[must_use]
+pub fn file(file: &str) -> Option<&'static IconAsset> {
+    REGISTRY
+        .iter()
+        .flat_map(|(_, assets)| assets.iter())
+        .find(|asset| asset.file == file)
+}
+
+/// Look up a logical icon by name, returning descriptors for every rendition.
+#[must_use]
+pub fn lookup(name: &str) -> Option<Vec<Icon>> {
+    renditions(name).map(|assets| assets.iter().map(IconAsset::to_icon).collect())
+}
+
+/// The icon for a file of `mime_type`: a speech bubble for text, an
+/// abacus for structured data, a package for anything else.
+#[must_use]
+pub fn for_mime_type(mime_type: &str) -> Vec<Icon> {
+    let essence = mime_type.split(';').next().unwrap_or("").trim();
+    let subtype = essence.split_once('/').map_or("", |(_, subtype)| subtype);
+    if essence.starts_with("text/") {
+        speech()
+    } else if subtype.ends_with("+json")
+        || subtype.ends_with("+xml")
+        || matches!(
+            subtype,
+            "json" | "xml" | "toml" | "x-toml" | "yaml" | "x-yaml"
+        )
+    {
+        abacus()
+    } else {
+        package()
+    }
+}
+
+/// Resolve a name that is known to be in the registry.
+fn named(name: &str) -> Vec<Icon> {
+    lookup(name).unwrap_or_else(|| panic!("icon '{name}' is not registered"))
 }
 
 /// Waving hand icon for greeting tools
 #[must_use]
 pub fn waving_hand() -> Vec<Icon> {
-    vec![icon("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAQAAAAEACAYAAABccqhmAAAACXBIWXMAAAsTAAALEwEAmpwYAAAAAXNSR0IArs4c6QAAAARnQU1BAACxjwv8YQUAAIS0SURBVHgB7X0HnCVVlfc5t6pe6DQzDAMMIFFExYzKuqsuoK6gi7LqIKAoGDCtrAldV8U2fK4560pSEARhXFSSYXVBl9VdRdcEqKhDzkzo+ELVvd/Nde+tqtc9M90zzVBnfjWV3qtX73Wd//mfcM8FqKWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWpaaINRSyyLLmouva2yajE5IW8sPJ3F7hBHSZYBTJCL3Y0bv40/hLRiTv/RY9w9X/8OKjVDLNpMaAGpZVDn6U1ctnyHt7/WHVj2ZNUYBkhYAIfrJQ0BUi9gnwCjfvhkYuzYi5BpC0quu/NXwdTCOFGpZFKkBoJZFled9+KqzZlurXsmtP7DGMEDc5E+dUni1qEcQkak38DUC08DAgCDcyncvbyC5uM1a/7X2GMyglgWTGgBqWTQ5cfyq1sbm6P2zzZVDaXMEaNLmDKAhzzGh4HJtMIBJ5QfQxl5uMwkC4rwgDRER7ADOwRjP+s4Lh26DWrZaagCoZdHkxI9et9tkmtzZaYxBn1t/yq0/i2N5jmkSIO2+ZAJG+QUQEKpBgHIRAcofBAAM+BN1gH7oMI/PCOjMgNxX8QFZ0y+BwFTv6eHBqm5XAgHhAEMiMsUjBf/8vRPgi3mvsM2Xl71l3Skd2v9Mj39eSphOE5r6ACIBQMYDYvjGt089fA08CKQGgFq2u7z0/b98LKXRed2s+eiUNbhbEPEFZWyA2rZfYlB/pDME3FITfNPlb9zlM5vzOWvWXNxo7rLPDTwWsF9PXDtC6QYw4QI0G4YFZEmW7X/Jux95MzwIpGYAtWx3Of+0J/y6PzZ9yFA0fWYTpyGmXYgFE8gyywagn+oqwh5QwQZS+sm//+w9L9qcz1m79phejPQTEeXOhmAAVC2GAYBiAVEWRy+BB4nUAFDLkpC1b/nr2fPGDz65mXRe18bpfsI6HAj6EgRECS/2uXL2MwkEjC+03yM07Z/73I/fcfDmfM40nf0qDz1uECCAZcqamqGyBRYAcmEOKBRTf1Di+4dMAPxMgBCU4fmXDfqtCKY/J+gEAk1ln1kofdiRb5x7cNCDUWoAqGWbSAaCGdAAAAAElFTkSuQmCC")]
+    named("waving_hand")
 }
 
 /// Question mark icon for query tools
 #[must_use]
 pub fn question() -> Vec<Icon> {
-    // Placeholder - using waving hand icon
-    waving_hand()
+    named("question")
 }
 
 /// Speech bubble icon for communication tools
 #[must_use]
 pub fn speech() -> Vec<Icon> {
-    // Placeholder - using waving hand icon
-    waving_hand()
+    named("speech")
 }
 
 /// Abacus icon for calculation tools
 #[must_use]
 pub fn abacus() -> Vec<Icon> {
-    // Placeholder - using waving hand icon
-    waving_hand()
+    named("abacus")
 }
 
 /// Sun behind cloud icon for weather tools
 #[must_use]
 pub fn sun_behind_cloud() -> Vec<Icon> {
-    vec![icon("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAQAAAAEACAYAAABccqhmAAAACXBIWXMAAAsTAAALEwEAmpwYAAAAAXNSR0IArs4c6QAAAARnQU1BAACxjwv8YQUAAIS0SURBVHgB7X0HnCVVlfc5t6pe6DQzDAMMIFFExYzKuqsuoK6gi7LqIKAoGDCtrAldV8U2fK4560pSEARhXFSSYXVBl9VdRdcEqKhDzkzo+ELVvd/Nde+tqtc9M90zzVBnfjWV3qtX73Wd//mfcM8FqKWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWpaaINRSyyLLmouva2yajE5IW8sPJ3F7hBHSZYBTJCL3Y0bv40/hLRiTv/RY9w9X/8OKjVDLNpMaAGpZVDn6U1ctnyHt7/WHVj2ZNUYBkhYAIfrJQ0BUi9gnwCjfvhkYuzYi5BpC0quu/NXwdTCOFGpZFKkBoJZFled9+KqzZlurXsmtP7DGMEDc5E+dUni1qEcQkak38DUC08DAgCDcyncvbyC5uM1a/7X2GMyglgWTGgBqWTQ5cfyq1sbm6P2zzZVDaXMEaNLmDKAhzzGh4HJtMIBJ5QfQxl5uMwkC4rwgDRER7ADOwRjP+s4Lh26DWrZaagCoZdHkxI9et9tkmtzZaYxBn1t/yq0/i2N5jmkSIO2+ZAJG+QUQEKpBgHIRAcofBAAM+BN1gH7oMI/PCOjMgNxX8QFZ0y+BwFTv6eHBqm5XAgHhAEMiMsUjBf/8vRPgi3mvsM2Xl71l3Skd2v9Mj39eSphOE5r6ACIBQMYDYvjGt089fA08CKQGgFq2u7z0/b98LKXRed2s+eiUNbhbEPEFZWyA2rZfYlB/pDME3FITfNPlb9zlM5vzOWvWXNxo7rLPDTwWsF9PXDtC6QYw4QI0G4YFZEmW7X/Jux95MzwIpGYAtWx3Of+0J/y6PzZ9yFA0fWYTpyGmXYgFE8gyywagn+oqwh5QwQZS+sm//+w9L9qcz1m79phejPQTEeXOhmAAVC2GAYBiAVEWRy+BB4nUAFDLkpC1b/nr2fPGDz65mXRe18bpfsI6HAj6EgRECS/2uXL2MwkEjC+03yM07Z/73I/fcfDmfM40nf0qDz1uECCAZcqamqGyBRYAcmEOKBRTf1Di+4dMAPxMgBCU4fmXDfqtCKY/J+gEAk1ln1kofdiRb5x7cNCDUWoAqGWbSAaCGdAAAAAElFTkSuQmCC")]
+    named("sun_behind_cloud")
 }
 
 /// Robot icon for automation tools
 #[must_use]
 pub fn robot() -> Vec<Icon> {
-    vec![icon("data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAQAAAAEACAYAAABccqhmAAAACXBIWXMAAAsTAAALEwEAmpwYAAAAAXNSR0IArs4c6QAAAARnQU1BAACxjwv8YQUAAIS0SURBVHgB7X0HnCVVlfc5t6pe6DQzDAMMIFFExYzKuqsuoK6gi7LqIKAoGDCtrAldV8U2fK4560pSEARhXFSSYXVBl9VdRdcEqKhDzkzo+ELVvd/Nde+tqtc9M90zzVBnfjWV3qtX73Wd//mfcM8FqKWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWmqppZZaaqmlllpqqaWWWpaaINRSyyLLmouva2yajE5IW8sPJ3F7hBHSZYBTJCL3Y0bv40/hLRiTv/RY9w9X/8OKjVDLNpMaAGpZVDn6U1ctnyHt7/WHVj2ZNUYBkhYAIfrJQ0BUi9gnwCjfvhkYuzYi5BpC0quu/NXwdTCOFGpZFKkBoJZFled9+KqzZlurXsmtP7DGMEDc5E+dUni1qEcQkak38DUC08DAgCDcyncvbyC5uM1a/7X2GMyglgWTGgBqWTQ5cfyq1sbm6P2zzZVDaXMEaNLmDKAhzzGh4HJtMIBJ5QfQxl5uMwkC4rwgDRER7ADOwRjP+s4Lh26DWrZaagCoZdHkxI9et9tkmtzZaYxBn1t/yq0/i2N5jmkSIO2+ZAJG+QUQEKpBgHIRAcofBAAM+BN1gH7oMI/PCOjMgNxX8QFZ0y+BwFTv6eHBqm5XAgHhAEMiMsUjBf/8vRPgi3mvsM2Xl71l3Skd2v9Mj39eSphOE5r6ACIBQMYDYvjGt089fA08CKQGgFq2u7z0/b98LKXRed2s+eiUNbhbEPEFZWyA2rZfYlB/pDME3FITfNPlb9zlM5vzOWvWXNxo7rLPDTwWsF9PXDtC6QYw4QI0G4YFZEmW7X/Jux95MzwIpGYAtWx3Of+0J/y6PzZ9yFA0fWYTpyGmXYgFE8gyywagn+oqwh5QwQZS+sm//+w9L9qcz1m79phejPQTEeXOhmAAVC2GAYBiAVEWRy+BB4nUAFDLkpC1b/nr2fPGDz65mXRe18bpfsI6HAj6EgRECS/2uXL2MwkEjC+03yM07Z/73I/fcfDmfM40nf0qDz1uECCAZcqamqGyBRYAcmEOKBRTf1Di+4dMAPxMgBCU4fmXDfqtCKY/J+gEAk1ln1kofdiRb5x7cNCDUWoAqGWbSAaCGdAAAAAElFTkSuQmCC")]
+    named("robot")
 }
 
 /// Hourglass icon for long-running tasks
 #[must_use]
 pub fn hourglass() -> Vec<Icon> {
-    // Placeholder - using waving hand icon
-    waving_hand()
+    named("hourglass")
 }
 
 /// Package icon for loading/installation tools
 #[must_use]
 pub fn package() -> Vec<Icon> {
-    // Placeholder - using waving hand icon
-    waving_hand()
+    named("package")
 }
 
 /// Thought balloon icon for AI/thinking tools
 #[must_use]
 pub fn thought_balloon() -> Vec<Icon> {
-    // Placeholder - using waving hand icon
-    waving_hand()
+    named("thought_balloon")
 }
@@ -0,0 +1,77 @@
+//! # MCP Rust Starter - Identity
+//!
+//! Who a request comes from. Two identities are available:
+//!
+//! - the **session**, from the `Mcp-Session-Id` header the Streamable HTTP
+//!   transport assigns (stdio has a single, unnamed session)
+//! - the **principal**, the authenticated user. This server does not
+//!   authenticate anyone itself; it trusts a header set by an authenticating
+//!   reverse proxy, `X-Authenticated-User` by default (`MCP_PRINCIPAL_HEADER`
+//!   to change it). Only deploy this behind a proxy that strips the header
+//!   from client requests.
+//!
+//! Both are read from the HTTP request parts that rmcp attaches to every
+//! request's extensions, so they are `None` over stdio.
+
+use std::sync::OnceLock;
+
+use axum::http::{request::Parts, HeaderMap};
+use rmcp::{service::RequestContext, RoleServer};
+
+/// Header carrying the Streamable HTTP session id.
+pub const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Header trusted to carry the authenticated principal, unless configured.
+pub const DEFAULT_PRINCIPAL_HEADER: &str = "x-authenticated-user";
+
+/// The header the principal is read from.
+pub fn principal_header() -> &'static str {
+    static HEADER: OnceLock<String> = OnceLock::new();
+    HEADER.get_or_init(|| {
+        std::env::var("MCP_PRINCIPAL_HEADER")
+            .ok()
+            .filter(|header| !header.is_empty())
+            .map_or_else(
+                || DEFAULT_PRINCIPAL_HEADER.to_string(),
+                |header| header.to_ascii_lowercase(),
+            )
+    })
+}
+
+/// The HTTP request a request arrived in, if it came over HTTP.
+#[must_use]
+pub fn http_parts(context: &RequestContext<RoleServer>) -> Option<&Parts> {
+    context.extensions.get::<Parts>()
+}
+
+/// The session id carried by `headers`.
+#[must_use]
+pub fn session_id_from(headers: &HeaderMap) -> Option<&str> {
+    header(headers, SESSION_HEADER)
+}
+
+/// The authenticated principal carried by `headers`.
+#[must_use]
+pub fn principal_from(headers: &HeaderMap) -> Option<&str> {
+    header(headers, principal_header())
+}
+
+/// The Streamable HTTP session a request belongs to, if any.
+#[must_use]
+pub fn session_id(context: &RequestContext<RoleServer>) -> Option<&str> {
+    session_id_from(&http_parts(context)?.headers)
+}
+
+/// The authenticated principal a request was made by, if any.
+#[must_use]
+pub fn principal(context: &RequestContext<RoleServer>) -> Option<&str> {
+    principal_from(&http_parts(context)?.headers)
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)?
+        .to_str()
+        .ok()
+        .filter(|value| !value.is_empty())
+}
@@ -14,25 +14,69 @@
 //! - [MCP Specification](https://modelcontextprotocol.io/)
 //! - [rmcp SDK](https://docs.rs/rmcp)
 
+pub mod audit;
+pub mod command;
+pub mod events;
+pub mod files;
+pub mod health;
 pub mod icons;
+pub mod identity;
+pub mod limits;
+pub mod manifest;
+pub mod metrics;
+pub mod peers;
+pub mod plugin;
+pub mod policy;
 pub mod prompts;
+pub mod reload;
 pub mod resources;
+pub mod roots;
+pub mod sessions;
+pub mod store;
+pub mod telemetry;
 pub mod tools;
+pub mod validation;
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
 
+use arc_swap::ArcSwap;
 use rmcp::{
-    handler::server::{tool::schema_for_type, tool::ToolRouter, wrapper::Parameters},
+    handler::server::{
+        tool::{schema_for_type, IntoCallToolResult, ToolRouter},
+        wrapper::Parameters,
+    },
     model::{
-        CallToolResult, Content, GetPromptResult, Implementation, ListPromptsResult,
-        ListResourcesResult, ReadResourceResult, ServerCapabilities, ServerInfo,
+        CallToolResult, Content, GetPromptResult, Implementation, JsonObject, ListPromptsResult,
+        ListResourcesResult, Meta, ProgressNotificationParam, ReadResourceResult,
+        ServerCapabilities, ServerInfo, SubscribeRequestParams, UnsubscribeRequestParams,
     },
     service::RequestContext,
-    tool, tool_router, ErrorData as McpError, RoleServer, ServerHandler,
+    tool, tool_router, ErrorData as McpError, Peer, RoleServer, ServerHandler,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    audit::AuditLog,
+    files::FileResources,
+    health::Health,
+    limits::{RateLimiter, RateLimits},
+    manifest::Catalog,
+    metrics::Metrics,
+    peers::Peers,
+    plugin::{PluginHost, PluginLimits},
+    policy::Policy,
+    reload::ReloadStatus,
+    resources::InlineLimit,
+    roots::{Roots, SessionRoots},
+    store::{Session, Sessions},
+    validation::Validator,
+};
+
 // Re-export types for convenience
 pub use tools::{
     AskLlmResponse, ConfirmActionResponse, GetFeedbackResponse, HelloResponse,
@@ -60,7 +104,12 @@ pub use tools::{
 #[schemars(title = "helloArguments")]
 pub struct HelloParams {
     /// Name of the person to greet
-    #[schemars(title = "Name", description = "Name of the person to greet")]
+    #[schemars(
+        title = "Name",
+        description = "Name of the person to greet",
+        length(min = 1, max = 100),
+        regex(pattern = r"^[^\x00-\x1f]+$")
+    )]
     pub name: String,
 }
 
@@ -69,7 +118,12 @@ pub struct HelloParams {
 #[schemars(title = "get_weatherArguments")]
 pub struct GetWeatherParams {
     /// City name to get weather for
-    #[schemars(title = "City", description = "City name to get weather for")]
+    #[schemars(
+        title = "City",
+        description = "City name to get weather for",
+        length(min = 1, max = 100),
+        regex(pattern = r"^[^\x00-\x1f]+$")
+    )]
     pub city: String,
 }
 
@@ -78,15 +132,21 @@ pub struct GetWeatherParams {
 #[schemars(title = "long_taskArguments")]
 pub struct LongTaskParams {
     /// Name for this task
-    #[schemars(title = "Task Name", description = "Name for this task")]
+    #[schemars(
+        title = "Task Name",
+        description = "Name for this task",
+        length(min = 1, max = 100)
+    )]
     #[serde(rename = "taskName")]
     pub task_name: String,
 
-    /// Number of steps to simulate
+    /// Number of steps to simulate, 100ms each: at most 90, so the task
+    /// always finishes within its 10s timeout
     #[schemars(
         title = "Steps",
         description = "Number of steps to simulate",
-        default = "default_steps"
+        default = "default_steps",
+        range(min = 1, max = 90)
     )]
     #[serde(default = "default_steps")]
     pub steps: i32,
@@ -103,7 +163,9 @@ pub struct AskLlmParams {
     /// The question or prompt to send to the LLM
     #[schemars(
         title = "Prompt",
-        description = "The question or prompt to send to the LLM"
+        description = "The question or prompt to send to the LLM",
+        length(min = 1, max = 10000),
+        extend("x-sensitive" = true)
     )]
     pub prompt: String,
 
@@ -111,7 +173,8 @@ pub struct AskLlmParams {
     #[schemars(
         title = "Max Tokens",
         description = "Maximum tokens in response",
-        default = "default_max_tokens"
+        default = "default_max_tokens",
+        range(min = 1, max = 4096)
     )]
     #[serde(rename = "maxTokens", default = "default_max_tokens")]
     pub max_tokens: i32,
@@ -126,7 +189,11 @@ const fn default_max_tokens() -> i32 {
 #[schemars(title = "confirm_actionArguments")]
 pub struct ConfirmActionParams {
     /// Description of the action to confirm
-    #[schemars(title = "Action", description = "Description of the action to confirm")]
+    #[schemars(
+        title = "Action",
+        description = "Description of the action to confirm",
+        length(min = 1, max = 500)
+    )]
     pub action: String,
 
     /// Whether the action is destructive
@@ -148,7 +215,11 @@ const fn default_destructive() -> bool {
 #[schemars(title = "get_feedbackArguments")]
 pub struct GetFeedbackParams {
     /// The question to ask the user
-    #[schemars(title = "Question", description = "The question to ask the user")]
+    #[schemars(
+        title = "Question",
+        description = "The question to ask the user",
+        length(min = 1, max = 500)
+    )]
     pub question: String,
 }
 
@@ -177,11 +248,96 @@ A demonstration MCP server showcasing Rust SDK capabilities.
 - All tools include annotations (readOnlyHint, idempotentHint, openWorldHint) to guide safe usage
 - Resources and prompts are available for context and templating — use `resources/list` and `prompts/list` to discover them";
 
+/// State shared by every session a process serves.
+///
+/// The HTTP transport creates one `McpServer` per session; each of them is
+/// built from a clone of the same `ServerState` so process-wide facilities
+/// (like metrics) see traffic from all sessions.
+#[derive(Clone, Default)]
+pub struct ServerState {
+    /// Prometheus metrics for tool, resource and prompt traffic.
+    pub metrics: Arc<Metrics>,
+    /// Dependency checks and the draining flag behind `/health/ready`.
+    pub health: Arc<Health>,
+    /// Rate limit buckets and the in-flight tool call cap.
+    pub limits: Arc<RateLimiter>,
+    /// Annotation-driven rules checked before each tool runs.
+    pub policy: Arc<Policy>,
+    /// Append-only record of every tool call (disabled by default).
+    pub audit: Arc<AuditLog>,
+    /// Tools, prompts and resources declared in the `MCP_TOOL_MANIFEST`
+    /// file (none by default), replaced whole when it is reloaded.
+    pub manifest: Arc<ArcSwap<Catalog>>,
+    /// Whether the latest reload of the manifest failed.
+    pub reloads: Arc<ReloadStatus>,
+    /// The `file://` resources under `MCP_RESOURCE_DIR` (none by default).
+    pub files: Arc<FileResources>,
+    /// Resources larger than this are read as links, not inline contents.
+    pub inline_limit: InlineLimit,
+    /// WebAssembly plugin tools loaded from `MCP_PLUGIN_DIR` (none by default).
+    pub plugins: Arc<PluginHost>,
+    /// Initialized sessions, for `list_changed` notifications.
+    pub peers: Arc<Peers>,
+    /// Stored HTTP sessions, restored when their clients come back (kept
+    /// in memory by default).
+    pub sessions: Arc<Sessions>,
+    /// Deadlines that replace the ones tools declare, by tool name (none by
+    /// default).
+    pub timeouts: Arc<HashMap<String, Duration>>,
+    /// Whether every HTTP request gets a fresh server and no session, so
+    /// nothing can be sent to the client outside a request.
+    pub stateless: bool,
+}
+
+impl ServerState {
+    /// Configure rate limits, policy, the audit log, file resources, the
+    /// tool manifest and plugin limits from the environment, with health
+    /// checks on them. Sessions are kept in memory; a transport that stores
+    /// them replaces [`Self::sessions`] and then [`Self::health`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invalid setting.
+    pub fn from_env() -> Result<Self, String> {
+        let limits =
+            RateLimits::from_env().map_err(|e| format!("Invalid rate limit configuration: {e}"))?;
+        let policy =
+            Policy::from_env().map_err(|e| format!("Invalid policy configuration: {e}"))?;
+        let audit =
+            AuditLog::from_env().map_err(|e| format!("Invalid audit log configuration: {e}"))?;
+        let files = FileResources::from_env()
+            .map_err(|e| format!("Invalid resource directory configuration: {e}"))?;
+        let inline_limit =
+            InlineLimit::from_env().map_err(|e| format!("Invalid resource configuration: {e}"))?;
+        let manifest = Catalog::from_env(&McpServer::builtin_tool_names())
+            .map_err(|e| format!("Invalid tool manifest: {e}"))?;
+        let plugins =
+            PluginLimits::from_env().map_err(|e| format!("Invalid plugin configuration: {e}"))?;
+        let mut state = Self {
+            limits: Arc::new(RateLimiter::new(limits)),
+            policy: Arc::new(policy),
+            audit: Arc::new(audit),
+            files: Arc::new(files),
+            inline_limit,
+            manifest: Arc::new(ArcSwap::from_pointee(manifest)),
+            plugins: Arc::new(PluginHost::new(plugins)),
+            ..Self::default()
+        };
+        state.health = Arc::new(Health::for_state(&state));
+        Ok(state)
+    }
+}
+
 /// The main MCP server implementing all handlers.
 #[derive(Clone)]
 pub struct McpServer {
     #[allow(dead_code)]
     tool_router: ToolRouter<Self>,
+    state: ServerState,
+    /// The client's roots, for this session only.
+    roots: Arc<SessionRoots>,
+    /// This session's subscriptions and loaded tools.
+    session: Arc<Session>,
 }
 
 impl Default for McpServer {
@@ -191,12 +347,218 @@ impl Default for McpServer {
 }
 
 impl McpServer {
-    /// Create a new MCP server instance.
+    /// Create a new MCP server instance with its own, unshared state.
     #[must_use]
     pub fn new() -> Self {
+        Self::with_state(ServerState::default())
+    }
+
+    /// Create a new MCP server instance using shared process state.
+    #[must_use]
+    pub fn with_state(state: ServerState) -> Self {
         Self {
             tool_router: Self::tool_router(),
+            state,
+            roots: Arc::default(),
+            session: Arc::default(),
+        }
+    }
+
+    /// Create the server of the stored session `id`, with the
+    /// subscriptions and tools it had.
+    pub async fn for_session(state: ServerState, id: &str) -> Self {
+        let server = Self::with_state(state);
+        server.session.bind(id, &server.state.sessions).await;
+        server
+    }
+
+    /// Names of the tools compiled into the server, including those a
+    /// session can load, which manifest and plugin tools may not reuse.
+    #[must_use]
+    pub fn builtin_tool_names() -> HashSet<String> {
+        Self::tool_router()
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .chain([tools::BONUS_CALCULATOR.to_string()])
+            .collect()
+    }
+
+    /// The definition of a compiled, session, manifest or plugin tool.
+    fn tool(&self, name: &str) -> Option<rmcp::model::Tool> {
+        self.tool_in(name, &self.state.manifest.load())
+    }
+
+    /// The definition of a compiled, session or plugin tool, or of a tool
+    /// declared in `manifest`.
+    fn tool_in(&self, name: &str, manifest: &Catalog) -> Option<rmcp::model::Tool> {
+        self.tool_router
+            .get(name)
+            .cloned()
+            .or_else(|| self.session_tool(name))
+            .or_else(|| manifest.tool(name).cloned())
+            .or_else(|| self.state.plugins.get(name))
+    }
+
+    /// The compiled input schema of a compiled, session or plugin tool, or
+    /// of a tool declared in `manifest`, looked up in the same order as
+    /// [`Self::tool_in`].
+    fn validator(&self, name: &str, manifest: &Catalog) -> Option<Arc<Validator>> {
+        static BUILTIN: OnceLock<HashMap<String, Arc<Validator>>> = OnceLock::new();
+        let builtin = BUILTIN.get_or_init(|| {
+            Self::tool_router()
+                .list_all()
+                .into_iter()
+                .chain(tools::session_tool(tools::BONUS_CALCULATOR))
+                .map(|tool| {
+                    let validator = Validator::new(&tool.input_schema)
+                        .unwrap_or_else(|e| panic!("tool '{}' input schema: {e}", tool.name));
+                    (tool.name.into_owned(), Arc::new(validator))
+                })
+                .collect()
+        });
+        if self.tool_router.has_route(name) || self.session_tool(name).is_some() {
+            return builtin.get(name).cloned();
+        }
+        manifest
+            .validator(name)
+            .or_else(|| self.state.plugins.validator(name))
+    }
+
+    /// The definition of a tool this session has loaded.
+    fn session_tool(&self, name: &str) -> Option<rmcp::model::Tool> {
+        self.session
+            .data()
+            .tools
+            .contains(name)
+            .then(|| tools::session_tool(name))
+            .flatten()
+    }
+
+    /// This session's state, attached to the stored session named in the
+    /// request's `Mcp-Session-Id` header the first time there is one.
+    async fn session(&self, context: &RequestContext<RoleServer>) -> &Session {
+        if let Some(id) = identity::session_id(context) {
+            self.session.bind(id, &self.state.sessions).await;
+        }
+        &self.session
+    }
+
+    /// The shared state this server records into.
+    #[must_use]
+    pub const fn state(&self) -> &ServerState {
+        &self.state
+    }
+
+    /// Apply the rate limits to a tool call, unless the HTTP layer already
+    /// did.
+    fn admit(
+        &self,
+        request: &rmcp::model::CallToolRequestParams,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<(), limits::Limited> {
+        let admitted = identity::http_parts(context)
+            .is_some_and(|parts| parts.extensions.get::<limits::Admitted>().is_some());
+        if admitted {
+            return Ok(());
+        }
+        self.state.limits.check(&limits::Caller {
+            session: identity::session_id(context),
+            principal: identity::principal(context),
+            tool: &request.name,
+        })
+    }
+
+    /// The tool result for a call rejected by a limit.
+    fn rate_limited(&self, limited: &limits::Limited) -> CallToolResult {
+        self.state
+            .metrics
+            .observe_rate_limited(limited.scope.as_str());
+        limited.to_tool_error().into_result()
+    }
+
+    /// Validate an admitted tool call's arguments, apply the safety policy,
+    /// then run the tool under its timeout. The concurrency cap is applied
+    /// only once the call is allowed, so time spent waiting for the user to
+    /// confirm it does not hold a slot.
+    async fn run_tool(
+        &self,
+        request: rmcp::model::CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // The manifest this call is validated, authorized and run against,
+        // even if a reload swaps it out meanwhile
+        let manifest = self.state.manifest.load_full();
+        let definition = self.tool_in(&request.name, &manifest);
+        if let Some(definition) = &definition {
+            if let Some(validator) = self.validator(&request.name, &manifest) {
+                if let Err(errors) = validator.validate(request.arguments.as_ref()) {
+                    return Err(validation::invalid_arguments(&request.name, &errors));
+                }
+            }
+            if let Err(denied) = self.state.policy.authorize(definition, &context).await {
+                return Ok(denied.into_result());
+            }
         }
+        let _permit = match self.state.limits.acquire() {
+            Ok(permit) => permit,
+            Err(limited) => return Ok(self.rate_limited(&limited)),
+        };
+
+        let timeout = self
+            .state
+            .timeouts
+            .get(&*request.name)
+            .copied()
+            .unwrap_or_else(|| {
+                definition
+                    .as_ref()
+                    .map_or(tools::DEFAULT_TIMEOUT, tools::timeout_of)
+            });
+        let name = request.name.clone();
+        let cancel = context.ct.clone();
+        let call = async {
+            if self.tool_router.has_route(&name) || definition.is_none() {
+                let tool_context =
+                    rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+                return self.tool_router.call(tool_context).await;
+            }
+            let arguments = request.arguments.unwrap_or_default();
+            if self.session_tool(&name).is_some() {
+                return tools::call_session_tool(&name, arguments).into_call_tool_result();
+            }
+            let response = if manifest.tool(&name).is_some() {
+                let roots = self.roots.get(&context.peer).await;
+                let progress = context
+                    .meta
+                    .get_progress_token()
+                    .map(|token| command::Progress {
+                        peer: context.peer.clone(),
+                        token,
+                    });
+                manifest.call(&name, &arguments, &roots, progress).await
+            } else {
+                self.state.plugins.call(&name, &arguments).await
+            };
+            response.into_call_tool_result()
+        };
+        // Dropping the tool future on timeout cancels it; cancelling the
+        // request token also stops anything it handed the token to.
+        tokio::time::timeout(timeout, call)
+            .await
+            .unwrap_or_else(|_| {
+                cancel.cancel();
+                tracing::warn!(tool = %name, ?timeout, "Tool call timed out");
+                Ok(tools::timed_out(&name, timeout).into_result())
+            })
+    }
+}
+
+/// Arguments are redacted using the `x-sensitive` flags in each tool's input schema.
+impl telemetry::RedactArguments for McpServer {
+    fn redact_arguments(&self, tool: &str, arguments: &JsonObject) -> serde_json::Value {
+        let schema = self.tool(tool).map(|tool| tool.input_schema);
+        telemetry::redact(arguments, schema.as_deref())
     }
 }
 
@@ -224,12 +586,12 @@ impl McpServer {
         ),
         icons = icons::waving_hand()
     )]
-    async fn hello(&self, params: Parameters<HelloParams>) -> Result<CallToolResult, McpError> {
+    async fn hello(&self, params: Parameters<HelloParams>) -> tools::ToolResponse {
         let message = format!(
             "Hello, {}! Welcome to the MCP Rust Starter Server.",
             params.0.name
         );
-        Ok(CallToolResult::success(vec![Content::text(message)]))
+        CallToolResult::success(vec![Content::text(message)]).into()
     }
 
     /// `get_weather` – Structured output with `output_schema`.
@@ -248,32 +610,16 @@ impl McpServer {
         ),
         icons = icons::sun_behind_cloud()
     )]
-    async fn get_weather(
-        &self,
-        params: Parameters<GetWeatherParams>,
-    ) -> Result<CallToolResult, McpError> {
-        use rand::Rng;
-        let mut rng = rand::rng();
-        let conditions = ["sunny", "cloudy", "rainy", "windy"];
-
-        let weather = tools::Weather {
-            location: params.0.city.clone(),
-            temperature: rng.random_range(15..35),
-            unit: "celsius".to_string(),
-            conditions: conditions[rng.random_range(0..conditions.len())].to_string(),
-            humidity: rng.random_range(40..80),
-        };
+    async fn get_weather(&self, params: Parameters<GetWeatherParams>) -> tools::ToolResponse {
+        let weather = tools::current_weather(&params.0.city);
 
-        let json_str = serde_json::to_string_pretty(&weather)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+        tools::json_result(&weather).into()
     }
 
     /// `long_task` – Progress reporting via notifications.
-    /// Shows how a tool can report incremental progress to the client.
-    /// In a full implementation, each step would send a `notifications/progress`
-    /// message so the client can display a progress bar.
+    /// Shows how a tool can report incremental progress to the client: when
+    /// the request carries a progress token, each step sends a
+    /// `notifications/progress` message so the client can display a progress bar.
     #[tool(
         name = "long_task",
         description = "Simulate a long-running task with progress updates",
@@ -285,36 +631,49 @@ impl McpServer {
             idempotent_hint = true,
             open_world_hint = false
         ),
-        icons = icons::hourglass()
+        icons = icons::hourglass(),
+        meta = tools::timeout(Duration::from_secs(10))
     )]
     async fn long_task(
         &self,
         params: Parameters<LongTaskParams>,
-    ) -> Result<CallToolResult, McpError> {
+        meta: Meta,
+        peer: Peer<RoleServer>,
+    ) -> tools::ToolResponse {
         use std::fmt::Write;
 
         // Simulate a long task with progress updates
         let steps = params.0.steps;
         let task_name = &params.0.task_name;
+        let token = meta.get_progress_token();
 
         let mut result = format!("Starting task '{task_name}' with {steps} steps:\n");
 
         for i in 1..=steps {
-            // In a real implementation, this would send progress notifications
-            writeln!(&mut result, "Step {i}/{steps} completed").unwrap();
             // Simulate work
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            let step = format!("Step {i}/{steps} completed");
+            if let Some(token) = &token {
+                let progress = ProgressNotificationParam::new(token.clone(), f64::from(i))
+                    .with_total(f64::from(steps))
+                    .with_message(step.clone());
+                // Progress is best-effort; the task goes on without it
+                if let Err(e) = peer.notify_progress(progress).await {
+                    tracing::debug!(error = %e, "Failed to send long_task progress");
+                }
+            }
+            writeln!(&mut result, "{step}").unwrap();
         }
 
         write!(&mut result, "Task '{task_name}' completed successfully!").unwrap();
 
-        Ok(CallToolResult::success(vec![Content::text(result)]))
+        CallToolResult::success(vec![Content::text(result)]).into()
     }
 
     /// `load_bonus_tool` – Dynamic tool registration (`listChanged` notification).
-    /// Demonstrates adding tools at runtime. When called, the server would
-    /// register a new tool and send a `notifications/tools/list_changed`
-    /// notification so clients refresh their tool list.
+    /// Demonstrates adding tools at runtime: loads `bonus_calculator` into
+    /// this session and sends a `notifications/tools/list_changed`
+    /// notification so the client refreshes its tool list.
     /// This is why `enable_tool_list_changed()` is set in `get_info()`.
     #[tool(
         name = "load_bonus_tool",
@@ -329,19 +688,37 @@ impl McpServer {
         ),
         icons = icons::package()
     )]
-    async fn load_bonus_tool(&self) -> Result<CallToolResult, McpError> {
-        // In a full implementation, this would dynamically register a new tool
-        let result = serde_json::json!({
-            "note": "This tool demonstrates dynamic tool loading capability.",
-            "description": "In a full implementation, this would register a new 'bonus_tool' that clients can discover and call.",
-            "usage": "Call this tool to trigger dynamic tool registration.",
-            "limitation": "rmcp SDK does not currently support runtime tool registration, so this is a placeholder."
-        });
-
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    async fn load_bonus_tool(&self, context: RequestContext<RoleServer>) -> tools::ToolResponse {
+        let loaded = self
+            .session(&context)
+            .await
+            .change(&self.state.sessions, |data| {
+                data.tools.insert(tools::BONUS_CALCULATOR.to_string())
+            })
+            .await;
+        if loaded && !self.state.stateless {
+            if let Err(e) = context.peer.notify_tool_list_changed().await {
+                tracing::debug!(error = %e, "Failed to send tools/list_changed");
+            }
+        }
+        let note = if loaded {
+            "Loaded 'bonus_calculator' into this session, demonstrating dynamic tool loading."
+        } else {
+            "'bonus_calculator' is already loaded in this session; dynamic tool loading is idempotent."
+        };
+        let limitation = if self.state.stateless {
+            "Served without a session, so the tool is forgotten once this request completes."
+        } else {
+            "The tool belongs to this session only, and is restored with it after a server restart."
+        };
+        let result = LoadBonusToolResponse {
+            note: note.to_string(),
+            description: "'bonus_calculator' performs arithmetic on two numbers.".to_string(),
+            usage: "Refresh the tool list, then call 'bonus_calculator' with 'a', 'b' and an 'operation' (add, subtract, multiply or divide).".to_string(),
+            limitation: limitation.to_string(),
+        };
 
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+        tools::json_result(&result).into()
     }
 
     /// `ask_llm` – LLM sampling capability.
@@ -359,9 +736,10 @@ impl McpServer {
             idempotent_hint = false,
             open_world_hint = false
         ),
-        icons = icons::thought_balloon()
+        icons = icons::thought_balloon(),
+        meta = tools::timeout(Duration::from_mins(1))
     )]
-    async fn ask_llm(&self, params: Parameters<AskLlmParams>) -> Result<CallToolResult, McpError> {
+    async fn ask_llm(&self, params: Parameters<AskLlmParams>) -> tools::ToolResponse {
         // In a full implementation, this would use the MCP sampling feature to ask the LLM
         let result = serde_json::json!({
             "note": "This tool demonstrates MCP sampling capability.",
@@ -372,10 +750,7 @@ impl McpServer {
             "sampling_support": "Requires rmcp 'sampling' feature and client support."
         });
 
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+        tools::json_result(&result).into()
     }
 
     /// `confirm_action` – Schema elicitation.
@@ -393,12 +768,10 @@ impl McpServer {
             idempotent_hint = false,
             open_world_hint = false
         ),
-        icons = icons::question()
+        icons = icons::question(),
+        meta = tools::timeout(Duration::from_mins(5))
     )]
-    async fn confirm_action(
-        &self,
-        params: Parameters<ConfirmActionParams>,
-    ) -> Result<CallToolResult, McpError> {
+    async fn confirm_action(&self, params: Parameters<ConfirmActionParams>) -> tools::ToolResponse {
         // In a full implementation, this would use:
         // let result = context.peer().elicit::<ConfirmSchema>("Confirm action?").await;
         let result = serde_json::json!({
@@ -410,10 +783,7 @@ impl McpServer {
             "elicitation_support": "Requires rmcp 'elicitation' feature and client support."
         });
 
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+        tools::json_result(&result).into()
     }
 
     /// `get_feedback` – URL elicitation.
@@ -431,12 +801,10 @@ impl McpServer {
             idempotent_hint = false,
             open_world_hint = true
         ),
-        icons = icons::speech()
+        icons = icons::speech(),
+        meta = tools::timeout(Duration::from_mins(5))
     )]
-    async fn get_feedback(
-        &self,
-        params: Parameters<GetFeedbackParams>,
-    ) -> Result<CallToolResult, McpError> {
+    async fn get_feedback(&self, params: Parameters<GetFeedbackParams>) -> tools::ToolResponse {
         // In a full implementation, this would use:
         // let feedback = context.peer().elicit::<FeedbackSchema>("Please provide feedback").await;
         let result = serde_json::json!({
@@ -447,10 +815,7 @@ impl McpServer {
             "elicitation_support": "Requires rmcp 'elicitation' feature and client support."
         });
 
-        let json_str = serde_json::to_string_pretty(&result)
-            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
-
-        Ok(CallToolResult::success(vec![Content::text(json_str)]))
+        tools::json_result(&result).into()
     }
 }
 
@@ -469,34 +834,82 @@ impl ServerHandler for McpServer {
     /// - `enable_tool_list_changed()` – server may add/remove tools at runtime
     ///   (needed because `load_bonus_tool` dynamically registers a new tool)
     /// - `enable_resources()`         – server exposes readable resources
+    /// - `enable_resources_subscribe()` – clients may subscribe to updates
+    ///   of a resource
     /// - `enable_prompts()`           – server exposes prompt templates
     ///
-    /// Prompts and resources do NOT enable `list_changed` because this server's
-    /// prompt and resource lists are static — they never change after startup.
+    /// Prompts and resources also enable `list_changed`: those declared in
+    /// the manifest change when it is reloaded. Neither `list_changed` nor
+    /// subscriptions are advertised when serving statelessly, as there is no
+    /// session to send notifications to.
     fn get_info(&self) -> ServerInfo {
-        ServerInfo::new(
-            ServerCapabilities::builder()
-                .enable_experimental()
-                .enable_prompts()
-                .enable_resources()
-                .enable_tools()
+        let capabilities = ServerCapabilities::builder()
+            .enable_experimental()
+            .enable_prompts()
+            .enable_resources()
+            .enable_tools();
+        let capabilities = if self.state.stateless {
+            capabilities.build()
+        } else {
+            capabilities
+                .enable_prompts_list_changed()
+                .enable_resources_list_changed()
+                .enable_resources_subscribe()
                 .enable_tool_list_changed()
-                .build(),
-        )
-        .with_server_info(Implementation::new("mcp-rust-starter", "1.0.0"))
-        .with_instructions(SERVER_INSTRUCTIONS)
+                .build()
+        };
+        ServerInfo::new(capabilities)
+            .with_server_info(Implementation::new("mcp-rust-starter", "1.0.0"))
+            .with_instructions(SERVER_INSTRUCTIONS)
+    }
+
+    /// Registers the session so it hears about tools, prompts and resources
+    /// appearing and disappearing outside its own requests, and asks the
+    /// client for its roots.
+    async fn on_initialized(&self, context: rmcp::service::NotificationContext<RoleServer>) {
+        self.state
+            .peers
+            .add(context.peer.clone(), self.session.clone());
+        // rmcp runs this before it reads any more messages, so waiting for
+        // the client's answer here would never finish
+        let roots = self.roots.clone();
+        tokio::spawn(async move {
+            roots.refresh(&context.peer).await;
+        });
+    }
+
+    /// Fetches the client's new roots. The files it may list change with
+    /// them, so the resource list is announced as changed too.
+    async fn on_roots_list_changed(&self, context: rmcp::service::NotificationContext<RoleServer>) {
+        let before = self.roots.get(&context.peer).await;
+        let after = self.roots.refresh(&context.peer).await;
+        if before != after && self.state.files.is_enabled() {
+            if let Err(e) = context.peer.notify_resource_list_changed().await {
+                tracing::debug!(error = %e, "Failed to send resources/list_changed");
+            }
+        }
     }
 
     // -- Tool handlers --
 
-    /// Lists all tools registered with this server (via the `#[tool_router]` macro).
+    /// Lists the tools registered via the `#[tool_router]` macro, followed by
+    /// those this session loaded, any declared in the tool manifest and
+    /// those of loaded plugins.
     async fn list_tools(
         &self,
         _request: Option<rmcp::model::PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<rmcp::model::ListToolsResult, McpError> {
+        let loaded = self.session(&context).await.data().tools;
         Ok(rmcp::model::ListToolsResult {
-            tools: self.tool_router.list_all(),
+            tools: self
+                .tool_router
+                .list_all()
+                .into_iter()
+                .chain(loaded.iter().filter_map(|name| tools::session_tool(name)))
+                .chain(self.state.manifest.load().tools().cloned())
+                .chain(self.state.plugins.tools())
+                .collect(),
             next_cursor: None,
             meta: None,
         })
@@ -508,20 +921,104 @@ impl ServerHandler for McpServer {
         request: rmcp::model::CallToolRequestParams,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let tool_context =
-            rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
-        self.tool_router.call(tool_context).await
+        // Attached first, as the tool may be one the session loaded before a restart
+        self.session(&context).await;
+        let tool = if self.tool(&request.name).is_some() {
+            request.name.to_string()
+        } else {
+            metrics::UNKNOWN.to_string()
+        };
+        let started = Instant::now();
+        let audited = self.state.audit.is_enabled().then(|| {
+            (
+                request.name.to_string(),
+                request.arguments.clone().unwrap_or_default(),
+                identity::session_id(&context).map(str::to_string),
+                identity::principal(&context).map(str::to_string),
+            )
+        });
+
+        let result = match self.admit(&request, &context) {
+            Ok(()) => self.run_tool(request, context).await,
+            Err(limited) => Ok(self.rate_limited(&limited)),
+        };
+
+        let failed = result
+            .as_ref()
+            .map_or(true, |result| result.is_error == Some(true));
+        self.state
+            .metrics
+            .observe_tool_call(&tool, failed, started.elapsed());
+        if let Some((name, arguments, session_id, principal)) = audited {
+            let redacted = telemetry::RedactArguments::redact_arguments(self, &name, &arguments);
+            let record =
+                audit::Record::new(&name, &arguments, redacted, &result, started.elapsed())
+                    .by(session_id.as_deref(), principal.as_deref());
+            self.state.audit.record(&record).await;
+        }
+        result
     }
 
     // -- Resource handlers (read-only data exposed to clients) --
 
-    /// Lists static resources available on this server.
+    /// Lists static resources available on this server, followed by any
+    /// declared in the manifest and then a page of files within the
+    /// client's roots. Later pages hold only files.
     async fn list_resources(
         &self,
-        _request: Option<rmcp::model::PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
+        request: Option<rmcp::model::PaginatedRequestParams>,
+        context: RequestContext<RoleServer>,
     ) -> Result<ListResourcesResult, McpError> {
-        resources::list_resources()
+        let cursor = request.and_then(|request| request.cursor);
+        let mut result = if cursor.is_none() {
+            let mut first = resources::list_resources()?;
+            first
+                .resources
+                .extend(self.state.manifest.load().resources().cloned());
+            first
+        } else {
+            ListResourcesResult::default()
+        };
+        let (files, next_cursor) = if self.state.files.is_enabled() {
+            let roots = self.roots.get(&context.peer).await;
+            self.state.files.list(cursor.as_deref(), &roots)
+        } else {
+            (Vec::new(), None)
+        };
+        result.resources.extend(files);
+        result.next_cursor = next_cursor;
+        Ok(result)
+    }
+
+    /// Remembers that this session wants `notifications/resources/updated`
+    /// for the resource.
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.session(&context)
+            .await
+            .change(&self.state.sessions, |data| {
+                data.subscriptions.insert(request.uri)
+            })
+            .await;
+        Ok(())
+    }
+
+    /// Forgets a subscription made with `resources/subscribe`.
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.session(&context)
+            .await
+            .change(&self.state.sessions, |data| {
+                data.subscriptions.remove(&request.uri)
+            })
+            .await;
+        Ok(())
     }
 
     /// Lists resource templates (parameterised URI patterns like `greeting://{name}`).
@@ -537,20 +1034,48 @@ impl ServerHandler for McpServer {
     async fn read_resource(
         &self,
         request: rmcp::model::ReadResourceRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<ReadResourceResult, McpError> {
-        resources::read_resource(&request.uri)
+        if let Some(result) = self.state.manifest.load().read_resource(&request.uri) {
+            self.state.metrics.observe_resource_read(&request.uri, true);
+            return Ok(result);
+        }
+        let roots = if self.state.files.is_enabled() {
+            self.roots.get(&context.peer).await
+        } else {
+            Roots::unrestricted()
+        };
+        if let Some(result) = self
+            .state
+            .files
+            .read(&request.uri, &roots, self.state.inline_limit)
+        {
+            self.state
+                .metrics
+                .observe_resource_read(files::LABEL, result.is_ok());
+            return result;
+        }
+        let result = resources::read_resource(&request.uri, self.state.inline_limit);
+        self.state
+            .metrics
+            .observe_resource_read(resources::label(&request.uri), result.is_ok());
+        result
     }
 
     // -- Prompt handlers (reusable message templates) --
 
-    /// Lists all prompt templates this server offers.
+    /// Lists all prompt templates this server offers, followed by any
+    /// declared in the manifest.
     async fn list_prompts(
         &self,
         _request: Option<rmcp::model::PaginatedRequestParams>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListPromptsResult, McpError> {
-        prompts::list_prompts()
+        let mut result = prompts::list_prompts()?;
+        result
+            .prompts
+            .extend(self.state.manifest.load().prompts().cloned());
+        Ok(result)
     }
 
     /// Retrieves a prompt by name, filling in the supplied arguments.
@@ -565,6 +1090,22 @@ impl ServerHandler for McpServer {
                 .filter_map(|(k, v)| v.as_str().map(|s| (k, s.to_string())))
                 .collect::<HashMap<String, String>>()
         });
-        prompts::get_prompt(&request.name, arguments)
+        // Declared prompt names are bounded by the manifest, so they are labels too
+        let declared = self
+            .state
+            .manifest
+            .load()
+            .get_prompt(&request.name, &arguments.clone().unwrap_or_default());
+        if let Some(result) = declared {
+            self.state
+                .metrics
+                .observe_prompt_fetch(&request.name, result.is_ok());
+            return result;
+        }
+        let result = prompts::get_prompt(&request.name, arguments);
+        self.state
+            .metrics
+            .observe_prompt_fetch(prompts::label(&request.name), result.is_ok());
+        result
     }
 }
@@ -0,0 +1,497 @@
+//! # MCP Rust Starter - Rate Limits
+//!
+//! Token-bucket rate limits for `tools/call`, plus a cap on how many tool
+//! calls may run at once.
+//!
+//! Three kinds of bucket can apply to a call, and a call must find a token
+//! in every one of them (tokens are only taken when all buckets have one):
+//!
+//! - **per session**: one bucket per MCP session (stdio is one session)
+//! - **per principal**: one bucket per authenticated user, shared by all of
+//!   that user's sessions (see [`crate::identity`])
+//! - **per tool**: one bucket per tool that has a configured limit, shared by
+//!   every caller, e.g. to protect an expensive tool like `long_task`
+//!
+//! Over HTTP, the [`enforce`] middleware checks the buckets before the
+//! request reaches the MCP service and answers `429 Too Many Requests` with a
+//! `Retry-After` header. Everywhere else (stdio, or requests the middleware
+//! could not inspect) the server checks them in `call_tool` and returns a
+//! tool result with `isError: true` that says when to retry. The concurrency
+//! cap is always enforced in `call_tool`, since it has to be held while the
+//! tool runs; it is taken after any policy confirmation, so a prompt left
+//! open does not hold a slot.
+//!
+//! ## Configuration
+//!
+//! Rates are written as `<burst>/<period>`, where the period is `sec`, `min`
+//! or `hour` (e.g. `60/min`); `off` disables a limit.
+//!
+//! | variable                        | default          |
+//! |---------------------------------|------------------|
+//! | `MCP_RATE_LIMIT_SESSION`        | `60/min`         |
+//! | `MCP_RATE_LIMIT_PRINCIPAL`      | `off`            |
+//! | `MCP_RATE_LIMIT_TOOLS`          | `long_task=10/min` (comma-separated `tool=rate`) |
+//! | `MCP_MAX_CONCURRENT_TOOL_CALLS` | `32` (`off` for no cap) |
+
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+use crate::{
+    identity,
+    tools::{ToolError, ToolErrorCode},
+    ServerState,
+};
+
+/// Largest request body the [`enforce`] middleware will buffer.
+const MAX_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// Bucket count above which idle (full) buckets are dropped.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// A token-bucket rate: up to `burst` calls at once, refilled evenly over `per`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub burst: u32,
+    pub per: Duration,
+}
+
+impl Rate {
+    /// `burst` calls per minute.
+    #[must_use]
+    pub const fn per_minute(burst: u32) -> Self {
+        Self {
+            burst,
+            per: Duration::from_mins(1),
+        }
+    }
+
+    /// `burst` calls per second.
+    #[must_use]
+    pub const fn per_second(burst: u32) -> Self {
+        Self {
+            burst,
+            per: Duration::from_secs(1),
+        }
+    }
+
+    fn tokens_per_second(self) -> f64 {
+        f64::from(self.burst) / self.per.as_secs_f64()
+    }
+}
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (burst, period) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("invalid rate '{s}' (expected e.g. 60/min)"))?;
+        let burst: u32 = burst
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid burst in rate '{s}'"))?;
+        if burst == 0 {
+            return Err(format!("rate '{s}' allows no calls; use 'off' to disable"));
+        }
+        let per = match period.trim() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_mins(1),
+            "h" | "hour" => Duration::from_hours(1),
+            other => return Err(format!("invalid period '{other}' in rate '{s}'")),
+        };
+        Ok(Self { burst, per })
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}s", self.burst, self.per.as_secs())
+    }
+}
+
+/// Which limits apply. The default has none.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    /// Calls per MCP session.
+    pub per_session: Option<Rate>,
+    /// Calls per authenticated principal.
+    pub per_principal: Option<Rate>,
+    /// Calls per tool, for the tools listed.
+    pub per_tool: HashMap<String, Rate>,
+    /// Tool calls that may be in flight at once, across all sessions.
+    pub max_concurrent_tool_calls: Option<usize>,
+}
+
+impl RateLimits {
+    /// Read the limits from the environment, using the documented defaults
+    /// for unset variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first malformed variable.
+    pub fn from_env() -> Result<Self, String> {
+        let var =
+            |name: &str, default: &str| std::env::var(name).unwrap_or_else(|_| default.to_string());
+        let rate = |name: &str, default: &str| -> Result<Option<Rate>, String> {
+            optional(&var(name, default))
+                .map(str::parse)
+                .transpose()
+                .map_err(|e| format!("{name}: {e}"))
+        };
+
+        let mut per_tool = HashMap::new();
+        for entry in var("MCP_RATE_LIMIT_TOOLS", "long_task=10/min")
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty() && *entry != "off")
+        {
+            let (tool, rate) = entry.split_once('=').ok_or_else(|| {
+                format!("MCP_RATE_LIMIT_TOOLS: expected tool=rate, got '{entry}'")
+            })?;
+            let rate = rate
+                .parse()
+                .map_err(|e| format!("MCP_RATE_LIMIT_TOOLS: {e}"))?;
+            per_tool.insert(tool.trim().to_string(), rate);
+        }
+
+        let max_concurrent_tool_calls = optional(&var("MCP_MAX_CONCURRENT_TOOL_CALLS", "32"))
+            .map(|cap| {
+                cap.parse::<usize>()
+                    .ok()
+                    .filter(|cap| *cap > 0)
+                    .ok_or_else(|| format!("MCP_MAX_CONCURRENT_TOOL_CALLS: invalid cap '{cap}'"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            per_session: rate("MCP_RATE_LIMIT_SESSION", "60/min")?,
+            per_principal: rate("MCP_RATE_LIMIT_PRINCIPAL", "off")?,
+            per_tool,
+            max_concurrent_tool_calls,
+        })
+    }
+}
+
+/// `None` for an empty or `off` setting.
+fn optional(value: &str) -> Option<&str> {
+    let value = value.trim();
+    (!value.is_empty() && !value.eq_ignore_ascii_case("off")).then_some(value)
+}
+
+/// The limit a call ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Session,
+    Principal,
+    Tool,
+    Concurrency,
+}
+
+impl Scope {
+    /// The scope name used in errors and metrics.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Session => "session",
+            Self::Principal => "principal",
+            Self::Tool => "tool",
+            Self::Concurrency => "concurrency",
+        }
+    }
+}
+
+/// A rejected call, and how long to wait before retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limited {
+    pub scope: Scope,
+    pub retry_after: Duration,
+}
+
+impl Limited {
+    /// Whole seconds to wait, rounded up and at least one, as sent in `Retry-After`.
+    #[must_use]
+    pub const fn retry_after_secs(&self) -> u64 {
+        let secs = self.retry_after.as_secs() + (self.retry_after.subsec_nanos() > 0) as u64;
+        if secs == 0 {
+            1
+        } else {
+            secs
+        }
+    }
+
+    /// A human-readable explanation.
+    #[must_use]
+    pub fn message(&self) -> String {
+        match self.scope {
+            Scope::Concurrency => format!(
+                "Too many tool calls in progress; retry after {}s",
+                self.retry_after_secs()
+            ),
+            scope => format!(
+                "Rate limit exceeded ({}); retry after {}s",
+                scope.as_str(),
+                self.retry_after_secs()
+            ),
+        }
+    }
+
+    /// The tool error returned for a rejected call.
+    #[must_use]
+    pub fn to_tool_error(&self) -> ToolError {
+        ToolError::new(ToolErrorCode::RateLimited, self.message())
+            .with_detail("scope", self.scope.as_str())
+            .with_detail("retryAfterSeconds", self.retry_after_secs())
+    }
+
+    /// The `429` response for a rejected JSON-RPC request with the given `id`.
+    #[must_use]
+    pub fn to_http_response(&self, id: Option<&Value>) -> Response {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": -32000,
+                "message": self.message(),
+                "data": self.to_tool_error(),
+            },
+        });
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, self.retry_after_secs().to_string())],
+            Json(body),
+        )
+            .into_response()
+    }
+}
+
+/// Who is making a tool call, for choosing buckets.
+#[derive(Debug, Clone, Copy)]
+pub struct Caller<'a> {
+    /// Session id; `None` for the single stdio session.
+    pub session: Option<&'a str>,
+    /// Authenticated principal, if any.
+    pub principal: Option<&'a str>,
+    /// Name of the tool being called.
+    pub tool: &'a str,
+}
+
+/// Marks an HTTP request whose buckets [`enforce`] has already charged.
+#[derive(Debug, Clone, Copy)]
+pub struct Admitted;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Session(String),
+    Principal(String),
+    Tool(String),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    full_at: Instant,
+}
+
+impl Bucket {
+    fn full(rate: Rate, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(rate.burst),
+            updated: now,
+            full_at: now,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = elapsed
+            .mul_add(rate.tokens_per_second(), self.tokens)
+            .min(f64::from(rate.burst));
+        self.updated = now;
+    }
+
+    fn take(&mut self, rate: Rate, now: Instant) {
+        self.tokens -= 1.0;
+        let missing = f64::from(rate.burst) - self.tokens;
+        self.full_at = now + Duration::from_secs_f64(missing / rate.tokens_per_second());
+    }
+
+    fn wait(&self, rate: Rate) -> Duration {
+        Duration::from_secs_f64((1.0 - self.tokens).max(0.0) / rate.tokens_per_second())
+    }
+}
+
+/// Process-wide rate limiter state.
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<HashMap<Key, Bucket>>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+/// `RateLimiter::default()` enforces no limits.
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimits::default())
+    }
+}
+
+impl RateLimiter {
+    /// A limiter enforcing `limits`.
+    #[must_use]
+    pub fn new(limits: RateLimits) -> Self {
+        let in_flight = limits
+            .max_concurrent_tool_calls
+            .map(|cap| Arc::new(Semaphore::new(cap)));
+        Self {
+            limits,
+            buckets: Mutex::new(HashMap::new()),
+            in_flight,
+        }
+    }
+
+    /// The limits being enforced.
+    #[must_use]
+    pub const fn limits(&self) -> &RateLimits {
+        &self.limits
+    }
+
+    /// Take a token from every bucket that applies to `caller`, or from none
+    /// of them if any is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns the limit that was hit; when several are, the one that takes
+    /// longest to refill.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bucket lock was poisoned.
+    pub fn check(&self, caller: &Caller<'_>) -> Result<(), Limited> {
+        let mut applicable = Vec::with_capacity(3);
+        if let Some(rate) = self.limits.per_session {
+            let session = caller.session.unwrap_or_default().to_string();
+            applicable.push((Key::Session(session), rate, Scope::Session));
+        }
+        if let (Some(rate), Some(principal)) = (self.limits.per_principal, caller.principal) {
+            applicable.push((
+                Key::Principal(principal.to_string()),
+                rate,
+                Scope::Principal,
+            ));
+        }
+        if let Some(rate) = self.limits.per_tool.get(caller.tool) {
+            applicable.push((Key::Tool(caller.tool.to_string()), *rate, Scope::Tool));
+        }
+        if applicable.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limit buckets lock");
+        let mut limited: Option<Limited> = None;
+        for (key, rate, scope) in &applicable {
+            let bucket = buckets
+                .entry(key.clone())
+                .or_insert_with(|| Bucket::full(*rate, now));
+            bucket.refill(*rate, now);
+            if bucket.tokens < 1.0 {
+                let retry_after = bucket.wait(*rate);
+                if limited.is_none_or(|limited| retry_after > limited.retry_after) {
+                    limited = Some(Limited {
+                        scope: *scope,
+                        retry_after,
+                    });
+                }
+            }
+        }
+        if let Some(limited) = limited {
+            drop(buckets);
+            return Err(limited);
+        }
+
+        for (key, rate, _) in &applicable {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.take(*rate, now);
+            }
+        }
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, bucket| bucket.full_at > now);
+        }
+        drop(buckets);
+        Ok(())
+    }
+
+    /// Reserve a slot for an in-flight tool call, held until the permit is
+    /// dropped. Returns `None` when there is no concurrency cap.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Scope::Concurrency`] rejection when every slot is taken.
+    pub fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, Limited> {
+        self.in_flight
+            .as_ref()
+            .map(|in_flight| {
+                in_flight.clone().try_acquire_owned().map_err(|_| Limited {
+                    scope: Scope::Concurrency,
+                    retry_after: Duration::from_secs(1),
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Axum middleware answering `429` to `tools/call` requests over the limit.
+///
+/// Requests it lets through are marked [`Admitted`] so `call_tool` does not
+/// charge the same call twice.
+pub async fn enforce(State(state): State<ServerState>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+
+    if let Ok(message) = serde_json::from_slice::<Value>(&bytes) {
+        if message.get("method").and_then(Value::as_str) == Some("tools/call") {
+            let caller = Caller {
+                session: identity::session_id_from(&parts.headers),
+                principal: identity::principal_from(&parts.headers),
+                tool: message
+                    .pointer("/params/name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+            };
+            if let Err(limited) = state.limits.check(&caller) {
+                state.metrics.observe_rate_limited(limited.scope.as_str());
+                return limited.to_http_response(message.get("id"));
+            }
+            parts.extensions.insert(Admitted);
+        }
+    }
+
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
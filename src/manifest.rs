@@ -0,0 +1,626 @@
+//! # MCP Rust Starter - Manifest
+//!
+//! Simple tools, prompts and resources can be declared in a TOML or YAML
+//! file instead of written in Rust. They are loaded at startup from
+//! `MCP_TOOL_MANIFEST` and served alongside the compiled ones; declared
+//! tools get the same argument validation, safety policy, rate limits,
+//! timeouts and audit log.
+//!
+//! ```toml
+//! [[tools]]
+//! name = "greet_team"
+//! title = "Greet Team"
+//! description = "Greet a team by name"
+//! icon = "waving_hand"                  # a built-in icon name
+//! timeout_ms = 5000                      # optional, default 30s
+//! annotations = { read_only = true, open_world = false }
+//! input_schema = { type = "object", required = ["team"], properties = { team = { type = "string", maxLength = 50 } } }
+//! run = { type = "template", template = "Hello, team {{team}}!" }
+//!
+//! [[tools]]
+//! name = "disk_usage"
+//! description = "Report disk usage for a path"
+//! input_schema = { type = "object", required = ["path"], properties = { path = { type = "string" } } }
+//! run = { type = "command", command = "du", args = ["-sh", "{{path}}"], working_dir = "data" }
+//!
+//! [[prompts]]
+//! name = "summarize"
+//! description = "Summarize a text"
+//! arguments = [{ name = "text", required = true }]
+//! messages = [{ role = "user", text = "Summarize this:\n\n{{text}}" }]
+//!
+//! [[resources]]
+//! uri = "doc://handbook"
+//! name = "Handbook"
+//! mime_type = "text/markdown"            # optional, default text/plain
+//! file = "handbook.md"                   # or `text = "..."`
+//! ```
+//!
+//! `{{field}}` placeholders are replaced by the argument of that name:
+//! strings as-is, other values as JSON, missing arguments as nothing.
+//! Commands are executed directly, never through a shell, in a sandboxed
+//! working directory; see the `command` module for their options.
+//! Resource `file`s are relative to the manifest and read when it loads.
+//! Declarations without an `icon` get a default one: a robot for tools, a
+//! speech bubble for prompts, and the icon for its MIME type for a resource.
+//!
+//! Declarations may not reuse the name of a compiled tool or prompt, or the
+//! URI of a compiled resource. Declared and plugin tool names may not clash
+//! either: whichever is loaded second, a plugin or a reloaded manifest, is
+//! rejected. The `reload` module watches the manifest and
+//! swaps in a new [`Catalog`] whenever it changes and is still valid.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use rmcp::{
+    handler::server::tool::schema_for_type,
+    model::{
+        CallToolResult, Content, GetPromptResult, Icon, JsonObject, Prompt, PromptArgument,
+        PromptMessage, PromptMessageRole, RawResource, ReadResourceResult, Resource,
+        ResourceContents, Tool, ToolAnnotations,
+    },
+    ErrorData as McpError,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    command::{self, CommandOutput, CommandSpec, Progress, Sandbox},
+    icons, prompts,
+    resources::{self, InlineLimit},
+    roots::Roots,
+    tools::{self, ToolError, ToolResponse},
+    validation::Validator,
+};
+
+/// The contents of a manifest file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub tools: Vec<ToolSpec>,
+    #[serde(default)]
+    pub prompts: Vec<PromptSpec>,
+    #[serde(default)]
+    pub resources: Vec<ResourceSpec>,
+}
+
+/// One declared tool.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ToolSpec {
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub description: String,
+    #[serde(default = "empty_schema")]
+    pub input_schema: JsonObject,
+    #[serde(default)]
+    pub annotations: AnnotationSpec,
+    /// Name of a built-in icon, see [`icons::names`].
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    pub run: Implementation,
+}
+
+fn empty_schema() -> JsonObject {
+    let mut schema = JsonObject::new();
+    schema.insert("type".into(), "object".into());
+    schema
+}
+
+/// Behaviour hints, as in `#[tool(annotations(...))]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnnotationSpec {
+    pub read_only: Option<bool>,
+    pub destructive: Option<bool>,
+    pub idempotent: Option<bool>,
+    pub open_world: Option<bool>,
+}
+
+/// What calling a declared tool does.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Implementation {
+    /// Return the rendered template as text.
+    Template { template: String },
+    /// Run a local executable; see [`command::run`].
+    Command(CommandSpec),
+}
+
+/// One declared prompt.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptSpec {
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Name of a built-in icon, see [`icons::names`].
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub arguments: Vec<ArgumentSpec>,
+    pub messages: Vec<MessageSpec>,
+}
+
+/// An argument a declared prompt accepts.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArgumentSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// One message of a declared prompt; `text` may use placeholders.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MessageSpec {
+    #[serde(default = "user")]
+    pub role: PromptMessageRole,
+    pub text: String,
+}
+
+const fn user() -> PromptMessageRole {
+    PromptMessageRole::User
+}
+
+/// One declared resource, with its contents inline or in a file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceSpec {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "text_plain")]
+    pub mime_type: String,
+    /// Name of a built-in icon, see [`icons::names`].
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+}
+
+fn text_plain() -> String {
+    "text/plain".into()
+}
+
+impl Manifest {
+    /// Parse a manifest, as TOML or YAML depending on the file extension.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the unsupported format, or of the read or
+    /// parse failure.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let yaml = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => false,
+            Some("yaml" | "yml") => true,
+            _ => {
+                return Err(format!(
+                    "{}: expected a .toml, .yaml or .yml file",
+                    path.display()
+                ))
+            }
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut manifest: Self = if yaml {
+            serde_norway::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?
+        } else {
+            toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?
+        };
+        // Resource files are relative to the manifest, not the server
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        for file in manifest
+            .resources
+            .iter_mut()
+            .filter_map(|r| r.file.as_mut())
+        {
+            *file = base.join(&*file);
+        }
+        Ok(manifest)
+    }
+}
+
+struct Declared {
+    tool: Tool,
+    validator: Arc<Validator>,
+    run: Implementation,
+}
+
+struct DeclaredPrompt {
+    prompt: Prompt,
+    spec: PromptSpec,
+}
+
+struct DeclaredResource {
+    resource: Resource,
+    text: String,
+    file: Option<PathBuf>,
+}
+
+/// Everything declared in a manifest, ready to serve.
+///
+/// A catalog is immutable; reloading the manifest builds a new one.
+#[derive(Default)]
+pub struct Catalog {
+    tools: Vec<Declared>,
+    prompts: Vec<DeclaredPrompt>,
+    resources: Vec<DeclaredResource>,
+    sandbox: Arc<Sandbox>,
+}
+
+impl Catalog {
+    /// Build the tools, prompts and resources declared in `manifest`,
+    /// rejecting any declared twice and tools whose name is in `reserved`,
+    /// prompts that reuse a compiled prompt's name and resources that shadow
+    /// a compiled resource. Command tools run in `sandbox`.
+    ///
+    /// `reserved` holds the compiled tools, and on reload the plugin tools
+    /// too. At startup the manifest is loaded before any plugin, so it is
+    /// the plugin that is rejected when their tool names clash.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invalid declaration.
+    pub fn new(
+        manifest: Manifest,
+        reserved: &HashSet<String>,
+        sandbox: Sandbox,
+    ) -> Result<Self, String> {
+        let mut seen = HashSet::new();
+        let mut tools = Vec::with_capacity(manifest.tools.len());
+        for spec in manifest.tools {
+            let name = spec.name.clone();
+            if reserved.contains(&name) {
+                return Err(format!("tool '{name}' is already defined by the server"));
+            }
+            if !seen.insert(name.clone()) {
+                return Err(format!("tool '{name}' is declared more than once"));
+            }
+            tools.push(declare(spec, &sandbox).map_err(|e| format!("tool '{name}': {e}"))?);
+        }
+
+        let builtin = prompts::list_prompts().map_err(|e| e.message.to_string())?;
+        let mut seen = HashSet::new();
+        let mut prompts = Vec::with_capacity(manifest.prompts.len());
+        for spec in manifest.prompts {
+            let name = spec.name.clone();
+            if builtin.prompts.iter().any(|prompt| prompt.name == name) {
+                return Err(format!("prompt '{name}' is already defined by the server"));
+            }
+            if !seen.insert(name.clone()) {
+                return Err(format!("prompt '{name}' is declared more than once"));
+            }
+            prompts.push(declare_prompt(spec).map_err(|e| format!("prompt '{name}': {e}"))?);
+        }
+
+        let mut seen = HashSet::new();
+        let mut resources = Vec::with_capacity(manifest.resources.len());
+        for spec in manifest.resources {
+            let uri = spec.uri.clone();
+            if resources::read_resource(&uri, InlineLimit::default()).is_ok() {
+                return Err(format!("resource '{uri}' is already defined by the server"));
+            }
+            if !seen.insert(uri.clone()) {
+                return Err(format!("resource '{uri}' is declared more than once"));
+            }
+            resources.push(declare_resource(spec).map_err(|e| format!("resource '{uri}': {e}"))?);
+        }
+
+        Ok(Self {
+            tools,
+            prompts,
+            resources,
+            sandbox: Arc::new(sandbox),
+        })
+    }
+
+    /// Load the manifest at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first problem with the file or a
+    /// declaration.
+    pub fn load(path: &Path, reserved: &HashSet<String>, sandbox: Sandbox) -> Result<Self, String> {
+        Self::new(Manifest::load(path)?, reserved, sandbox)
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Load the manifest at `path` again, in the same sandbox as this one.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first problem with the file or a
+    /// declaration.
+    pub fn reload(&self, path: &Path, reserved: &HashSet<String>) -> Result<Self, String> {
+        Self::load(path, reserved, Sandbox::clone(&self.sandbox))
+    }
+
+    /// Load the manifest named by `MCP_TOOL_MANIFEST`, if set, with command
+    /// tools sandboxed by `MCP_COMMAND_DIRS`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first problem with the file or a
+    /// declaration.
+    pub fn from_env(reserved: &HashSet<String>) -> Result<Self, String> {
+        path_from_env().map_or_else(
+            || Ok(Self::default()),
+            |path| Self::load(&path, reserved, Sandbox::from_env()?),
+        )
+    }
+
+    /// The declared tools, in manifest order.
+    pub fn tools(&self) -> impl Iterator<Item = &Tool> {
+        self.tools.iter().map(|declared| &declared.tool)
+    }
+
+    /// The declared tool called `name`.
+    #[must_use]
+    pub fn tool(&self, name: &str) -> Option<&Tool> {
+        self.tools().find(|tool| tool.name == name)
+    }
+
+    /// The compiled input schema of the declared tool called `name`.
+    #[must_use]
+    pub fn validator(&self, name: &str) -> Option<Arc<Validator>> {
+        self.tools
+            .iter()
+            .find(|declared| declared.tool.name == name)
+            .map(|declared| Arc::clone(&declared.validator))
+    }
+
+    /// Call the declared tool `name` with already-validated `arguments`,
+    /// within the client's `roots`, reporting command output lines to
+    /// `progress` if given.
+    pub async fn call(
+        &self,
+        name: &str,
+        arguments: &JsonObject,
+        roots: &Roots,
+        progress: Option<Progress>,
+    ) -> ToolResponse {
+        let Some(declared) = self
+            .tools
+            .iter()
+            .find(|declared| declared.tool.name == name)
+        else {
+            return ToolError::internal(format!("Tool '{name}' is not declared")).into();
+        };
+        match &declared.run {
+            Implementation::Template { template } => {
+                CallToolResult::success(vec![Content::text(render(template, arguments))]).into()
+            }
+            Implementation::Command(spec) => {
+                command::run(spec, arguments, &self.sandbox, roots, progress)
+                    .await
+                    .into()
+            }
+        }
+    }
+
+    /// The declared prompts, in manifest order.
+    pub fn prompts(&self) -> impl Iterator<Item = &Prompt> {
+        self.prompts.iter().map(|declared| &declared.prompt)
+    }
+
+    /// Fill in the declared prompt `name`, or `None` if there is none.
+    ///
+    /// # Errors
+    ///
+    /// `invalid_params` if a required argument is missing.
+    #[must_use]
+    #[allow(clippy::implicit_hasher)]
+    pub fn get_prompt(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Option<Result<GetPromptResult, McpError>> {
+        let declared = self.prompts.iter().find(|p| p.prompt.name == name)?;
+        if let Some(missing) = declared
+            .spec
+            .arguments
+            .iter()
+            .find(|argument| argument.required && !arguments.contains_key(&argument.name))
+        {
+            return Some(Err(McpError::invalid_params(
+                format!("Missing required '{}' argument", missing.name),
+                None,
+            )));
+        }
+        let arguments: JsonObject = arguments
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::from(value.as_str())))
+            .collect();
+        let messages = declared
+            .spec
+            .messages
+            .iter()
+            .map(|message| {
+                PromptMessage::new_text(message.role.clone(), render(&message.text, &arguments))
+            })
+            .collect();
+        let mut result = GetPromptResult::new(messages);
+        if let Some(description) = &declared.spec.description {
+            result = result.with_description(description);
+        }
+        Some(Ok(result))
+    }
+
+    /// The declared resources, in manifest order.
+    pub fn resources(&self) -> impl Iterator<Item = &Resource> {
+        self.resources.iter().map(|declared| &declared.resource)
+    }
+
+    /// The contents of the declared resource at `uri`, if there is one.
+    #[must_use]
+    pub fn read_resource(&self, uri: &str) -> Option<ReadResourceResult> {
+        let declared = self.resources.iter().find(|r| r.resource.uri == uri)?;
+        Some(ReadResourceResult::new(vec![ResourceContents::text(
+            declared.text.clone(),
+            uri,
+        )
+        .with_mime_type(
+            declared.resource.mime_type.clone().unwrap_or_default(),
+        )]))
+    }
+
+    /// The files declared resources were read from.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.resources
+            .iter()
+            .filter_map(|declared| declared.file.as_deref())
+    }
+}
+
+/// The manifest named by `MCP_TOOL_MANIFEST`, if set.
+#[must_use]
+pub fn path_from_env() -> Option<PathBuf> {
+    std::env::var_os("MCP_TOOL_MANIFEST")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+fn declare(spec: ToolSpec, sandbox: &Sandbox) -> Result<Declared, String> {
+    if spec.input_schema.get("type").and_then(Value::as_str) != Some("object") {
+        return Err("input_schema must have \"type\": \"object\"".into());
+    }
+    let validator = Validator::new(&spec.input_schema).map_err(|e| format!("input_schema: {e}"))?;
+    let annotations = ToolAnnotations::from_raw(
+        spec.title.clone(),
+        spec.annotations.read_only,
+        spec.annotations.destructive,
+        spec.annotations.idempotent,
+        spec.annotations.open_world,
+    );
+    let mut tool =
+        Tool::new(spec.name, spec.description, spec.input_schema).with_annotations(annotations);
+    if let Some(title) = spec.title {
+        tool = tool.with_title(title);
+    }
+    tool = tool.with_icons(declared_icons(spec.icon.as_deref(), icons::robot)?);
+    if let Some(millis) = spec.timeout_ms {
+        tool = tool.with_meta(tools::timeout(Duration::from_millis(millis)));
+    }
+    if let Implementation::Command(command) = &spec.run {
+        command.check(sandbox)?;
+        tool = tool.with_raw_output_schema(schema_for_type::<CommandOutput>());
+    }
+    Ok(Declared {
+        tool,
+        validator: Arc::new(validator),
+        run: spec.run,
+    })
+}
+
+fn declare_prompt(spec: PromptSpec) -> Result<DeclaredPrompt, String> {
+    if spec.messages.is_empty() {
+        return Err("at least one message is required".into());
+    }
+    let arguments = spec
+        .arguments
+        .iter()
+        .map(|argument| {
+            let mut declared = PromptArgument::new(&argument.name).with_required(argument.required);
+            if let Some(description) = &argument.description {
+                declared = declared.with_description(description);
+            }
+            declared
+        })
+        .collect::<Vec<_>>();
+    let mut prompt = Prompt::new(
+        &spec.name,
+        spec.description.as_deref(),
+        (!arguments.is_empty()).then_some(arguments),
+    );
+    if let Some(title) = &spec.title {
+        prompt = prompt.with_title(title);
+    }
+    prompt = prompt.with_icons(declared_icons(spec.icon.as_deref(), icons::speech)?);
+    Ok(DeclaredPrompt { prompt, spec })
+}
+
+fn declare_resource(spec: ResourceSpec) -> Result<DeclaredResource, String> {
+    if !spec.uri.contains("://") {
+        return Err("uri must have a scheme, like doc://name".into());
+    }
+    let text = match (spec.text, &spec.file) {
+        (Some(text), None) => text,
+        (None, Some(file)) => {
+            std::fs::read_to_string(file).map_err(|e| format!("{}: {e}", file.display()))?
+        }
+        _ => return Err("exactly one of text and file is required".into()),
+    };
+    let icons = declared_icons(spec.icon.as_deref(), || {
+        icons::for_mime_type(&spec.mime_type)
+    })?;
+    let resource = Resource::new(
+        RawResource {
+            uri: spec.uri,
+            name: spec.name,
+            title: spec.title,
+            description: spec.description,
+            mime_type: Some(spec.mime_type),
+            size: u32::try_from(text.len()).ok(),
+            icons: Some(icons),
+            meta: None,
+        },
+        None,
+    );
+    Ok(DeclaredResource {
+        resource,
+        text,
+        file: spec.file,
+    })
+}
+
+/// The built-in icon named `icon`, or the `fallback` when none is named.
+fn declared_icons(
+    icon: Option<&str>,
+    fallback: impl FnOnce() -> Vec<Icon>,
+) -> Result<Vec<Icon>, String> {
+    icon.map_or_else(
+        || Ok(fallback()),
+        |icon| icons::lookup(icon).ok_or_else(|| format!("unknown icon '{icon}'")),
+    )
+}
+
+/// Replace each `{{field}}` in `template` with the argument of that name.
+#[must_use]
+pub fn render(template: &str, arguments: &JsonObject) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let field = rest[start + 2..start + 2 + end].trim();
+        match arguments.get(field) {
+            Some(Value::String(text)) => rendered.push_str(text),
+            Some(value) => rendered.push_str(&value.to_string()),
+            None => {}
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
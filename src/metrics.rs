@@ -0,0 +1,225 @@
+//! # MCP Rust Starter - Metrics
+//!
+//! Prometheus metrics for MCP traffic. The server records tool calls,
+//! resource reads and prompt fetches as they pass through `ServerHandler`,
+//! and the transports add session and HTTP-level figures. Metrics are
+//! exposed in the Prometheus text format:
+//!
+//! - the HTTP binary serves them at `/metrics`
+//! - the stdio binary serves them on an optional side port (`MCP_METRICS_ADDR`)
+//!
+//! Label values are limited to names the server knows about (unknown tools,
+//! prompts and resources are recorded as `unknown`) so a misbehaving client
+//! cannot blow up metric cardinality.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{extract::State, http::header, response::IntoResponse, routing::get, Router};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+/// Label value used for names the server does not recognise.
+pub const UNKNOWN: &str = "unknown";
+
+/// Latency buckets for tool calls, in seconds.
+const TOOL_DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// All metrics collected by the server, backed by a dedicated registry.
+pub struct Metrics {
+    registry: Registry,
+    tool_calls: IntCounterVec,
+    tool_errors: IntCounterVec,
+    tool_duration: HistogramVec,
+    resource_reads: IntCounterVec,
+    prompt_fetches: IntCounterVec,
+    active_sessions: IntGauge,
+    http_responses: IntCounterVec,
+    rate_limited: IntCounterVec,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Create and register every metric.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a metric definition is invalid, which is a programming error.
+    #[must_use]
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let tool_calls = IntCounterVec::new(
+            Opts::new("mcp_tool_calls_total", "Tool calls handled, by tool"),
+            &["tool"],
+        )
+        .expect("valid metric");
+        let tool_errors = IntCounterVec::new(
+            Opts::new(
+                "mcp_tool_errors_total",
+                "Tool calls that failed or returned isError, by tool",
+            ),
+            &["tool"],
+        )
+        .expect("valid metric");
+        let tool_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mcp_tool_call_duration_seconds",
+                "Tool call latency, by tool",
+            )
+            .buckets(TOOL_DURATION_BUCKETS.to_vec()),
+            &["tool"],
+        )
+        .expect("valid metric");
+        let resource_reads = IntCounterVec::new(
+            Opts::new(
+                "mcp_resource_reads_total",
+                "Resource reads, by resource (or template) and outcome",
+            ),
+            &["resource", "outcome"],
+        )
+        .expect("valid metric");
+        let prompt_fetches = IntCounterVec::new(
+            Opts::new(
+                "mcp_prompt_fetches_total",
+                "Prompt fetches, by prompt and outcome",
+            ),
+            &["prompt", "outcome"],
+        )
+        .expect("valid metric");
+        let active_sessions = IntGauge::new("mcp_active_sessions", "Currently active MCP sessions")
+            .expect("valid metric");
+        let http_responses = IntCounterVec::new(
+            Opts::new(
+                "mcp_http_responses_total",
+                "HTTP responses, by request method and status code",
+            ),
+            &["method", "status"],
+        )
+        .expect("valid metric");
+        let rate_limited = IntCounterVec::new(
+            Opts::new(
+                "mcp_rate_limited_total",
+                "Tool calls rejected by a rate limit or the concurrency cap, by scope",
+            ),
+            &["scope"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(tool_calls.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(tool_errors.clone()),
+            Box::new(tool_duration.clone()),
+            Box::new(resource_reads.clone()),
+            Box::new(prompt_fetches.clone()),
+            Box::new(active_sessions.clone()),
+            Box::new(http_responses.clone()),
+            Box::new(rate_limited.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            tool_calls,
+            tool_errors,
+            tool_duration,
+            resource_reads,
+            prompt_fetches,
+            active_sessions,
+            http_responses,
+            rate_limited,
+        }
+    }
+
+    /// Record a completed tool call.
+    pub fn observe_tool_call(&self, tool: &str, failed: bool, elapsed: Duration) {
+        self.tool_calls.with_label_values(&[tool]).inc();
+        if failed {
+            self.tool_errors.with_label_values(&[tool]).inc();
+        }
+        self.tool_duration
+            .with_label_values(&[tool])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record a resource read.
+    pub fn observe_resource_read(&self, resource: &str, ok: bool) {
+        self.resource_reads
+            .with_label_values(&[resource, outcome(ok)])
+            .inc();
+    }
+
+    /// Record a prompt fetch.
+    pub fn observe_prompt_fetch(&self, prompt: &str, ok: bool) {
+        self.prompt_fetches
+            .with_label_values(&[prompt, outcome(ok)])
+            .inc();
+    }
+
+    /// Set the number of currently active sessions.
+    pub fn set_active_sessions(&self, sessions: usize) {
+        self.active_sessions
+            .set(i64::try_from(sessions).unwrap_or(i64::MAX));
+    }
+
+    /// Record an HTTP response.
+    pub fn observe_http_response(&self, method: &str, status: u16) {
+        self.http_responses
+            .with_label_values(&[method, &status.to_string()])
+            .inc();
+    }
+
+    /// Record a tool call rejected by a rate limit (`scope` as in [`crate::limits::Scope`]).
+    pub fn observe_rate_limited(&self, scope: &str) {
+        self.rate_limited.with_label_values(&[scope]).inc();
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        // Encoding into a Vec only fails for malformed metric families,
+        // which the registry never produces.
+        let _ = TextEncoder::new().encode(&self.registry.gather(), &mut buffer);
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+const fn outcome(ok: bool) -> &'static str {
+    if ok {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+/// Axum handler serving the Prometheus text format.
+pub async fn handler(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        metrics.render(),
+    )
+}
+
+/// Serve `/metrics` on its own port, for transports without an HTTP server.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound or the server fails.
+pub async fn serve(addr: SocketAddr, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(handler))
+        .with_state(metrics);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await
+}
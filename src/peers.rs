@@ -0,0 +1,90 @@
+//! # MCP Rust Starter - Connected Clients
+//!
+//! Every session registers its [`Peer`] once the client has initialized, so
+//! changes made outside a request (plugins loading, definitions reloading)
+//! can be announced to all of them with `list_changed` notifications, and
+//! resource updates to the sessions subscribed to them.
+//! Sessions whose transport has closed are dropped on the next broadcast.
+
+use std::sync::{Arc, Mutex};
+
+use rmcp::{model::ResourceUpdatedNotificationParam, Peer, RoleServer};
+
+use crate::store::Session;
+
+/// The live sessions of this process.
+#[derive(Default)]
+pub struct Peers {
+    peers: Mutex<Vec<(Peer<RoleServer>, Arc<Session>)>>,
+}
+
+impl Peers {
+    /// Remember an initialized session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub fn add(&self, peer: Peer<RoleServer>, session: Arc<Session>) {
+        self.peers.lock().unwrap().push((peer, session));
+    }
+
+    /// The sessions still connected, forgetting any that have closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn live(&self) -> Vec<Peer<RoleServer>> {
+        self.live_sessions()
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .collect()
+    }
+
+    /// The sessions still connected, with their state.
+    fn live_sessions(&self) -> Vec<(Peer<RoleServer>, Arc<Session>)> {
+        let mut peers = self.peers.lock().unwrap();
+        peers.retain(|(peer, _)| !peer.is_transport_closed());
+        peers.clone()
+    }
+
+    /// Tell every live session that the tool list changed.
+    pub async fn notify_tool_list_changed(&self) {
+        for peer in self.live() {
+            if let Err(e) = peer.notify_tool_list_changed().await {
+                tracing::debug!(error = %e, "Failed to send tools/list_changed");
+            }
+        }
+    }
+
+    /// Tell every live session that the prompt list changed.
+    pub async fn notify_prompt_list_changed(&self) {
+        for peer in self.live() {
+            if let Err(e) = peer.notify_prompt_list_changed().await {
+                tracing::debug!(error = %e, "Failed to send prompts/list_changed");
+            }
+        }
+    }
+
+    /// Tell every live session that the resource list changed.
+    pub async fn notify_resource_list_changed(&self) {
+        for peer in self.live() {
+            if let Err(e) = peer.notify_resource_list_changed().await {
+                tracing::debug!(error = %e, "Failed to send resources/list_changed");
+            }
+        }
+    }
+
+    /// Tell every live session subscribed to `uri` that it was updated.
+    pub async fn notify_resource_updated(&self, uri: &str) {
+        for (peer, session) in self.live_sessions() {
+            if !session.data().subscriptions.contains(uri) {
+                continue;
+            }
+            let params = ResourceUpdatedNotificationParam::new(uri);
+            if let Err(e) = peer.notify_resource_updated(params).await {
+                tracing::debug!(error = %e, "Failed to send resources/updated");
+            }
+        }
+    }
+}
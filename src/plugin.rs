@@ -0,0 +1,646 @@
+//! # MCP Rust Starter - WebAssembly Plugins
+//!
+//! Third parties can ship tools as WebAssembly modules dropped into
+//! `MCP_PLUGIN_DIR`, without recompiling the server. Each `.wasm` (or `.wat`)
+//! file is one plugin, named after the file. The directory is watched, and
+//! rescanned once a burst of file events has settled: new and changed files
+//! are (re)loaded, deleted ones unloaded, and every live session gets
+//! `notifications/tools/list_changed`.
+//!
+//! ## Plugin interface
+//!
+//! Plugins are core WebAssembly modules that import nothing (so they have no
+//! access to the host beyond their arguments) and exchange JSON through
+//! their linear memory. Results are returned as a pointer and length packed
+//! into an `i64` (`ptr << 32 | len`).
+//!
+//! | Export | Signature | Purpose |
+//! |---|---|---|
+//! | `memory` | memory | Linear memory holding all strings |
+//! | `alloc` | `(len: i32) -> i32` | Reserve `len` bytes for the host to write input into |
+//! | `tools` | `() -> i64` | JSON array of MCP tool definitions (`name`, `description`, `inputSchema`, `annotations`, …) |
+//! | `call` | `(name_ptr, name_len, args_ptr, args_len: i32) -> i64` | JSON `CallToolResult` for a call with JSON arguments |
+//!
+//! A tool defined without `icons` is listed with the package icon.
+//!
+//! Every call runs in a fresh instance, so plugins keep no state between
+//! calls, with fuel (roughly one unit per instruction) and memory limits.
+//! A call that is given up on, because it timed out or the client cancelled
+//! it, is interrupted within [`EPOCH_TICK`] rather than left running until
+//! its fuel runs out. A trap, running out of fuel or memory, or an invalid
+//! result is returned as an `internal` tool error.
+//!
+//! ## Configuration
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_PLUGIN_DIR` | Directory of plugin modules | disabled |
+//! | `MCP_PLUGIN_FUEL` | Fuel per call | 1000000000 |
+//! | `MCP_PLUGIN_MEMORY_MB` | Linear memory per call, in MiB | 64 |
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    time::{Duration, SystemTime},
+};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use rmcp::model::{CallToolResult, JsonObject, Tool};
+use tokio::sync::{mpsc, Notify};
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    UpdateDeadline,
+};
+
+use crate::{
+    icons,
+    peers::Peers,
+    reload,
+    tools::{ToolError, ToolResponse},
+    validation::Validator,
+    McpServer, ServerState,
+};
+
+/// How often running guests check whether their call was given up on.
+pub const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Per-call resource limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginLimits {
+    pub fuel: u64,
+    pub memory_bytes: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000_000,
+            memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl PluginLimits {
+    /// Read `MCP_PLUGIN_FUEL` and `MCP_PLUGIN_MEMORY_MB`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invalid variable.
+    pub fn from_env() -> Result<Self, String> {
+        let mut limits = Self::default();
+        if let Ok(fuel) = std::env::var("MCP_PLUGIN_FUEL") {
+            limits.fuel = fuel
+                .parse()
+                .map_err(|e| format!("MCP_PLUGIN_FUEL={fuel}: {e}"))?;
+        }
+        if let Ok(value) = std::env::var("MCP_PLUGIN_MEMORY_MB") {
+            let megabytes: usize = value
+                .parse()
+                .map_err(|e| format!("MCP_PLUGIN_MEMORY_MB={value}: {e}"))?;
+            limits.memory_bytes = megabytes
+                .checked_mul(1024 * 1024)
+                .ok_or_else(|| format!("MCP_PLUGIN_MEMORY_MB={value}: too large"))?;
+        }
+        Ok(limits)
+    }
+}
+
+/// If `MCP_PLUGIN_DIR` is set, load its plugins into `state` and keep
+/// watching it. Plugin tools may not reuse compiled or manifest tool names,
+/// as of the manifest current at each rescan.
+pub fn watch_from_env(state: &ServerState) -> Option<tokio::task::JoinHandle<()>> {
+    let dir = PathBuf::from(std::env::var_os("MCP_PLUGIN_DIR").filter(|dir| !dir.is_empty())?);
+    let manifest = state.manifest.clone();
+    let reserved = move || {
+        let mut reserved = McpServer::builtin_tool_names();
+        reserved.extend(manifest.load().tools().map(|tool| tool.name.to_string()));
+        reserved
+    };
+    tracing::info!(dir = %dir.display(), "Loading plugins");
+    state.plugins.sync(&dir, &reserved());
+    match state
+        .plugins
+        .clone()
+        .watch(dir, reserved, state.peers.clone(), reload::SETTLE)
+    {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            tracing::error!(error = %e, "Cannot watch the plugin directory for changes");
+            None
+        }
+    }
+}
+
+struct Plugin {
+    name: String,
+    modified: Option<SystemTime>,
+    module: Module,
+    tools: Vec<Tool>,
+    /// Each tool's compiled input schema, by tool name.
+    validators: BTreeMap<String, Arc<Validator>>,
+}
+
+/// The loaded plugins and the engine that runs them.
+#[derive(Default)]
+pub struct PluginHost {
+    engine: OnceLock<Engine>,
+    limits: PluginLimits,
+    plugins: RwLock<BTreeMap<String, Arc<Plugin>>>,
+    /// Modification times of files that failed to load, so a broken
+    /// plugin is retried only once it changes.
+    rejected: Mutex<BTreeMap<String, Option<SystemTime>>>,
+    /// Wakes the watcher to rescan, retrying rejected plugins too.
+    rescan: Notify,
+}
+
+impl PluginHost {
+    /// A host with no plugins loaded yet.
+    #[must_use]
+    pub fn new(limits: PluginLimits) -> Self {
+        Self {
+            limits,
+            ..Self::default()
+        }
+    }
+
+    fn engine(&self) -> &Engine {
+        self.engine.get_or_init(|| {
+            let mut config = Config::new();
+            config.consume_fuel(true).epoch_interruption(true);
+            let engine = Engine::new(&config).expect("fuel metering is supported on every target");
+            // Advance the epoch, so guests check in, until the engine is dropped
+            let weak = engine.weak();
+            std::thread::spawn(move || {
+                while let Some(engine) = weak.upgrade() {
+                    engine.increment_epoch();
+                    drop(engine);
+                    std::thread::sleep(EPOCH_TICK);
+                }
+            });
+            engine
+        })
+    }
+
+    /// Load (or reload) the plugin at `path`. Its tools may not reuse a
+    /// name in `reserved` or one from another plugin.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of why the module or its tools were rejected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin lock was poisoned by a panic while held.
+    pub fn load(&self, path: &Path, reserved: &HashSet<String>) -> Result<Vec<String>, String> {
+        let name = plugin_name(path)?;
+        let modified = std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        let module = Module::from_file(self.engine(), path).map_err(|e| format!("{e:#}"))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "imports {}::{}, but plugins may not import anything",
+                import.module(),
+                import.name()
+            ));
+        }
+        let mut tools: Vec<Tool> =
+            serde_json::from_slice(&self.run(&module, &Cancel::default(), Guest::tools)?)
+                .map_err(|e| format!("invalid tool list: {e}"))?;
+        // Every listed tool carries an icon, even if its plugin has none
+        for tool in &mut tools {
+            if tool.icons.as_ref().is_none_or(Vec::is_empty) {
+                tool.icons = Some(icons::package());
+            }
+        }
+        let validators = tools
+            .iter()
+            .map(|tool| {
+                Validator::new(&tool.input_schema)
+                    .map(|validator| (tool.name.to_string(), Arc::new(validator)))
+                    .map_err(|e| format!("tool '{}' has an invalid input schema: {e}", tool.name))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut plugins = self.plugins.write().unwrap();
+        let mut taken: HashSet<String> = plugins
+            .values()
+            .filter(|plugin| plugin.name != name)
+            .flat_map(|plugin| plugin.tools.iter().map(|tool| tool.name.to_string()))
+            .chain(reserved.iter().cloned())
+            .collect();
+        if let Some(tool) = tools
+            .iter()
+            .find(|tool| !taken.insert(tool.name.to_string()))
+        {
+            return Err(format!("tool '{}' is already defined", tool.name));
+        }
+        let names = tools.iter().map(|tool| tool.name.to_string()).collect();
+        plugins.insert(
+            name.clone(),
+            Arc::new(Plugin {
+                name,
+                modified,
+                module,
+                tools,
+                validators,
+            }),
+        );
+        drop(plugins);
+        Ok(names)
+    }
+
+    /// Unload the plugin called `name`, returning whether it was loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin lock was poisoned by a panic while held.
+    pub fn unload(&self, name: &str) -> bool {
+        self.plugins.write().unwrap().remove(name).is_some()
+    }
+
+    /// Bring the loaded plugins in line with the files in `dir`: load new
+    /// and modified ones, unload removed ones. Returns whether anything
+    /// changed; load failures are logged and leave any previous version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin lock was poisoned by a panic while held.
+    pub fn sync(&self, dir: &Path, reserved: &HashSet<String>) -> bool {
+        self.scan(dir, reserved, false)
+    }
+
+    /// Ask the watcher to rescan the plugin directory, retrying plugins
+    /// that were rejected, e.g. because the manifest has freed a tool name
+    /// one of them wants.
+    pub fn rescan(&self) {
+        self.rescan.notify_one();
+    }
+
+    /// [`Self::sync`], skipping rejected files that have not changed
+    /// unless `retry` is set.
+    fn scan(&self, dir: &Path, reserved: &HashSet<String>, retry: bool) -> bool {
+        let files = match plugin_files(dir) {
+            Ok(files) => files,
+            Err(e) => {
+                tracing::error!(dir = %dir.display(), error = %e, "Failed to scan plugin directory");
+                return false;
+            }
+        };
+        let mut changed = false;
+
+        let loaded: Vec<(String, Option<SystemTime>)> = self
+            .plugins
+            .read()
+            .unwrap()
+            .values()
+            .map(|plugin| (plugin.name.clone(), plugin.modified))
+            .collect();
+        for (name, _) in &loaded {
+            if !files.contains_key(name) && self.unload(name) {
+                tracing::info!(plugin = %name, "Unloaded plugin");
+                changed = true;
+            }
+        }
+        for (name, candidates) in &files {
+            let modified = candidates
+                .iter()
+                .map(|(_, modified)| *modified)
+                .max()
+                .flatten();
+            let current = loaded.iter().find(|(loaded, _)| loaded == name);
+            let rejected = !retry && self.rejected.lock().unwrap().get(name) == Some(&modified);
+            let unchanged =
+                candidates.len() == 1 && current.is_some_and(|(_, loaded)| *loaded == modified);
+            if rejected || unchanged {
+                continue;
+            }
+            let result = match candidates.as_slice() {
+                [(path, _)] => self.load(path, reserved),
+                _ => Err(format!(
+                    "{} are the same plugin; remove all but one",
+                    candidates
+                        .iter()
+                        .map(|(path, _)| path.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(" and ")
+                )),
+            };
+            match result {
+                Ok(tools) => {
+                    tracing::info!(plugin = %name, ?tools, "Loaded plugin");
+                    self.rejected.lock().unwrap().remove(name);
+                    changed = true;
+                }
+                Err(e) => {
+                    tracing::error!(plugin = %name, error = %e, "Failed to load plugin");
+                    self.rejected.lock().unwrap().insert(name.clone(), modified);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Rescan `dir` whenever its files change, once events have stopped for
+    /// `settle`, or [`Self::rescan`] is called, announcing changes to
+    /// `peers`. Each rescan rejects the tool names `reserved` returns then.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of why the directory cannot be watched.
+    pub fn watch(
+        self: Arc<Self>,
+        dir: PathBuf,
+        reserved: impl Fn() -> HashSet<String> + Send + 'static,
+        peers: Arc<Peers>,
+        settle: Duration,
+    ) -> Result<tokio::task::JoinHandle<()>, String> {
+        let (sender, mut events) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // The receiver only goes away when the watch is stopped
+            let _ = sender.send(event);
+        })
+        .map_err(|e| e.to_string())?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("{}: {e}", dir.display()))?;
+
+        Ok(tokio::spawn(async move {
+            // Dropping the watcher would end the events
+            let _watcher = watcher;
+            loop {
+                let retry = tokio::select! {
+                    event = events.recv() => match event {
+                        None => break,
+                        Some(Ok(event)) if matches!(event.kind, EventKind::Access(_)) => continue,
+                        Some(Ok(_)) => false,
+                        Some(Err(e)) => {
+                            tracing::warn!(error = %e, "Error watching the plugin directory");
+                            continue;
+                        }
+                    },
+                    () = self.rescan.notified() => true,
+                };
+                if !retry {
+                    // Copying a module in is often several events; wait for them all
+                    while let Ok(Some(_)) = tokio::time::timeout(settle, events.recv()).await {}
+                }
+                let host = self.clone();
+                let (dir_, reserved_) = (dir.clone(), reserved());
+                let changed =
+                    tokio::task::spawn_blocking(move || host.scan(&dir_, &reserved_, retry))
+                        .await
+                        .unwrap_or(false);
+                if changed {
+                    peers.notify_tool_list_changed().await;
+                }
+            }
+        }))
+    }
+
+    /// The plugins whose current file failed to load, by name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn rejected(&self) -> Vec<String> {
+        self.rejected.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// The tools of every loaded plugin, ordered by plugin name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn tools(&self) -> Vec<Tool> {
+        self.plugins
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|plugin| plugin.tools.iter().cloned())
+            .collect()
+    }
+
+    /// The plugin tool called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Tool> {
+        self.find(name).map(|(_, tool)| tool)
+    }
+
+    /// The compiled input schema of the plugin tool called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn validator(&self, name: &str) -> Option<Arc<Validator>> {
+        self.plugins
+            .read()
+            .unwrap()
+            .values()
+            .find_map(|plugin| plugin.validators.get(name).cloned())
+    }
+
+    fn find(&self, name: &str) -> Option<(Arc<Plugin>, Tool)> {
+        self.plugins.read().unwrap().values().find_map(|plugin| {
+            plugin
+                .tools
+                .iter()
+                .find(|tool| tool.name == name)
+                .map(|tool| (plugin.clone(), tool.clone()))
+        })
+    }
+
+    /// Call the plugin tool `name` with already-validated `arguments`.
+    pub async fn call(self: &Arc<Self>, name: &str, arguments: &JsonObject) -> ToolResponse {
+        let Some((plugin, _)) = self.find(name) else {
+            return ToolError::internal(format!("Tool '{name}' is not provided by a plugin"))
+                .into();
+        };
+        let host = self.clone();
+        let tool = name.to_string();
+        let arguments = serde_json::to_vec(arguments).unwrap_or_default();
+        // Guest code is CPU-bound and runs on the blocking pool; if this
+        // future is dropped (on timeout or cancellation), the guard tells
+        // the guest to stop at its next epoch check
+        let cancel = Cancel::default();
+        let _guard = cancel.on_drop();
+        let output = tokio::task::spawn_blocking(move || {
+            host.run(&plugin.module, &cancel, |guest, store| {
+                guest.call(store, tool.as_bytes(), &arguments)
+            })
+            .map_err(|e| format!("Plugin '{}' failed: {e}", plugin.name))
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|output| output);
+        let result = output.and_then(|output| {
+            let mut result = serde_json::from_slice::<CallToolResult>(&output)
+                .map_err(|e| format!("Plugin tool '{name}' returned an invalid result: {e}"))?;
+            result.is_error.get_or_insert(false);
+            Ok(result)
+        });
+        result.map_err(ToolError::internal).into()
+    }
+
+    /// Instantiate `module` under the call limits and run `f` against it,
+    /// until it returns or `cancel` is set.
+    fn run(
+        &self,
+        module: &Module,
+        cancel: &Cancel,
+        f: impl FnOnce(&Guest, &mut Store<StoreLimits>) -> wasmtime::Result<Vec<u8>>,
+    ) -> Result<Vec<u8>, String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.memory_bytes)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(self.engine(), limits);
+        store.limiter(|limits| limits);
+        store
+            .set_fuel(self.limits.fuel)
+            .map_err(|e| e.to_string())?;
+        let cancel = cancel.clone();
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| {
+            Ok(if cancel.is_set() {
+                UpdateDeadline::Interrupt
+            } else {
+                UpdateDeadline::Continue(1)
+            })
+        });
+        Instance::new(&mut store, module, &[])
+            .and_then(|instance| Guest::new(&mut store, &instance))
+            .and_then(|guest| f(&guest, &mut store))
+            .map_err(|e| match e.downcast_ref::<Trap>() {
+                Some(Trap::OutOfFuel) => format!("ran out of fuel ({} units)", self.limits.fuel),
+                Some(Trap::Interrupt) => "was cancelled".to_string(),
+                _ => format!("{e:#}"),
+            })
+    }
+}
+
+/// Set when a call is given up on, so its guest stops.
+#[derive(Clone, Default)]
+struct Cancel(Arc<AtomicBool>);
+
+impl Cancel {
+    fn is_set(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// A guard that sets this when dropped.
+    fn on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+struct CancelOnDrop(Cancel);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0 .0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The exports of an instantiated plugin.
+struct Guest {
+    memory: Memory,
+    alloc: wasmtime::TypedFunc<i32, i32>,
+    tools: wasmtime::TypedFunc<(), i64>,
+    call: wasmtime::TypedFunc<(i32, i32, i32, i32), i64>,
+}
+
+impl Guest {
+    fn new(store: &mut Store<StoreLimits>, instance: &Instance) -> wasmtime::Result<Self> {
+        Ok(Self {
+            memory: instance
+                .get_memory(&mut *store, "memory")
+                .ok_or_else(|| wasmtime::Error::msg("no exported memory"))?,
+            alloc: instance.get_typed_func(&mut *store, "alloc")?,
+            tools: instance.get_typed_func(&mut *store, "tools")?,
+            call: instance.get_typed_func(&mut *store, "call")?,
+        })
+    }
+
+    fn tools(&self, store: &mut Store<StoreLimits>) -> wasmtime::Result<Vec<u8>> {
+        let packed = self.tools.call(&mut *store, ())?;
+        self.read(store, packed)
+    }
+
+    fn call(
+        &self,
+        store: &mut Store<StoreLimits>,
+        name: &[u8],
+        arguments: &[u8],
+    ) -> wasmtime::Result<Vec<u8>> {
+        let name = self.write(store, name)?;
+        let arguments = self.write(store, arguments)?;
+        let packed = self
+            .call
+            .call(&mut *store, (name.0, name.1, arguments.0, arguments.1))?;
+        self.read(store, packed)
+    }
+
+    fn write(&self, store: &mut Store<StoreLimits>, bytes: &[u8]) -> wasmtime::Result<(i32, i32)> {
+        let len = i32::try_from(bytes.len())?;
+        let ptr = self.alloc.call(&mut *store, len)?;
+        self.memory
+            .write(&mut *store, usize::try_from(ptr)?, bytes)?;
+        Ok((ptr, len))
+    }
+
+    fn read(&self, store: &Store<StoreLimits>, packed: i64) -> wasmtime::Result<Vec<u8>> {
+        #[allow(clippy::cast_sign_loss)]
+        let packed = packed as u64;
+        let ptr = usize::try_from(packed >> 32)?;
+        let len = usize::try_from(packed & 0xffff_ffff)?;
+        self.memory
+            .data(store)
+            .get(ptr..ptr + len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| wasmtime::Error::msg(format!("result at {ptr}+{len} is outside memory")))
+    }
+}
+
+fn plugin_name(path: &Path) -> Result<String, String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("{}: not a plugin file name", path.display()))
+}
+
+/// A plugin file and when it was last modified.
+type PluginFile = (PathBuf, Option<SystemTime>);
+
+/// The `.wasm` and `.wat` files in `dir`, by plugin name: more than one if
+/// both `foo.wasm` and `foo.wat` exist.
+fn plugin_files(dir: &Path) -> std::io::Result<BTreeMap<String, Vec<PluginFile>>> {
+    let mut files: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_plugin = path
+            .extension()
+            .is_some_and(|ext| ext == "wasm" || ext == "wat");
+        if let (true, Ok(name)) = (is_plugin, plugin_name(&path)) {
+            let modified = std::fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok();
+            files.entry(name).or_default().push((path, modified));
+        }
+    }
+    for candidates in files.values_mut() {
+        candidates.sort();
+    }
+    Ok(files)
+}
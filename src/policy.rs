@@ -0,0 +1,355 @@
+//! # MCP Rust Starter - Safety Policy
+//!
+//! Rules that act on tool annotations before a tool runs. Annotations are
+//! hints to clients, but a server that declares a tool destructive or
+//! open-world can also hold itself to that declaration.
+//!
+//! Each rule is `<action> <target> [unauthenticated]`:
+//!
+//! - action `deny` refuses matching calls; `confirm` asks the user to confirm
+//!   them through elicitation first, and refuses them if the user declines or
+//!   the client cannot ask
+//! - target `destructive`, `open_world`, `mutating` (anything not read-only)
+//!   or `any`, judged by the tool's annotations with the MCP defaults for
+//!   unset hints
+//! - `unauthenticated` limits the rule to calls without an authenticated
+//!   principal (see [`crate::identity`]), which includes every stdio call
+//!
+//! Deny rules win over confirm rules. Every call a rule matches is logged
+//! with the rule and the decision.
+//!
+//! ## Configuration
+//!
+//! | variable      | meaning |
+//! |---------------|---------|
+//! | `MCP_POLICY`  | comma-separated rules, e.g. `deny destructive unauthenticated, confirm destructive` (default: none) |
+//! | `MCP_OFFLINE` | `true` adds `deny open_world` |
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use rmcp::{
+    model::Tool,
+    service::{ElicitationError, RequestContext},
+    RoleServer,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identity,
+    tools::{ToolError, ToolErrorCode},
+};
+
+/// How long the user has to answer a confirmation request.
+const CONFIRM_TIMEOUT: Duration = Duration::from_mins(2);
+
+/// What a rule does to the calls it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Deny,
+    Confirm,
+}
+
+/// Which tools a rule matches, by annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Destructive,
+    OpenWorld,
+    Mutating,
+    Any,
+}
+
+impl Target {
+    /// Whether `tool` falls under this target.
+    #[must_use]
+    pub fn matches(self, tool: &Tool) -> bool {
+        match self {
+            Self::Destructive => is_destructive(tool),
+            Self::OpenWorld => is_open_world(tool),
+            Self::Mutating => !is_read_only(tool),
+            Self::Any => true,
+        }
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Destructive => "destructive",
+            Self::OpenWorld => "open_world",
+            Self::Mutating => "mutating",
+            Self::Any => "any",
+        }
+    }
+}
+
+/// One policy rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub action: Action,
+    pub target: Target,
+    /// Only apply to calls without an authenticated principal.
+    pub unauthenticated_only: bool,
+}
+
+impl Rule {
+    /// A rule applying to every caller.
+    #[must_use]
+    pub const fn new(action: Action, target: Target) -> Self {
+        Self {
+            action,
+            target,
+            unauthenticated_only: false,
+        }
+    }
+
+    /// Restrict this rule to unauthenticated callers.
+    #[must_use]
+    pub const fn unauthenticated(mut self) -> Self {
+        self.unauthenticated_only = true;
+        self
+    }
+
+    fn matches(self, tool: &Tool, authenticated: bool) -> bool {
+        !(self.unauthenticated_only && authenticated) && self.target.matches(tool)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let (action, target, unauthenticated_only) = match words.as_slice() {
+            [action, target] => (action, target, false),
+            [action, target, "unauthenticated"] => (action, target, true),
+            _ => {
+                return Err(format!(
+                    "expected '<action> <target> [unauthenticated]', got '{s}'"
+                ))
+            }
+        };
+        let action = match *action {
+            "deny" => Action::Deny,
+            "confirm" => Action::Confirm,
+            other => {
+                return Err(format!(
+                    "unknown action '{other}' (expected deny or confirm)"
+                ))
+            }
+        };
+        let target = match *target {
+            "destructive" => Target::Destructive,
+            "open_world" => Target::OpenWorld,
+            "mutating" => Target::Mutating,
+            "any" => Target::Any,
+            other => {
+                return Err(format!(
+                    "unknown target '{other}' (expected destructive, open_world, mutating or any)"
+                ))
+            }
+        };
+        Ok(Self {
+            action,
+            target,
+            unauthenticated_only,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            Action::Deny => "deny",
+            Action::Confirm => "confirm",
+        };
+        write!(f, "{action} {}", self.target.as_str())?;
+        if self.unauthenticated_only {
+            f.write_str(" unauthenticated")?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of evaluating the policy for one call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// No rule matched.
+    Allow,
+    /// A deny rule matched.
+    Deny(Rule),
+    /// A confirm rule matched, and no deny rule did.
+    Confirm(Rule),
+}
+
+impl Decision {
+    /// The decision as logged.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny(_) => "deny",
+            Self::Confirm(_) => "confirm",
+        }
+    }
+}
+
+/// The configured rules. The default has none and allows every call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    /// A policy with the given rules.
+    #[must_use]
+    pub const fn new(rules: Vec<Rule>) -> Self {
+        Self { rules }
+    }
+
+    /// Read the policy from `MCP_POLICY` and `MCP_OFFLINE`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first invalid setting.
+    pub fn from_env() -> Result<Self, String> {
+        let mut rules = std::env::var("MCP_POLICY")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| rule.parse().map_err(|e| format!("MCP_POLICY: {e}")))
+            .collect::<Result<Vec<Rule>, _>>()?;
+        if let Ok(offline) = std::env::var("MCP_OFFLINE") {
+            match offline.as_str() {
+                "1" | "true" => rules.push(Rule::new(Action::Deny, Target::OpenWorld)),
+                "" | "0" | "false" => {}
+                other => {
+                    return Err(format!(
+                        "MCP_OFFLINE: expected true or false, got '{other}'"
+                    ))
+                }
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    /// Decide what to do with a call to `tool`.
+    #[must_use]
+    pub fn evaluate(&self, tool: &Tool, authenticated: bool) -> Decision {
+        let matching = || {
+            self.rules
+                .iter()
+                .filter(move |rule| rule.matches(tool, authenticated))
+                .copied()
+        };
+        matching()
+            .find(|rule| rule.action == Action::Deny)
+            .map(Decision::Deny)
+            .or_else(|| matching().next().map(Decision::Confirm))
+            .unwrap_or(Decision::Allow)
+    }
+
+    /// Evaluate the policy for a call and act on the decision, asking the
+    /// user through `context` when confirmation is required.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `policy_denied` tool error if the call may not proceed.
+    pub async fn authorize(
+        &self,
+        tool: &Tool,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<(), ToolError> {
+        let principal = identity::principal(context);
+        let decision = self.evaluate(tool, principal.is_some());
+        let (Decision::Deny(rule) | Decision::Confirm(rule)) = decision else {
+            return Ok(());
+        };
+        let rule = rule.to_string();
+        tracing::info!(
+            tool = %tool.name,
+            principal,
+            rule,
+            decision = decision.as_str(),
+            "Policy decision"
+        );
+        if let Decision::Deny(_) = decision {
+            return Err(denied(
+                tool,
+                format!("Tool '{}' is not allowed by policy ({rule})", tool.name),
+                &rule,
+            ));
+        }
+
+        let answer = context
+            .peer
+            .elicit_with_timeout::<Confirmation>(
+                format!("Allow the tool '{}' to run?", tool.name),
+                Some(CONFIRM_TIMEOUT),
+            )
+            .await;
+        let refusal = match answer {
+            Ok(Some(Confirmation { confirm: true })) => None,
+            Ok(_) | Err(ElicitationError::UserDeclined | ElicitationError::UserCancelled) => {
+                Some("the user did not confirm it".to_string())
+            }
+            Err(ElicitationError::CapabilityNotSupported) => {
+                Some("the client cannot ask the user to confirm it".to_string())
+            }
+            Err(e) => Some(format!("confirmation failed: {e}")),
+        };
+        tracing::info!(
+            tool = %tool.name,
+            rule,
+            confirmed = refusal.is_none(),
+            "Policy confirmation"
+        );
+        refusal.map_or(Ok(()), |reason| {
+            Err(denied(
+                tool,
+                format!("Tool '{}' requires confirmation, but {reason}", tool.name),
+                &rule,
+            ))
+        })
+    }
+}
+
+/// The form shown to the user by a `confirm` rule.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Confirmation {
+    /// Whether to let the tool run
+    pub confirm: bool,
+}
+
+rmcp::elicit_safe!(Confirmation);
+
+fn denied(tool: &Tool, message: String, rule: &str) -> ToolError {
+    ToolError::new(ToolErrorCode::PolicyDenied, message)
+        .with_detail("tool", tool.name.to_string())
+        .with_detail("rule", rule)
+}
+
+fn is_read_only(tool: &Tool) -> bool {
+    tool.annotations
+        .as_ref()
+        .and_then(|annotations| annotations.read_only_hint)
+        .unwrap_or(false)
+}
+
+/// Destructive unless read-only or explicitly not destructive, as the MCP
+/// specification defaults it.
+fn is_destructive(tool: &Tool) -> bool {
+    !is_read_only(tool)
+        && tool
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.destructive_hint)
+            .unwrap_or(true)
+}
+
+/// Open-world unless explicitly not, as the MCP specification defaults it.
+fn is_open_world(tool: &Tool) -> bool {
+    tool.annotations
+        .as_ref()
+        .and_then(|annotations| annotations.open_world_hint)
+        .unwrap_or(true)
+}
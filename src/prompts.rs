@@ -18,6 +18,8 @@ use rmcp::{
     ErrorData as McpError,
 };
 
+use crate::icons;
+
 /// Returns the list of available prompts.
 ///
 /// # Errors
@@ -38,7 +40,8 @@ pub fn list_prompts() -> Result<ListPromptsResult, McpError> {
                     .with_required(false),
             ]),
         )
-        .with_title("Greeting Prompt"),
+        .with_title("Greeting Prompt")
+        .with_icons(icons::waving_hand()),
         Prompt::new(
             "code_review",
             Some("Review code for potential improvements"),
@@ -46,7 +49,8 @@ pub fn list_prompts() -> Result<ListPromptsResult, McpError> {
                 .with_description("The code to review")
                 .with_required(true)]),
         )
-        .with_title("Code Review"),
+        .with_title("Code Review")
+        .with_icons(icons::thought_balloon()),
     ];
 
     Ok(ListPromptsResult {
@@ -79,6 +83,17 @@ pub fn get_prompt(
     }
 }
 
+/// A bounded label for a prompt name: the name itself if it is known,
+/// otherwise `unknown`.
+#[must_use]
+pub fn label(prompt_name: &str) -> &'static str {
+    match prompt_name {
+        "greet" => "greet",
+        "code_review" => "code_review",
+        _ => crate::metrics::UNKNOWN,
+    }
+}
+
 fn greet_prompt(args: &HashMap<String, String>) -> Result<GetPromptResult, McpError> {
     let name = args.get("name").ok_or_else(|| {
         McpError::invalid_params("Missing required 'name' argument".to_string(), None)
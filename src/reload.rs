@@ -0,0 +1,209 @@
+//! # MCP Rust Starter - Hot Reload
+//!
+//! The manifest named by `MCP_TOOL_MANIFEST` and the resource files it
+//! declares are watched for changes, so editing them does not need a
+//! restart. Once a burst of file events has settled, the manifest is loaded
+//! and validated again. Only if that succeeds is the new [`Catalog`] swapped
+//! in, in a single step: a request sees either the old definitions or the new
+//! ones, never a mix. A manifest that fails to load is logged and the
+//! previous definitions stay in place until it is fixed.
+//!
+//! After a swap, every live session gets `notifications/tools/list_changed`,
+//! `notifications/prompts/list_changed` and/or
+//! `notifications/resources/list_changed`, for the lists that changed.
+//! Sessions subscribed to a declared resource whose contents changed also
+//! get `notifications/resources/updated` for it.
+//!
+//! Directories are watched rather than the files themselves, so editors that
+//! save by renaming a new file over the old one are noticed too.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::{
+    manifest::{self, Catalog},
+    McpServer, ServerState,
+};
+
+/// How long file events must stop for before [`watch_from_env`] reloads.
+pub const SETTLE: Duration = Duration::from_millis(200);
+
+/// If `MCP_TOOL_MANIFEST` is set, keep `state` in step with it.
+pub fn watch_from_env(state: &ServerState) -> Option<tokio::task::JoinHandle<()>> {
+    let path = manifest::path_from_env()?;
+    match watch(&path, state.clone(), SETTLE) {
+        Ok(handle) => Some(handle),
+        Err(e) => {
+            tracing::error!(error = %e, "Cannot watch the tool manifest for changes");
+            None
+        }
+    }
+}
+
+/// Reload the manifest at `path` into `state` whenever it or one of its
+/// resource files changes, once events have stopped for `settle`.
+///
+/// # Errors
+///
+/// Returns a description of why the files cannot be watched.
+pub fn watch(
+    path: &Path,
+    state: ServerState,
+    settle: Duration,
+) -> Result<tokio::task::JoinHandle<()>, String> {
+    let path = std::path::absolute(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let (events, mut changes) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        // The receiver only goes away when the watch is stopped
+        let _ = events.send(event);
+    })
+    .map_err(|e| e.to_string())?;
+    let mut dirs = BTreeSet::new();
+    let mut files = watched_files(&path, &state.manifest.load_full());
+    watch_dirs(&mut watcher, &mut dirs, &files)?;
+
+    Ok(tokio::spawn(async move {
+        while let Some(event) = changes.recv().await {
+            if !concerns(&event, &files) {
+                continue;
+            }
+            // One save is often several events; wait for them all
+            while let Ok(Some(_)) = tokio::time::timeout(settle, changes.recv()).await {}
+            if let Err(e) = reload(&path, &state).await {
+                tracing::error!(
+                    error = %e,
+                    "Failed to reload the tool manifest; keeping the previous definitions"
+                );
+            }
+            files = watched_files(&path, &state.manifest.load_full());
+            if let Err(e) = watch_dirs(&mut watcher, &mut dirs, &files) {
+                tracing::warn!(error = %e, "Cannot watch a manifest resource file");
+            }
+        }
+    }))
+}
+
+/// Whether the latest reload of the manifest failed, for `/health/ready`.
+#[derive(Debug, Default)]
+pub struct ReloadStatus {
+    error: Mutex<Option<String>>,
+}
+
+impl ReloadStatus {
+    /// Why the latest reload failed, or `None` if it succeeded (or there
+    /// has been none).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    fn record(&self, result: &Result<(), String>) {
+        *self.error.lock().unwrap() = result.as_ref().err().cloned();
+    }
+}
+
+/// Load the manifest at `path` again and, if it is valid, swap it into
+/// `state` and tell every live session which lists changed. The outcome is
+/// kept in [`ServerState::reloads`].
+///
+/// # Errors
+///
+/// Returns a description of the first problem with the file or a
+/// declaration; `state` is left as it was.
+pub async fn reload(path: &Path, state: &ServerState) -> Result<(), String> {
+    let result = swap(path, state).await;
+    state.reloads.record(&result);
+    result
+}
+
+async fn swap(path: &Path, state: &ServerState) -> Result<(), String> {
+    let previous = state.manifest.load_full();
+    // Declared tools may not take the name of a compiled or plugin tool
+    let mut reserved = McpServer::builtin_tool_names();
+    reserved.extend(
+        state
+            .plugins
+            .tools()
+            .into_iter()
+            .map(|tool| tool.name.into_owned()),
+    );
+    let (current, path_) = (previous.clone(), path.to_path_buf());
+    let next = tokio::task::spawn_blocking(move || current.reload(&path_, &reserved))
+        .await
+        .map_err(|e| e.to_string())??;
+    let next = Arc::new(next);
+    state.manifest.store(next.clone());
+    tracing::info!(path = %path.display(), "Reloaded the tool manifest");
+
+    if !previous.tools().eq(next.tools()) {
+        // A plugin may want a tool name the manifest no longer declares
+        state.plugins.rescan();
+        state.peers.notify_tool_list_changed().await;
+    }
+    if !previous.prompts().eq(next.prompts()) {
+        state.peers.notify_prompt_list_changed().await;
+    }
+    if !previous.resources().eq(next.resources()) {
+        state.peers.notify_resource_list_changed().await;
+    }
+    for resource in next.resources() {
+        let uri = &resource.uri;
+        if previous.read_resource(uri) != next.read_resource(uri) {
+            state.peers.notify_resource_updated(uri).await;
+        }
+    }
+    Ok(())
+}
+
+/// The manifest and the files its resources were read from.
+fn watched_files(path: &Path, catalog: &Catalog) -> BTreeSet<PathBuf> {
+    std::iter::once(path.to_path_buf())
+        .chain(
+            catalog
+                .files()
+                .filter_map(|file| std::path::absolute(file).ok()),
+        )
+        .collect()
+}
+
+/// Whether `event` may have changed one of `files`.
+fn concerns(event: &notify::Result<Event>, files: &BTreeSet<PathBuf>) -> bool {
+    match event {
+        Ok(event) => {
+            !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|path| files.contains(path))
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Error watching the tool manifest");
+            false
+        }
+    }
+}
+
+/// Start watching the directories of `files` not already in `dirs`.
+fn watch_dirs(
+    watcher: &mut RecommendedWatcher,
+    dirs: &mut BTreeSet<PathBuf>,
+    files: &BTreeSet<PathBuf>,
+) -> Result<(), String> {
+    for dir in files.iter().filter_map(|file| file.parent()) {
+        if !dirs.contains(dir) {
+            watcher
+                .watch(dir, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("{}: {e}", dir.display()))?;
+            dirs.insert(dir.to_path_buf());
+        }
+    }
+    Ok(())
+}
@@ -7,15 +7,33 @@
 //! This module demonstrates two patterns:
 //! - **Static resources** with fixed URIs (`about://server`, `doc://example`)
 //! - **Resource templates** with parameterised URIs (`greeting://{name}`, `item://{id}`)
+//! - **Binary resources**: the PNG icons in `assets/icons` are listed as
+//!   `icon://{file}` and read as base64 blobs with an `image/png` MIME type
+//!
+//! ## Contents
+//!
+//! [`contents`] is shared by every resource source: UTF-8 data with a textual
+//! MIME type is returned as text, anything else as a base64 blob. Contents
+//! larger than the inline limit are not returned at all; instead the read
+//! yields a link, a `text/uri-list` whose text is a URL to fetch the data
+//! from (the resource's own URI if it has no other) and whose `_meta` carries
+//! the real `mimeType` and `size`.
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_RESOURCE_MAX_INLINE_BYTES` | Largest resource returned inline | 1048576 (1 MiB) |
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use rmcp::{
     model::{
-        ListResourceTemplatesResult, ListResourcesResult, RawResource, RawResourceTemplate,
+        ListResourceTemplatesResult, ListResourcesResult, Meta, RawResource, RawResourceTemplate,
         ReadResourceResult, Resource, ResourceContents, ResourceTemplate,
     },
     ErrorData as McpError,
 };
 
+use crate::icons;
+
 /// Returns the list of available resources.
 ///
 /// # Errors
@@ -23,7 +41,7 @@ use rmcp::{
 /// This function currently does not return errors, but the Result type
 /// is used for consistency with the MCP protocol.
 pub fn list_resources() -> Result<ListResourcesResult, McpError> {
-    let resources = vec![
+    let mut resources = vec![
         Resource::new(
             RawResource {
                 uri: "about://server".into(),
@@ -32,7 +50,7 @@ pub fn list_resources() -> Result<ListResourcesResult, McpError> {
                 description: Some("Information about this MCP server".into()),
                 mime_type: Some("text/plain".into()),
                 size: None,
-                icons: None,
+                icons: Some(icons::robot()),
                 meta: None,
             },
             None,
@@ -45,12 +63,30 @@ pub fn list_resources() -> Result<ListResourcesResult, McpError> {
                 description: Some("An example document resource".into()),
                 mime_type: Some("text/plain".into()),
                 size: None,
-                icons: None,
+                icons: Some(icons::speech()),
                 meta: None,
             },
             None,
         ),
     ];
+    resources.extend(icons::pngs().map(|(name, asset)| {
+        Resource::new(
+            RawResource {
+                uri: format!("icon://{}", asset.file),
+                name: asset.file.into(),
+                title: Some(format!("{name} icon ({})", asset.size())),
+                description: None,
+                mime_type: Some(asset.mime_type.into()),
+                size: u32::try_from(asset.bytes.len()).ok(),
+                // Each image is shown with the smallest rendition of itself
+                icons: icons::renditions(name)
+                    .and_then(<[_]>::first)
+                    .map(|smallest| vec![smallest.to_icon()]),
+                meta: None,
+            },
+            None,
+        )
+    }));
 
     Ok(ListResourcesResult {
         resources,
@@ -74,7 +110,7 @@ pub fn list_resource_templates() -> Result<ListResourceTemplatesResult, McpError
                 title: Some("Personalized Greeting".into()),
                 description: Some("A personalized greeting for a specific person".into()),
                 mime_type: Some("text/plain".into()),
-                icons: None,
+                icons: Some(icons::waving_hand()),
             },
             None,
         ),
@@ -85,7 +121,7 @@ pub fn list_resource_templates() -> Result<ListResourceTemplatesResult, McpError
                 title: Some("Item Data".into()),
                 description: Some("Data for a specific item by ID".into()),
                 mime_type: Some("application/json".into()),
-                icons: None,
+                icons: Some(icons::package()),
             },
             None,
         ),
@@ -104,34 +140,154 @@ pub fn list_resource_templates() -> Result<ListResourceTemplatesResult, McpError
 ///
 /// Returns `McpError::resource_not_found` if the URI does not match
 /// any known resource.
-pub fn read_resource(uri: &str) -> Result<ReadResourceResult, McpError> {
-    let content = match uri {
-        "about://server" => about_content(),
-        "doc://example" => example_document_content(),
+pub fn read_resource(uri: &str, limit: InlineLimit) -> Result<ReadResourceResult, McpError> {
+    let not_found = || McpError::resource_not_found(format!("Resource not found: {uri}"), None);
+    if let Some(file) = uri.strip_prefix("icon://") {
+        let asset = icons::file(file)
+            .filter(|asset| asset.mime_type == "image/png")
+            .ok_or_else(not_found)?;
+        return Ok(ReadResourceResult::new(vec![contents(
+            uri,
+            asset.mime_type,
+            asset.bytes,
+            asset.url().as_deref(),
+            limit,
+        )]));
+    }
+
+    let (content, mime_type) = match uri {
+        "about://server" => (about_content(), "text/plain"),
+        "doc://example" => (example_document_content(), "text/plain"),
         _ if uri.starts_with("greeting://") => {
             // Extract name from greeting://{name}
             let name = uri.strip_prefix("greeting://").unwrap_or("World");
-            greeting_content(name)
+            (greeting_content(name), "text/plain")
         }
         _ if uri.starts_with("item://") => {
             // Extract ID from item://{id}
             let id = uri.strip_prefix("item://").unwrap_or("0");
-            item_content(id)
-        }
-        _ => {
-            return Err(McpError::resource_not_found(
-                format!("Resource not found: {uri}"),
-                None,
-            ))
+            (item_content(id), "application/json")
         }
+        _ => return Err(not_found()),
     };
 
-    Ok(ReadResourceResult::new(vec![ResourceContents::text(
-        content,
-        uri.to_string(),
+    Ok(ReadResourceResult::new(vec![contents(
+        uri,
+        mime_type,
+        content.as_bytes(),
+        None,
+        limit,
     )]))
 }
 
+/// Default [`InlineLimit`]: 1 MiB.
+pub const DEFAULT_MAX_INLINE_BYTES: usize = 1024 * 1024;
+
+/// The largest resource, in bytes, returned inline by `resources/read`;
+/// larger ones are returned as links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineLimit(pub usize);
+
+impl Default for InlineLimit {
+    fn default() -> Self {
+        Self(DEFAULT_MAX_INLINE_BYTES)
+    }
+}
+
+impl InlineLimit {
+    /// Read `MCP_RESOURCE_MAX_INLINE_BYTES`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of a value that is not a byte count.
+    pub fn from_env() -> Result<Self, String> {
+        std::env::var("MCP_RESOURCE_MAX_INLINE_BYTES")
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map_or_else(|| Ok(Self::default()), |value| value.parse().map(Self))
+            .map_err(|e| format!("MCP_RESOURCE_MAX_INLINE_BYTES: {e}"))
+    }
+
+    /// Whether contents of `size` bytes are returned inline.
+    #[must_use]
+    pub fn inlines(self, size: u64) -> bool {
+        usize::try_from(size).is_ok_and(|size| size <= self.0)
+    }
+}
+
+/// The contents of `uri`: text, a base64 blob, or a [`link`] to `target`
+/// when `bytes` is over `limit`.
+#[must_use]
+pub fn contents(
+    uri: &str,
+    mime_type: &str,
+    bytes: &[u8],
+    target: Option<&str>,
+    limit: InlineLimit,
+) -> ResourceContents {
+    if !limit.inlines(bytes.len() as u64) {
+        return link(uri, mime_type, bytes.len() as u64, target);
+    }
+    let contents = match std::str::from_utf8(bytes) {
+        Ok(text) if is_textual(mime_type) => ResourceContents::text(text, uri),
+        _ => ResourceContents::blob(BASE64.encode(bytes), uri),
+    };
+    contents.with_mime_type(mime_type)
+}
+
+/// A link standing in for contents too large to inline: a `text/uri-list`
+/// naming `target` (or `uri` itself), with the real MIME type and size in
+/// `_meta`.
+#[must_use]
+pub fn link(uri: &str, mime_type: &str, size: u64, target: Option<&str>) -> ResourceContents {
+    let mut meta = Meta::new();
+    meta.0.insert("mimeType".into(), mime_type.into());
+    meta.0.insert("size".into(), size.into());
+    ResourceContents::text(target.unwrap_or(uri), uri)
+        .with_mime_type("text/uri-list")
+        .with_meta(meta)
+}
+
+/// Whether UTF-8 content of this type is better returned as text. Unknown
+/// types (`application/octet-stream`) count, so a `Makefile` is text.
+fn is_textual(mime_type: &str) -> bool {
+    let Some((kind, subtype)) = mime_type.split(';').next().unwrap_or("").split_once('/') else {
+        return false;
+    };
+    let subtype = subtype.trim();
+    kind == "text"
+        || subtype.ends_with("+json")
+        || subtype.ends_with("+xml")
+        || matches!(
+            subtype,
+            "json"
+                | "xml"
+                | "javascript"
+                | "toml"
+                | "x-toml"
+                | "yaml"
+                | "x-yaml"
+                | "x-sh"
+                | "octet-stream"
+        )
+}
+
+/// A bounded label for the resource (or template) a URI belongs to.
+///
+/// Template URIs collapse to their template (`greeting://{name}`) so that
+/// metrics and logs do not grow a new series for every argument value.
+#[must_use]
+pub fn label(uri: &str) -> &'static str {
+    match uri {
+        "about://server" => "about://server",
+        "doc://example" => "doc://example",
+        _ if uri.starts_with("greeting://") => "greeting://{name}",
+        _ if uri.starts_with("item://") => "item://{id}",
+        _ if uri.starts_with("icon://") => "icon://{file}",
+        _ => crate::metrics::UNKNOWN,
+    }
+}
+
 fn about_content() -> String {
     r"MCP Rust Starter v1.0.0
 
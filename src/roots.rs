@@ -0,0 +1,125 @@
+//! # MCP Rust Starter - Client Roots
+//!
+//! Clients that declare the `roots` capability tell the server which
+//! directories it may work in. Each session asks for them with `roots/list`
+//! once the client has initialized, asks again whenever the client sends
+//! `notifications/roots/list_changed`, and keeps the latest answer.
+//!
+//! Filesystem access is then limited to those roots, on top of the server's
+//! own configuration:
+//! - **File resources** outside every root are neither listed nor readable.
+//! - **Command tools** only run in a working directory inside a root.
+//!
+//! A client without the capability is not restricted. A client that has it
+//! but lists no roots, or fails to answer, gets no filesystem access at all.
+//! Only `file://` roots count; roots that do not exist on this machine are
+//! ignored.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use rmcp::{model::Root, Peer, RoleServer};
+use url::Url;
+
+/// The directories a session may touch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Roots {
+    /// `None` if the client does not support roots.
+    dirs: Option<Arc<[PathBuf]>>,
+}
+
+impl Roots {
+    /// No restriction, for clients without the `roots` capability.
+    #[must_use]
+    pub const fn unrestricted() -> Self {
+        Self { dirs: None }
+    }
+
+    /// Allow only `dirs` and their subdirectories.
+    #[must_use]
+    pub fn new(dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            dirs: Some(
+                dirs.into_iter()
+                    // Canonical, like the paths they are checked against
+                    .filter_map(|dir| dir.canonicalize().ok())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The directories named by a `roots/list` answer.
+    #[must_use]
+    pub fn from_list(roots: &[Root]) -> Self {
+        Self::new(roots.iter().filter_map(|root| {
+            Url::parse(&root.uri)
+                .ok()
+                .filter(|url| url.scheme() == "file")?
+                .to_file_path()
+                .ok()
+        }))
+    }
+
+    /// The allowed directories, or `None` if access is not restricted.
+    #[must_use]
+    pub fn dirs(&self) -> Option<&[PathBuf]> {
+        self.dirs.as_deref()
+    }
+
+    /// Whether the canonical `path` is inside one of the roots.
+    #[must_use]
+    pub fn allows(&self, path: &Path) -> bool {
+        self.dirs
+            .as_ref()
+            .is_none_or(|dirs| dirs.iter().any(|dir| path.starts_with(dir)))
+    }
+}
+
+/// The roots of one session, fetched from its client.
+#[derive(Default)]
+pub struct SessionRoots {
+    current: Mutex<Option<Roots>>,
+}
+
+impl SessionRoots {
+    /// The session's roots, asking the client for them if they have not
+    /// been fetched yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub async fn get(&self, peer: &Peer<RoleServer>) -> Roots {
+        let current = self.current.lock().unwrap().clone();
+        match current {
+            Some(roots) => roots,
+            None => self.refresh(peer).await,
+        }
+    }
+
+    /// Ask the client for its roots again and remember the answer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub async fn refresh(&self, peer: &Peer<RoleServer>) -> Roots {
+        let supported = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.roots.is_some());
+        let roots = if supported {
+            match peer.list_roots().await {
+                Ok(result) => Roots::from_list(&result.roots),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to list client roots");
+                    Roots::new([])
+                }
+            }
+        } else {
+            Roots::unrestricted()
+        };
+        tracing::debug!(roots = ?roots.dirs(), "Client roots");
+        *self.current.lock().unwrap() = Some(roots.clone());
+        roots
+    }
+}
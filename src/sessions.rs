@@ -0,0 +1,438 @@
+//! # MCP Rust Starter - HTTP Sessions
+//!
+//! The Streamable HTTP transport runs in one of two modes:
+//!
+//! - **Stateful** (the default): `initialize` creates a session, named by the
+//!   `Mcp-Session-Id` response header, whose server lives in this process
+//!   until the client deletes it or the process exits. Server-initiated
+//!   requests and notifications reach the client over the session's streams.
+//! - **Stateless**: every POST is handled by a fresh server that lives only
+//!   as long as that request, and no session ID is issued, so any instance
+//!   behind a load balancer can serve any request. Features that need the
+//!   server to reach the client outside a request are off: capabilities no
+//!   longer advertise `listChanged`, elicitation (and so `confirm` policy
+//!   rules) is unavailable, and client roots are never requested. Progress
+//!   notifications still stream on the request's own response.
+//!
+//! Stateful sessions are also kept in the session store (see the `store`
+//! module). When a client sends an `Mcp-Session-Id` this process does not
+//! know, because it has restarted or the session began on another replica
+//! sharing the store, the session is restored: a fresh server is started
+//! and given the client's original `initialize` request, then picks up the
+//! session's subscriptions and loaded tools. Deleting a session forgets it,
+//! and so does this process once the session has been idle for the store's
+//! TTL, in case its client went away without deleting it.
+//!
+//! What a session streams is numbered and logged (see the `events` module),
+//! so a client reconnecting with `Last-Event-ID` gets what it missed. The
+//! log is kept in memory only, so it is not restored with the session.
+//!
+//! ## Configuration
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_HTTP_STATELESS` | `true` serves every request without a session | `false` |
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::Stream;
+use rmcp::{
+    model::{ClientJsonRpcMessage, ClientRequest, ServerJsonRpcMessage},
+    transport::{
+        streamable_http_server::{
+            session::{
+                local::{
+                    create_local_session, LocalSessionManager, LocalSessionWorker, SessionConfig,
+                },
+                ServerSseMessage,
+            },
+            SessionId, SessionManager,
+        },
+        StreamableHttpServerConfig, StreamableHttpService, WorkerTransport,
+    },
+    ServiceExt,
+};
+
+use crate::{
+    events::{EventLog, EventStream, Retention},
+    store::SessionRecord,
+    telemetry::Traced,
+    McpServer, ServerState,
+};
+
+/// How often a live session is marked as seen in the store.
+const TOUCH_INTERVAL: Duration = Duration::from_mins(1);
+
+/// Whether HTTP clients get sessions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Stateful,
+    Stateless,
+}
+
+impl Mode {
+    /// Read `MCP_HTTP_STATELESS`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of a value that is not a boolean.
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("MCP_HTTP_STATELESS").as_deref() {
+            Ok("1" | "true") => Ok(Self::Stateless),
+            Ok("" | "0" | "false") | Err(_) => Ok(Self::Stateful),
+            Ok(other) => Err(format!(
+                "MCP_HTTP_STATELESS: expected true or false, got '{other}'"
+            )),
+        }
+    }
+
+    /// The transport configuration for this mode.
+    #[must_use]
+    pub fn config(self) -> StreamableHttpServerConfig {
+        StreamableHttpServerConfig {
+            stateful_mode: self == Self::Stateful,
+            ..StreamableHttpServerConfig::default()
+        }
+    }
+}
+
+/// The MCP endpoint: a traced [`McpServer`] per session, or per request when
+/// `config` is stateless, each built from a clone of the state `sessions`
+/// was created with.
+#[must_use]
+pub fn service(
+    sessions: Arc<PersistentSessionManager>,
+    config: StreamableHttpServerConfig,
+) -> StreamableHttpService<Traced<McpServer>, PersistentSessionManager> {
+    let mut state = sessions.state.clone();
+    state.stateless = !config.stateful_mode;
+    StreamableHttpService::new(
+        move || Ok(Traced::new(McpServer::with_state(state.clone()))),
+        sessions,
+        config,
+    )
+}
+
+/// The sessions of this process, backed by the session store in
+/// [`ServerState::sessions`] so they can be restored after a restart or on
+/// another replica.
+pub struct PersistentSessionManager {
+    local: Arc<LocalSessionManager>,
+    state: ServerState,
+    tracked: Arc<Tracked>,
+    /// A lock per session being restored, so each is restored only once.
+    restoring: Mutex<HashMap<SessionId, Arc<tokio::sync::Mutex<()>>>>,
+    retention: Retention,
+}
+
+/// What this process keeps about each live session, forgotten when the
+/// session ends, whether it is deleted or its worker stops.
+#[derive(Default)]
+struct Tracked {
+    /// When each session was last marked as seen in the store.
+    touched: Mutex<HashMap<SessionId, Instant>>,
+    /// The event log of each session.
+    logs: Mutex<HashMap<SessionId, Arc<EventLog>>>,
+}
+
+impl Tracked {
+    fn forget(&self, id: &SessionId) {
+        self.touched.lock().unwrap().remove(id);
+        self.logs.lock().unwrap().remove(id);
+    }
+}
+
+impl PersistentSessionManager {
+    /// Serve sessions with servers built from `state`. A session idle for
+    /// the store's TTL is closed, so one whose client went away does not
+    /// live on here.
+    #[must_use]
+    pub fn new(state: ServerState) -> Self {
+        let local = LocalSessionManager {
+            sessions: tokio::sync::RwLock::default(),
+            session_config: SessionConfig {
+                keep_alive: Some(state.sessions.ttl()),
+                ..SessionConfig::default()
+            },
+        };
+        Self {
+            local: Arc::new(local),
+            state,
+            tracked: Arc::default(),
+            restoring: Mutex::default(),
+            retention: Retention::default(),
+        }
+    }
+
+    /// Keep as much of each session's stream for replay as `retention` allows.
+    #[must_use]
+    pub const fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// The number of sessions live in this process.
+    pub async fn live(&self) -> usize {
+        self.local.sessions.read().await.len()
+    }
+
+    /// Mark a live session as seen, at most once per [`TOUCH_INTERVAL`], so
+    /// it does not expire from the store while in use.
+    async fn touch(&self, id: &SessionId) {
+        let now = Instant::now();
+        let due = {
+            let mut touched = self.tracked.touched.lock().unwrap();
+            let due = touched
+                .get(id)
+                .is_none_or(|last| now.duration_since(*last) >= TOUCH_INTERVAL);
+            if due {
+                touched.insert(id.clone(), now);
+            }
+            due
+        };
+        if due {
+            self.state.sessions.update(id, |_| {}).await;
+        }
+    }
+
+    /// The event log of session `id`, started on first use.
+    fn log(&self, id: &SessionId) -> Arc<EventLog> {
+        self.tracked
+            .logs
+            .lock()
+            .unwrap()
+            .entry(id.clone())
+            .or_insert_with(|| Arc::new(EventLog::new(self.retention)))
+            .clone()
+    }
+
+    /// The standalone stream of session `id`, rejoined if it is running.
+    async fn standalone(&self, id: &SessionId) -> io::Result<EventStream> {
+        let log = self.log(id);
+        if let Some(events) = log.rejoin_standalone() {
+            return Ok(events);
+        }
+        let source = self
+            .local
+            .create_standalone_stream(id)
+            .await
+            .map_err(io::Error::other)?;
+        Ok(log.attach_standalone(source))
+    }
+
+    /// Bring the stored session `id` back to life in this process, returning
+    /// whether there was one to restore.
+    async fn restore(&self, id: &SessionId) -> io::Result<bool> {
+        // The session joins the live ones only once initialized, so no
+        // request reaches it before its replayed `initialize`; until then,
+        // other requests for it wait here rather than restore it again
+        let restoring = Restoring::new(&self.restoring, id);
+        let _restoring = restoring.lock.lock().await;
+        if self.local.has_session(id).await.map_err(io::Error::other)? {
+            return Ok(true);
+        }
+        let Some(record) = self.state.sessions.get(id).await else {
+            return Ok(false);
+        };
+        let (handle, worker) = create_local_session(id.clone(), self.local.session_config.clone());
+        let server = Traced::new(McpServer::for_session(self.state.clone(), id).await);
+        tokio::spawn(serve(
+            server,
+            worker,
+            self.local.clone(),
+            self.tracked.clone(),
+            self.state.clone(),
+            id.clone(),
+        ));
+
+        let response = handle
+            .initialize(replayed_initialize(&record)?)
+            .await
+            .map_err(io::Error::other)?;
+        if let ServerJsonRpcMessage::Error(error) = response {
+            handle.close().await.ok();
+            return Err(io::Error::other(error.error.message));
+        }
+        handle
+            .push_message(initialized(), None)
+            .await
+            .map_err(io::Error::other)?;
+        self.local.sessions.write().await.insert(id.clone(), handle);
+        self.touch(id).await;
+        tracing::info!(session_id = %id, client = %record.client.client_info.name, "Restored session");
+        Ok(true)
+    }
+}
+
+/// The restore lock of one session, forgotten once nobody holds or awaits it.
+struct Restoring<'a> {
+    locks: &'a Mutex<HashMap<SessionId, Arc<tokio::sync::Mutex<()>>>>,
+    id: &'a SessionId,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<'a> Restoring<'a> {
+    fn new(
+        locks: &'a Mutex<HashMap<SessionId, Arc<tokio::sync::Mutex<()>>>>,
+        id: &'a SessionId,
+    ) -> Self {
+        let lock = locks.lock().unwrap().entry(id.clone()).or_default().clone();
+        Self { locks, id, lock }
+    }
+}
+
+impl Drop for Restoring<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.lock().unwrap();
+        // One reference in the map, one here: nobody else is waiting
+        if Arc::strong_count(&self.lock) == 2 {
+            locks.remove(self.id);
+        }
+    }
+}
+
+/// Run the server of a restored session until it ends, then forget the
+/// session, as the HTTP service does for the sessions it starts.
+async fn serve(
+    server: Traced<McpServer>,
+    worker: LocalSessionWorker,
+    local: Arc<LocalSessionManager>,
+    tracked: Arc<Tracked>,
+    state: ServerState,
+    id: SessionId,
+) {
+    match server.serve(WorkerTransport::spawn(worker)).await {
+        Ok(running) => {
+            let _ = running.waiting().await;
+        }
+        Err(e) => tracing::error!(session_id = %id, error = %e, "Failed to restore session"),
+    }
+    local.close_session(&id).await.ok();
+    tracked.forget(&id);
+    state.sessions.remove(&id).await;
+}
+
+/// The client's original `initialize` request, sent again to a fresh server.
+fn replayed_initialize(record: &SessionRecord) -> io::Result<ClientJsonRpcMessage> {
+    Ok(serde_json::from_value(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": record.client,
+    }))?)
+}
+
+/// The `notifications/initialized` that completes a replayed handshake.
+fn initialized() -> ClientJsonRpcMessage {
+    serde_json::from_value(serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/initialized",
+    }))
+    .expect("a valid notification")
+}
+
+impl SessionManager for PersistentSessionManager {
+    type Error = io::Error;
+    type Transport = WorkerTransport<LocalSessionWorker>;
+
+    async fn create_session(&self) -> io::Result<(SessionId, Self::Transport)> {
+        self.local.create_session().await.map_err(io::Error::other)
+    }
+
+    /// Initializes the session, then stores it with the client's parameters.
+    async fn initialize_session(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> io::Result<ServerJsonRpcMessage> {
+        let client = match &message {
+            ClientJsonRpcMessage::Request(request) => match &request.request {
+                ClientRequest::InitializeRequest(initialize) => Some(initialize.params.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let response = self
+            .local
+            .initialize_session(id, message)
+            .await
+            .map_err(io::Error::other)?;
+        if let (Some(client), ServerJsonRpcMessage::Response(_)) = (client, &response) {
+            self.state
+                .sessions
+                .put(id, &SessionRecord::new(client))
+                .await;
+        }
+        Ok(response)
+    }
+
+    /// Whether the session is live here, or stored and now restored.
+    async fn has_session(&self, id: &SessionId) -> io::Result<bool> {
+        if self.local.has_session(id).await.map_err(io::Error::other)? {
+            self.touch(id).await;
+            return Ok(true);
+        }
+        self.restore(id).await
+    }
+
+    /// Closes the session and forgets it, on `DELETE` or once its worker
+    /// has stopped.
+    async fn close_session(&self, id: &SessionId) -> io::Result<()> {
+        self.tracked.forget(id);
+        self.state.sessions.remove(id).await;
+        self.local.close_session(id).await.map_err(io::Error::other)
+    }
+
+    /// Streams the response, logged for replay.
+    async fn create_stream(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> io::Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static> {
+        let source = self
+            .local
+            .create_stream(id, message)
+            .await
+            .map_err(io::Error::other)?;
+        Ok(self.log(id).attach(source))
+    }
+
+    async fn accept_message(
+        &self,
+        id: &SessionId,
+        message: ClientJsonRpcMessage,
+    ) -> io::Result<()> {
+        self.local
+            .accept_message(id, message)
+            .await
+            .map_err(io::Error::other)
+    }
+
+    /// Streams server-initiated messages, logged for replay.
+    async fn create_standalone_stream(
+        &self,
+        id: &SessionId,
+    ) -> io::Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static> {
+        self.standalone(id).await
+    }
+
+    /// Replays what the client missed from the event log, or opens the
+    /// standalone stream if the log does not know `last_event_id`.
+    async fn resume(
+        &self,
+        id: &SessionId,
+        last_event_id: String,
+    ) -> io::Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static> {
+        if let Some(events) = self.log(id).resume(&last_event_id) {
+            return Ok(events);
+        }
+        tracing::debug!(session_id = %id, last_event_id, "Unknown event, opening the standalone stream");
+        self.standalone(id).await
+    }
+}
@@ -0,0 +1,448 @@
+//! # MCP Rust Starter - Session Store
+//!
+//! What a stateful HTTP session needs to outlive the process serving it:
+//!
+//! - **Metadata**: when it was created and last seen.
+//! - **Negotiated capabilities**: the client's `initialize` parameters (its
+//!   protocol version, identity and capabilities), replayed to a fresh
+//!   server when the session is restored.
+//! - **Subscriptions**: the resource URIs it asked to hear about with
+//!   `resources/subscribe`.
+//! - **Loaded tools**: tools it added with `load_bonus_tool`.
+//!
+//! Records live in a [`SessionStore`]. By default they are kept in memory,
+//! only until the process exits; naming a database file instead keeps them
+//! in an embedded [redb](https://www.redb.org) database, across restarts of
+//! one process.
+//! Replicas share sessions when they share a store, so a deployment with
+//! several instances implements [`SessionStore`] over its own database and
+//! passes it to [`Sessions::new`]. Store calls run on tokio's blocking pool,
+//! so a slow disk or database holds up no request but the one waiting on it.
+//! A failed read or write is logged and otherwise ignored: the session keeps
+//! working, it just may not survive.
+//!
+//! Sessions not seen for `MCP_SESSION_TTL_SECS` are forgotten.
+//!
+//! ## Configuration
+//!
+//! | Variable | Meaning | Default |
+//! |---|---|---|
+//! | `MCP_SESSION_STORE` | Path of a session database, or `memory` | `memory` |
+//! | `MCP_SESSION_TTL_SECS` | How long an idle session is kept | `86400` (1 day) |
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    io,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use redb::{Database, ReadableTable, TableDefinition};
+use rmcp::model::InitializeRequestParams;
+use serde::{Deserialize, Serialize};
+
+/// Default time an idle session is kept.
+pub const DEFAULT_TTL: Duration = Duration::from_hours(24);
+
+/// The redb table of sessions, keyed by session ID, holding JSON records.
+const SESSIONS: TableDefinition<&str, &[u8]> = TableDefinition::new("sessions");
+
+/// What a session has changed since it initialized.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionData {
+    /// URIs of the resources it subscribed to.
+    #[serde(default)]
+    pub subscriptions: BTreeSet<String>,
+    /// Names of the tools it loaded.
+    #[serde(default)]
+    pub tools: BTreeSet<String>,
+}
+
+/// Everything stored about one session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    /// The parameters of the client's `initialize` request.
+    pub client: InitializeRequestParams,
+    pub created_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    #[serde(flatten)]
+    pub data: SessionData,
+}
+
+impl SessionRecord {
+    /// A record for a session that has just initialized.
+    #[must_use]
+    pub fn new(client: InitializeRequestParams) -> Self {
+        let now = Utc::now();
+        Self {
+            client,
+            created_at: now,
+            last_seen: now,
+            data: SessionData::default(),
+        }
+    }
+}
+
+/// Where session records are kept. Its methods may block: [`Sessions`]
+/// calls them on tokio's blocking pool.
+pub trait SessionStore: Send + Sync {
+    /// The record of session `id`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>>;
+
+    /// Create or replace the record of session `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()>;
+
+    /// Forget session `id`, if it is known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be written.
+    fn remove(&self, id: &str) -> io::Result<()>;
+
+    /// The IDs of every stored session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the store cannot be read.
+    fn ids(&self) -> io::Result<Vec<String>>;
+}
+
+/// Records kept in memory, lost when the process exits.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    records: Mutex<HashMap<String, SessionRecord>>,
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>> {
+        Ok(self.records.lock().unwrap().get(id).cloned())
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()> {
+        self.records
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), record.clone());
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        self.records.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    fn ids(&self) -> io::Result<Vec<String>> {
+        Ok(self.records.lock().unwrap().keys().cloned().collect())
+    }
+}
+
+/// Records kept in a redb database file. Only one process may have the
+/// file open at a time.
+pub struct RedbStore {
+    db: Database,
+}
+
+impl RedbStore {
+    /// Open the database at `path`, creating it if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or opened, or is open
+    /// in another process.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let db = Database::create(path).map_err(io::Error::other)?;
+        // Create the table up front so reads never find it missing
+        let tx = db.begin_write().map_err(io::Error::other)?;
+        tx.open_table(SESSIONS).map_err(io::Error::other)?;
+        tx.commit().map_err(io::Error::other)?;
+        Ok(Self { db })
+    }
+}
+
+impl SessionStore for RedbStore {
+    fn load(&self, id: &str) -> io::Result<Option<SessionRecord>> {
+        let tx = self.db.begin_read().map_err(io::Error::other)?;
+        let table = tx.open_table(SESSIONS).map_err(io::Error::other)?;
+        let Some(value) = table.get(id).map_err(io::Error::other)? else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::from_slice(value.value())?))
+    }
+
+    fn save(&self, id: &str, record: &SessionRecord) -> io::Result<()> {
+        let json = serde_json::to_vec(record)?;
+        let tx = self.db.begin_write().map_err(io::Error::other)?;
+        tx.open_table(SESSIONS)
+            .map_err(io::Error::other)?
+            .insert(id, json.as_slice())
+            .map_err(io::Error::other)?;
+        tx.commit().map_err(io::Error::other)
+    }
+
+    fn remove(&self, id: &str) -> io::Result<()> {
+        let tx = self.db.begin_write().map_err(io::Error::other)?;
+        tx.open_table(SESSIONS)
+            .map_err(io::Error::other)?
+            .remove(id)
+            .map_err(io::Error::other)?;
+        tx.commit().map_err(io::Error::other)
+    }
+
+    fn ids(&self) -> io::Result<Vec<String>> {
+        let tx = self.db.begin_read().map_err(io::Error::other)?;
+        let table = tx.open_table(SESSIONS).map_err(io::Error::other)?;
+        table
+            .iter()
+            .map_err(io::Error::other)?
+            .map(|entry| {
+                let (id, _) = entry.map_err(io::Error::other)?;
+                Ok(id.value().to_string())
+            })
+            .collect()
+    }
+}
+
+/// The session store of a process, and how long idle sessions are kept.
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+    ttl: Duration,
+}
+
+impl Default for Sessions {
+    /// Sessions kept in memory for [`DEFAULT_TTL`].
+    fn default() -> Self {
+        Self::new(MemoryStore::default(), DEFAULT_TTL)
+    }
+}
+
+impl Sessions {
+    /// Keep sessions in `store`, forgetting any not seen for `ttl`.
+    #[must_use]
+    pub fn new(store: impl SessionStore + 'static, ttl: Duration) -> Self {
+        Self {
+            store: Arc::new(store),
+            ttl,
+        }
+    }
+
+    /// Configure from `MCP_SESSION_STORE` and `MCP_SESSION_TTL_SECS`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of an invalid setting or an unopenable database.
+    pub fn from_env() -> Result<Self, String> {
+        let ttl = std::env::var("MCP_SESSION_TTL_SECS")
+            .map_or(Ok(DEFAULT_TTL), |value| {
+                value.parse().map(Duration::from_secs)
+            })
+            .map_err(|e| format!("MCP_SESSION_TTL_SECS: {e}"))?;
+        let path = std::env::var("MCP_SESSION_STORE").unwrap_or_default();
+        if path.is_empty() || path == "memory" {
+            return Ok(Self::new(MemoryStore::default(), ttl));
+        }
+        let store = RedbStore::open(&path).map_err(|e| format!("MCP_SESSION_STORE {path}: {e}"))?;
+        Ok(Self::new(store, ttl))
+    }
+
+    /// How long an idle session is kept.
+    #[must_use]
+    pub const fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Run `f` against the store on the blocking pool, as store calls may
+    /// wait on the disk (a redb commit syncs it) and must not stall the
+    /// runtime.
+    async fn with_store<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&dyn SessionStore) -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || f(store.as_ref()))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// The record of session `id`, unless it is unknown or has expired.
+    pub async fn get(&self, id: &str) -> Option<SessionRecord> {
+        let (key, ttl) = (id.to_string(), self.ttl);
+        self.with_store(move |store| {
+            let Some(record) = store.load(&key)? else {
+                return Ok(None);
+            };
+            if is_expired(record.last_seen, ttl) {
+                store.remove(&key)?;
+                return Ok(None);
+            }
+            Ok(Some(record))
+        })
+        .await
+        .inspect_err(|e| tracing::warn!(session_id = id, error = %e, "Failed to load session"))
+        .ok()
+        .flatten()
+    }
+
+    /// Store the record of session `id`.
+    pub async fn put(&self, id: &str, record: &SessionRecord) {
+        let (key, record) = (id.to_string(), record.clone());
+        if let Err(e) = self
+            .with_store(move |store| store.save(&key, &record))
+            .await
+        {
+            tracing::warn!(session_id = id, error = %e, "Failed to save session");
+        }
+    }
+
+    /// Apply `change` to the stored session `id`, if there is one that has
+    /// not expired, and mark it as seen now.
+    pub async fn update(&self, id: &str, change: impl FnOnce(&mut SessionRecord) + Send + 'static) {
+        let (key, ttl) = (id.to_string(), self.ttl);
+        let updated = self
+            .with_store(move |store| {
+                let Some(mut record) = store.load(&key)? else {
+                    return Ok(());
+                };
+                if is_expired(record.last_seen, ttl) {
+                    return store.remove(&key);
+                }
+                change(&mut record);
+                record.last_seen = Utc::now();
+                store.save(&key, &record)
+            })
+            .await;
+        if let Err(e) = updated {
+            tracing::warn!(session_id = id, error = %e, "Failed to update session");
+        }
+    }
+
+    /// Forget session `id`.
+    pub async fn remove(&self, id: &str) {
+        let key = id.to_string();
+        if let Err(e) = self.with_store(move |store| store.remove(&key)).await {
+            tracing::warn!(session_id = id, error = %e, "Failed to remove session");
+        }
+    }
+
+    /// Whether the store can be read.
+    ///
+    /// # Errors
+    ///
+    /// Returns the store's error.
+    pub async fn check(&self) -> io::Result<()> {
+        self.with_store(|store| store.load("").map(drop)).await
+    }
+
+    /// Forget every expired session, returning how many there were.
+    pub async fn prune(&self) -> usize {
+        let ttl = self.ttl;
+        self.with_store(move |store| {
+            let mut pruned = 0;
+            for id in store.ids()? {
+                let expired = store
+                    .load(&id)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|record| is_expired(record.last_seen, ttl));
+                if expired {
+                    store.remove(&id)?;
+                    pruned += 1;
+                }
+            }
+            Ok(pruned)
+        })
+        .await
+        .inspect_err(|e| tracing::warn!(error = %e, "Failed to prune sessions"))
+        .unwrap_or(0)
+    }
+}
+
+/// Whether a record last seen at `last_seen` has outlived `ttl`.
+fn is_expired(last_seen: DateTime<Utc>, ttl: Duration) -> bool {
+    (Utc::now() - last_seen)
+        .to_std()
+        .is_ok_and(|idle| idle >= ttl)
+}
+
+/// One server's view of its session: what it has changed, and the stored
+/// session those changes are saved to once it is known.
+#[derive(Debug, Default)]
+pub struct Session {
+    id: OnceLock<String>,
+    data: Mutex<SessionData>,
+    /// Held while a change is saved, so changes reach the store in order.
+    saving: tokio::sync::Mutex<()>,
+}
+
+impl Session {
+    /// Attach to the stored session `id`, taking up what it had changed so
+    /// far. Only the first call has any effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub async fn bind(&self, id: &str, sessions: &Sessions) {
+        if self.id.get().is_some() {
+            return;
+        }
+        let record = sessions.get(id).await;
+        let mut data = self.data.lock().unwrap();
+        // Whichever call sets the ID takes up the stored data with it
+        if self.id.set(id.to_string()).is_ok() {
+            if let Some(record) = record {
+                *data = record.data;
+            }
+        }
+    }
+
+    /// The stored session this is attached to, if any.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.get().map(String::as_str)
+    }
+
+    /// A copy of what the session has changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    #[must_use]
+    pub fn data(&self) -> SessionData {
+        self.data.lock().unwrap().clone()
+    }
+
+    /// Apply `change`, which returns whether it changed anything, saving
+    /// the result if it did and the session is attached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lock was poisoned by a panic while held.
+    pub async fn change(
+        &self,
+        sessions: &Sessions,
+        change: impl FnOnce(&mut SessionData) -> bool,
+    ) -> bool {
+        let _saving = self.saving.lock().await;
+        let data = {
+            let mut data = self.data.lock().unwrap();
+            if !change(&mut data) {
+                return false;
+            }
+            data.clone()
+        };
+        if let Some(id) = self.id() {
+            sessions.update(id, move |record| record.data = data).await;
+        }
+        true
+    }
+}
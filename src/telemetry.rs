@@ -0,0 +1,516 @@
+//! # MCP Rust Starter - Telemetry
+//!
+//! Logging and distributed tracing shared by both binaries.
+//!
+//! [`init`] installs the `tracing` subscriber. When `OTEL_EXPORTER_OTLP_ENDPOINT`
+//! (or `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT`) is set, spans are additionally
+//! exported over OTLP/HTTP to a collector such as the OpenTelemetry Collector
+//! or Jaeger (`http://localhost:4318`). The other standard `OTEL_*` variables
+//! (`OTEL_SERVICE_NAME`, `OTEL_EXPORTER_OTLP_HEADERS`, ...) are honoured too.
+//!
+//! [`Traced`] wraps the server and opens one `mcp.request` span per MCP
+//! request, carrying the method, tool name, session id and outcome. The span
+//! continues the caller's trace when a W3C `traceparent` is supplied, either
+//! in the request's `_meta` or in the HTTP request headers (in that order).
+//!
+//! ## Log format
+//!
+//! Logs are human-readable by default. Set `MCP_LOG_FORMAT=json` for one JSON
+//! object per line; any value other than `text` or `json` stops the server
+//! at startup. Every completed request is logged with these fields,
+//! which are kept stable for log pipelines:
+//!
+//! | field         | content                                             |
+//! |---------------|-----------------------------------------------------|
+//! | `session_id`  | Streamable HTTP session id (absent on stdio)        |
+//! | `request_id`  | JSON-RPC request id                                 |
+//! | `method`      | MCP method, e.g. `tools/call`                       |
+//! | `tool`        | tool name, for `tools/call`                         |
+//! | `arguments`   | tool arguments, with sensitive values redacted      |
+//! | `duration_ms` | handling time in milliseconds                       |
+//! | `outcome`     | `ok`, `tool_error` or `error`                       |
+//! | `error_code`  | JSON-RPC error code, when `outcome` is `error`      |
+//!
+//! ## Redaction
+//!
+//! A tool argument is sensitive when its input schema property carries
+//! `"x-sensitive": true` (set with `#[schemars(extend("x-sensitive" = true))]`).
+//! So is a field of an object argument or an element of an array argument
+//! whose schema, under `properties` or `items`, carries it. Sensitive values
+//! are logged as `"[redacted]"`. Arguments to unknown tools are redacted
+//! entirely, since there is no schema to consult, and so are values whose
+//! schema offers alternatives (`anyOf`, `oneOf`, ...) or a `$ref` outside
+//! the tool's own `$defs`.
+
+use std::{str::FromStr, time::Instant};
+
+use opentelemetry::{
+    propagation::{Extractor, TextMapPropagator},
+    trace::TracerProvider as _,
+    Context,
+};
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use rmcp::{
+    model::{ClientNotification, ClientRequest, JsonObject, Meta, ServerInfo, ServerResult},
+    service::{NotificationContext, RequestContext},
+    ErrorData as McpError, RoleServer, Service,
+};
+use serde_json::Value;
+use tracing::{field::Empty, Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{fmt::MakeWriter, prelude::*, registry::LookupSpan, EnvFilter, Layer};
+
+use crate::identity;
+
+/// Service name reported to the collector unless `OTEL_SERVICE_NAME` is set.
+const SERVICE_NAME: &str = "mcp-rust-starter";
+
+/// Schema keyword marking a tool argument as sensitive.
+pub const SENSITIVE_KEYWORD: &str = "x-sensitive";
+
+/// Placeholder logged instead of a sensitive value.
+pub const REDACTED: &str = "[redacted]";
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable, one line per event.
+    #[default]
+    Text,
+    /// One JSON object per event, fields flattened to the top level.
+    Json,
+}
+
+impl LogFormat {
+    /// The format selected by `MCP_LOG_FORMAT`, defaulting to text.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of an unknown format.
+    pub fn from_env() -> Result<Self, String> {
+        std::env::var("MCP_LOG_FORMAT").map_or(Ok(Self::Text), |value| {
+            value.parse().map_err(|e| format!("MCP_LOG_FORMAT: {e}"))
+        })
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "" | "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "unknown log format '{other}' (expected text or json)"
+            )),
+        }
+    }
+}
+
+/// Keeps the trace exporter alive; flushes pending spans when dropped.
+#[must_use = "dropping the guard stops trace export"]
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush traces: {e}");
+            }
+        }
+    }
+}
+
+/// Install the global `tracing` subscriber, logging to `writer` in `format`.
+///
+/// The log level defaults to `info` and can be changed with `RUST_LOG`.
+/// rmcp's own debug events print raw requests, including unredacted tool
+/// arguments, so they stay off unless `RUST_LOG` names `rmcp` explicitly.
+///
+/// # Panics
+///
+/// Panics if a global subscriber has already been installed.
+pub fn init<W>(format: LogFormat, writer: W) -> Telemetry
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let provider = otlp_endpoint_configured()
+        .then(|| match tracer_provider() {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                eprintln!("OpenTelemetry export disabled: {e}");
+                None
+            }
+        })
+        .flatten();
+    let otel = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+
+    let mut filter = EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into());
+    if !std::env::var("RUST_LOG").is_ok_and(|directives| directives.contains("rmcp")) {
+        filter = filter.add_directive("rmcp=info".parse().expect("valid directive"));
+    }
+
+    tracing_subscriber::registry()
+        .with(log_layer(format, writer))
+        .with(otel)
+        .with(filter)
+        .init();
+
+    if provider.is_some() {
+        tracing::info!("Exporting traces over OTLP");
+    }
+    Telemetry { provider }
+}
+
+/// The layer writing log lines to `writer` in `format`.
+pub fn log_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .boxed(),
+    }
+}
+
+fn otlp_endpoint_configured() -> bool {
+    [
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    ]
+    .iter()
+    .any(|var| std::env::var_os(var).is_some_and(|value| !value.is_empty()))
+}
+
+fn tracer_provider() -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .build()?;
+    let mut resource = Resource::builder();
+    if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name(SERVICE_NAME);
+    }
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build())
+}
+
+/// The trace context a request should continue, if the client sent one.
+///
+/// A `traceparent` in the request's `_meta` takes precedence over the HTTP
+/// headers, since it is set by the MCP client itself rather than a proxy.
+#[must_use]
+pub fn parent_context(context: &RequestContext<RoleServer>) -> Option<Context> {
+    let propagator = TraceContextPropagator::new();
+    let from_meta = MetaExtractor(&context.meta);
+    if from_meta.get("traceparent").is_some() {
+        return Some(propagator.extract(&from_meta));
+    }
+    let headers = &identity::http_parts(context)?.headers;
+    let from_headers = HeaderExtractor(headers);
+    from_headers
+        .get("traceparent")
+        .map(|_| propagator.extract(&from_headers))
+}
+
+/// Reads propagation fields from a request's `_meta` object.
+struct MetaExtractor<'a>(&'a Meta);
+
+impl Extractor for MetaExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(serde_json::Value::as_str)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(String::as_str).collect()
+    }
+}
+
+/// Reads propagation fields from HTTP request headers.
+struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(axum::http::HeaderName::as_str).collect()
+    }
+}
+
+/// Open the span for one MCP request.
+#[must_use]
+pub fn request_span(request: &ClientRequest, context: &RequestContext<RoleServer>) -> Span {
+    let method = request.method();
+    let tool = match request {
+        ClientRequest::CallToolRequest(call) => Some(call.params.name.as_ref()),
+        _ => None,
+    };
+    let name = tool.map_or_else(|| method.to_string(), |tool| format!("{method} {tool}"));
+
+    let span = tracing::info_span!(
+        "mcp.request",
+        otel.name = %name,
+        otel.kind = "server",
+        otel.status_code = Empty,
+        mcp.method = method,
+        mcp.tool = tool,
+        mcp.session_id = identity::session_id(context),
+        mcp.request_id = %context.id,
+        mcp.outcome = Empty,
+        mcp.error_code = Empty,
+    );
+    if let Some(parent) = parent_context(context) {
+        // Only fails if the span is disabled, in which case there is nothing to link.
+        let _ = span.set_parent(parent);
+    }
+    span
+}
+
+/// How a request ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The request succeeded.
+    Ok,
+    /// A tool ran but returned a result with `isError: true`.
+    ToolError,
+    /// The request failed with a JSON-RPC error carrying this code.
+    Error(i32),
+}
+
+impl Outcome {
+    /// Classify a request's result.
+    #[must_use]
+    pub fn of(result: &Result<ServerResult, McpError>) -> Self {
+        match result {
+            Ok(ServerResult::CallToolResult(result)) if result.is_error == Some(true) => {
+                Self::ToolError
+            }
+            Ok(_) => Self::Ok,
+            Err(e) => Self::Error(e.code.0),
+        }
+    }
+
+    /// The outcome as logged and recorded on spans.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::ToolError => "tool_error",
+            Self::Error(_) => "error",
+        }
+    }
+
+    /// The JSON-RPC error code, for failed requests.
+    #[must_use]
+    pub const fn error_code(self) -> Option<i32> {
+        match self {
+            Self::Error(code) => Some(code),
+            Self::Ok | Self::ToolError => None,
+        }
+    }
+}
+
+/// Record how a request ended on its span.
+pub fn record_outcome(span: &Span, outcome: Outcome) {
+    span.record("mcp.outcome", outcome.as_str());
+    if let Some(code) = outcome.error_code() {
+        span.record("mcp.error_code", code);
+    }
+    span.record(
+        "otel.status_code",
+        if outcome == Outcome::Ok {
+            "OK"
+        } else {
+            "ERROR"
+        },
+    );
+}
+
+/// Copy `arguments`, replacing sensitive values with [`REDACTED`].
+///
+/// `schema` is the tool's input schema; without one every value is redacted.
+#[must_use]
+pub fn redact(arguments: &JsonObject, schema: Option<&JsonObject>) -> Value {
+    match schema {
+        Some(schema) if schema.get("properties").is_some_and(Value::is_object) => {
+            redact_object(arguments, schema, schema)
+        }
+        _ => arguments
+            .keys()
+            .map(|name| (name.clone(), Value::from(REDACTED)))
+            .collect::<JsonObject>()
+            .into(),
+    }
+}
+
+/// Redact the fields of `object` by the `properties` of its `schema`;
+/// `$ref`s are looked up in the tool's `root` schema.
+fn redact_object(object: &JsonObject, schema: &JsonObject, root: &JsonObject) -> Value {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    object
+        .iter()
+        .map(|(name, value)| {
+            let value = properties
+                .and_then(|p| p.get(name))
+                .and_then(Value::as_object)
+                .map_or_else(
+                    || value.clone(),
+                    |property| redact_value(value, property, root),
+                );
+            (name.clone(), value)
+        })
+        .collect::<JsonObject>()
+        .into()
+}
+
+/// Redact `value` if its `schema` marks it sensitive, or the sensitive
+/// fields and elements inside it. A value whose schema has alternatives
+/// (`anyOf`, ...) is redacted whole, as it is unknown which one applies.
+fn redact_value(value: &Value, schema: &JsonObject, root: &JsonObject) -> Value {
+    let sensitive =
+        |schema: &JsonObject| schema.get(SENSITIVE_KEYWORD).and_then(Value::as_bool) == Some(true);
+    if sensitive(schema) {
+        return Value::from(REDACTED);
+    }
+    let schema = match schema.get("$ref") {
+        Some(reference) => match definition(reference, root) {
+            Some(definition) => definition,
+            None => return Value::from(REDACTED),
+        },
+        None => schema,
+    };
+    if sensitive(schema)
+        || ["$ref", "allOf", "anyOf", "oneOf", "if"]
+            .iter()
+            .any(|keyword| schema.contains_key(*keyword))
+    {
+        return Value::from(REDACTED);
+    }
+    match value {
+        Value::Object(object) if schema.contains_key("properties") => {
+            redact_object(object, schema, root)
+        }
+        Value::Array(elements) => schema.get("items").and_then(Value::as_object).map_or_else(
+            || value.clone(),
+            |items| {
+                elements
+                    .iter()
+                    .map(|element| redact_value(element, items, root))
+                    .collect()
+            },
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// The definition a local `$ref` such as `#/$defs/Operation` points to.
+fn definition<'a>(reference: &Value, root: &'a JsonObject) -> Option<&'a JsonObject> {
+    let reference = reference.as_str()?;
+    let (keyword, name) = reference
+        .strip_prefix("#/$defs/")
+        .map(|name| ("$defs", name))
+        .or_else(|| {
+            reference
+                .strip_prefix("#/definitions/")
+                .map(|name| ("definitions", name))
+        })?;
+    root.get(keyword)?.get(name)?.as_object()
+}
+
+/// A service that knows which of its tool arguments must not be logged.
+pub trait RedactArguments {
+    /// A loggable copy of the arguments of a call to `tool`.
+    fn redact_arguments(&self, tool: &str, arguments: &JsonObject) -> Value;
+}
+
+/// Wraps an MCP service so every request runs inside a [`request_span`] and
+/// is logged once it completes.
+#[derive(Debug, Clone)]
+pub struct Traced<S>(S);
+
+impl<S> Traced<S> {
+    /// Trace requests handled by `inner`.
+    pub const fn new(inner: S) -> Self {
+        Self(inner)
+    }
+
+    /// The wrapped service.
+    pub const fn inner(&self) -> &S {
+        &self.0
+    }
+}
+
+impl<S: Service<RoleServer> + RedactArguments> Service<RoleServer> for Traced<S> {
+    async fn handle_request(
+        &self,
+        request: ClientRequest,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ServerResult, McpError> {
+        let span = request_span(&request, &context);
+        let method = request.method().to_string();
+        let session_id = identity::session_id(&context).map(str::to_string);
+        let request_id = context.id.to_string();
+        let (tool, arguments) = match &request {
+            ClientRequest::CallToolRequest(call) => (
+                Some(call.params.name.to_string()),
+                call.params.arguments.as_ref().map(|arguments| {
+                    self.0
+                        .redact_arguments(&call.params.name, arguments)
+                        .to_string()
+                }),
+            ),
+            _ => (None, None),
+        };
+        let started = Instant::now();
+
+        let result = self
+            .0
+            .handle_request(request, context)
+            .instrument(span.clone())
+            .await;
+
+        let outcome = Outcome::of(&result);
+        record_outcome(&span, outcome);
+        span.in_scope(|| {
+            tracing::info!(
+                session_id,
+                request_id,
+                method,
+                tool,
+                arguments,
+                duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+                outcome = outcome.as_str(),
+                error_code = outcome.error_code(),
+                "request completed"
+            );
+        });
+        result
+    }
+
+    async fn handle_notification(
+        &self,
+        notification: ClientNotification,
+        context: NotificationContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.0.handle_notification(notification, context).await
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        self.0.get_info()
+    }
+}
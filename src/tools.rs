@@ -12,8 +12,233 @@
 //! - `idempotent_hint`: Repeated calls with same args have same effect
 //! - `open_world_hint`: Tool accesses external systems (web, APIs, etc.)
 
+use std::time::Duration;
+
+use rmcp::{
+    handler::server::tool::IntoCallToolResult,
+    model::{CallToolResult, Content, JsonObject, Meta, Tool, ToolAnnotations},
+    ErrorData,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// =============================================================================
+// TIMEOUTS
+//
+// Every tool call runs under a deadline. Tools that legitimately take longer
+// (or should give up sooner) declare their own in `#[tool(meta = ...)]`,
+// next to their annotations, which also advertises it to clients in the
+// tool's `_meta`.
+// =============================================================================
+
+/// Deadline for tools that do not declare their own.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `_meta` key carrying a tool's timeout, in milliseconds.
+pub const TIMEOUT_META_KEY: &str = "timeoutMs";
+
+/// Tool metadata declaring a timeout, for `#[tool(meta = timeout(...))]`.
+#[must_use]
+pub fn timeout(timeout: Duration) -> Meta {
+    let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+    let mut meta = Meta::new();
+    meta.0.insert(TIMEOUT_META_KEY.to_string(), millis.into());
+    meta
+}
+
+/// The deadline for calls to `tool`: its declared timeout, or the default.
+#[must_use]
+pub fn timeout_of(tool: &Tool) -> Duration {
+    tool.meta
+        .as_ref()
+        .and_then(|meta| meta.0.get(TIMEOUT_META_KEY))
+        .and_then(serde_json::Value::as_u64)
+        .map_or(DEFAULT_TIMEOUT, Duration::from_millis)
+}
+
+/// The error returned when a tool call runs past its deadline.
+#[must_use]
+pub fn timed_out(tool: &str, timeout: Duration) -> ToolError {
+    ToolError::new(
+        ToolErrorCode::Timeout,
+        format!("Tool '{tool}' timed out after {timeout:?} and was cancelled"),
+    )
+    .with_detail(
+        "timeoutMs",
+        u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+    )
+}
+
+// =============================================================================
+// ERRORS
+//
+// A failing tool call is reported in one of two ways:
+//
+// - a *protocol error* (JSON-RPC error response) when the request itself is
+//   wrong: unknown tool, malformed or invalid arguments
+// - a *tool execution error* (`CallToolResult` with `isError: true`) when the
+//   tool ran and failed. The model sees these and can react, e.g. by retrying
+//   or picking another tool.
+//
+// Tools return [`ToolResponse`], so every execution error is a [`ToolError`]
+// rendered the same way: its message as text content and
+// `{ "code", "message", "retryable", ...details }` as structured content.
+// =============================================================================
+
+/// Machine-readable category of a [`ToolError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorCode {
+    /// The tool failed unexpectedly.
+    Internal,
+    /// Something the tool depends on is unavailable; try again later.
+    Unavailable,
+    /// The tool ran past its deadline and was cancelled.
+    Timeout,
+    /// The caller is over a rate limit or the concurrency cap.
+    RateLimited,
+    /// The server's safety policy refused the call.
+    PolicyDenied,
+    /// An external command ran but exited unsuccessfully.
+    CommandFailed,
+    /// The arguments match the schema but the tool cannot act on them.
+    InvalidInput,
+}
+
+impl ToolErrorCode {
+    /// Whether errors with this code are worth retrying by default.
+    #[must_use]
+    pub const fn retryable(self) -> bool {
+        match self {
+            Self::Internal | Self::PolicyDenied | Self::CommandFailed | Self::InvalidInput => false,
+            Self::Unavailable | Self::Timeout | Self::RateLimited => true,
+        }
+    }
+
+    /// The code as it appears in the structured payload.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Internal => "internal",
+            Self::Unavailable => "unavailable",
+            Self::Timeout => "timeout",
+            Self::RateLimited => "rate_limited",
+            Self::PolicyDenied => "policy_denied",
+            Self::CommandFailed => "command_failed",
+            Self::InvalidInput => "invalid_input",
+        }
+    }
+}
+
+/// A tool execution error, returned to the client as an `isError` result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolError {
+    pub code: ToolErrorCode,
+    pub message: String,
+    pub retryable: bool,
+    /// Extra code-specific fields, e.g. `timeoutMs` or `retryAfterSeconds`.
+    #[serde(flatten)]
+    pub details: JsonObject,
+}
+
+impl ToolError {
+    /// An error with the code's default retryability and no details.
+    pub fn new(code: ToolErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            retryable: code.retryable(),
+            details: JsonObject::new(),
+        }
+    }
+
+    /// An unexpected failure inside the tool.
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ToolErrorCode::Internal, message)
+    }
+
+    /// A dependency of the tool is unavailable.
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ToolErrorCode::Unavailable, message)
+    }
+
+    /// Override whether the client should retry.
+    #[must_use]
+    pub const fn retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Add a field to the structured payload.
+    #[must_use]
+    pub fn with_detail(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Render as an `isError` tool result.
+    #[must_use]
+    pub fn into_result(self) -> CallToolResult {
+        let mut result = CallToolResult::error(vec![Content::text(self.message.clone())]);
+        result.structured_content = serde_json::to_value(self).ok();
+        result
+    }
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code.as_str(), self.message)
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+impl From<serde_json::Error> for ToolError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::internal(format!("Failed to serialize tool output: {error}"))
+    }
+}
+
+/// What a tool returns: its result, or a [`ToolError`] rendered as an
+/// `isError` result. Protocol errors are not tool responses; rmcp raises
+/// them before the tool runs.
+#[derive(Debug)]
+pub struct ToolResponse(pub Result<CallToolResult, ToolError>);
+
+impl From<Result<CallToolResult, ToolError>> for ToolResponse {
+    fn from(result: Result<CallToolResult, ToolError>) -> Self {
+        Self(result)
+    }
+}
+
+impl From<CallToolResult> for ToolResponse {
+    fn from(result: CallToolResult) -> Self {
+        Self(Ok(result))
+    }
+}
+
+impl From<ToolError> for ToolResponse {
+    fn from(error: ToolError) -> Self {
+        Self(Err(error))
+    }
+}
+
+impl IntoCallToolResult for ToolResponse {
+    fn into_call_tool_result(self) -> Result<CallToolResult, ErrorData> {
+        Ok(self.0.unwrap_or_else(ToolError::into_result))
+    }
+}
+
+/// A successful result carrying `value` as pretty-printed JSON text.
+///
+/// # Errors
+///
+/// Returns an internal [`ToolError`] if `value` cannot be serialized.
+pub fn json_result<T: Serialize>(value: &T) -> Result<CallToolResult, ToolError> {
+    let json = serde_json::to_string_pretty(value)?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
 
 /// Weather data returned by the `get_weather` tool.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -31,6 +256,24 @@ pub struct Weather {
     pub humidity: i32,
 }
 
+/// The weather provider behind `get_weather`.
+///
+/// Conditions are simulated; a real server would call a weather API here.
+#[must_use]
+pub fn current_weather(city: &str) -> Weather {
+    use rand::Rng;
+    let mut rng = rand::rng();
+    let conditions = ["sunny", "cloudy", "rainy", "windy"];
+
+    Weather {
+        location: city.to_string(),
+        temperature: rng.random_range(15..35),
+        unit: "celsius".to_string(),
+        conditions: conditions[rng.random_range(0..conditions.len())].to_string(),
+        humidity: rng.random_range(40..80),
+    }
+}
+
 /// Response from the hello tool.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[schemars(title = "HelloResponse")]
@@ -116,3 +359,97 @@ pub struct GetFeedbackResponse {
 // Note: Tool implementations are in lib.rs using the #[tool_router] macro.
 // The rmcp SDK handles tool parameter parsing automatically.
 // See the tool methods in McpServer impl block in lib.rs.
+
+// =============================================================================
+// SESSION TOOLS
+//
+// Tools a session loads for itself at runtime (with `load_bonus_tool`). They
+// are listed and callable only in that session, and are remembered with it
+// in the session store.
+// =============================================================================
+
+/// The tool `load_bonus_tool` loads.
+pub const BONUS_CALCULATOR: &str = "bonus_calculator";
+
+/// An arithmetic operation of `bonus_calculator`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// Parameters for the `bonus_calculator` tool.
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "bonus_calculatorArguments")]
+pub struct BonusCalculatorParams {
+    /// First operand
+    #[schemars(title = "A", description = "First operand")]
+    pub a: f64,
+    /// Second operand
+    #[schemars(title = "B", description = "Second operand")]
+    pub b: f64,
+    /// Operation to apply
+    #[schemars(title = "Operation", description = "Operation to apply")]
+    pub operation: Operation,
+}
+
+/// Response from the `bonus_calculator` tool.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "BonusCalculatorResponse")]
+pub struct BonusCalculatorResponse {
+    #[schemars(title = "Expression")]
+    pub expression: String,
+    #[schemars(title = "Result")]
+    pub result: f64,
+}
+
+/// The definition of session tool `name`, if there is one.
+#[must_use]
+pub fn session_tool(name: &str) -> Option<Tool> {
+    (name == BONUS_CALCULATOR).then(|| {
+        Tool::new(
+            BONUS_CALCULATOR,
+            "Perform arithmetic on two numbers (loaded by load_bonus_tool)",
+            rmcp::handler::server::tool::schema_for_type::<BonusCalculatorParams>(),
+        )
+        .with_output_schema::<BonusCalculatorResponse>()
+        .with_annotations(
+            ToolAnnotations::with_title("Bonus Calculator")
+                .read_only(true)
+                .destructive(false)
+                .idempotent(true)
+                .open_world(false),
+        )
+        .with_icons(crate::icons::abacus())
+    })
+}
+
+/// Run session tool `name` with arguments already validated against its
+/// input schema.
+#[must_use]
+pub fn call_session_tool(name: &str, arguments: JsonObject) -> ToolResponse {
+    if name != BONUS_CALCULATOR {
+        return ToolError::internal(format!("No session tool '{name}'")).into();
+    }
+    let params: BonusCalculatorParams = match serde_json::from_value(Value::Object(arguments)) {
+        Ok(params) => params,
+        Err(e) => return ToolError::internal(format!("Invalid arguments: {e}")).into(),
+    };
+    let (symbol, result) = match params.operation {
+        Operation::Add => ("+", params.a + params.b),
+        Operation::Subtract => ("-", params.a - params.b),
+        Operation::Multiply => ("*", params.a * params.b),
+        Operation::Divide if params.b == 0.0 => {
+            return ToolError::new(ToolErrorCode::InvalidInput, "Cannot divide by zero").into();
+        }
+        Operation::Divide => ("/", params.a / params.b),
+    };
+    json_result(&BonusCalculatorResponse {
+        expression: format!("{} {symbol} {}", params.a, params.b),
+        result,
+    })
+    .into()
+}
@@ -0,0 +1,422 @@
+//! # MCP Rust Starter - Argument Validation
+//!
+//! Constraints on tool arguments are declared once, with schemars attributes
+//! on the parameter structs:
+//!
+//! ```ignore
+//! #[schemars(length(min = 1, max = 100), regex(pattern = r"^[^\x00-\x1f]+$"))]
+//! pub name: String,
+//! #[schemars(range(min = 1, max = 200))]
+//! pub steps: i32,
+//! ```
+//!
+//! They end up in the tool's input schema (`minLength`, `maxLength`,
+//! `pattern`, `minimum`, `maximum`), so clients can see them, and a
+//! [`Validator`] built from that same schema when the tool is loaded checks
+//! the arguments of every call before the tool body runs. Every invalid
+//! field is reported, not just the first. Fields inside objects and arrays
+//! are named by their path, such as `address.city` or `tags[1]`.
+//!
+//! Only the keywords the server's own schemas use are checked: `required`,
+//! `type`, `enum`, string length and `pattern`, numeric bounds, and
+//! `properties` and `items` at any depth, following `$ref`s to the schema's
+//! own `$defs`. A schema that constrains its arguments with anything else
+//! (`anyOf`, `if`, a remote `$ref`, ...), or has an invalid `pattern`, is
+//! rejected when its tool is loaded rather than let calls through unchecked.
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use rmcp::{model::JsonObject, ErrorData};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Keywords that constrain a value in ways [`Validator`] does not check.
+const UNSUPPORTED: &[&str] = &[
+    "$dynamicRef",
+    "allOf",
+    "anyOf",
+    "oneOf",
+    "not",
+    "if",
+    "then",
+    "else",
+    "dependentRequired",
+    "dependentSchemas",
+    "patternProperties",
+    "propertyNames",
+    "prefixItems",
+    "contains",
+];
+
+/// One invalid argument.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    /// The argument's name, or its path for a field inside an object or
+    /// array argument.
+    pub field: String,
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A tool's input schema, compiled once for checking its calls' arguments.
+#[derive(Debug, Clone)]
+pub struct Validator {
+    root: Node,
+}
+
+impl Validator {
+    /// Compile a tool's input `schema`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first keyword that cannot be checked or
+    /// pattern that is not a valid regex.
+    pub fn new(schema: &JsonObject) -> Result<Self, String> {
+        Ok(Self {
+            root: Node::compile(
+                "",
+                schema,
+                &mut Definitions {
+                    root: schema,
+                    resolving: Vec::new(),
+                },
+            )?,
+        })
+    }
+
+    /// Check a call's `arguments`.
+    ///
+    /// # Errors
+    ///
+    /// Returns every invalid field: in each object, missing required fields
+    /// first, then invalid fields in name order.
+    pub fn validate(&self, arguments: Option<&JsonObject>) -> Result<(), Vec<FieldError>> {
+        let empty = JsonObject::new();
+        let mut errors = Vec::new();
+        self.root
+            .check_object("", arguments.unwrap_or(&empty), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// The `$defs` of the schema being compiled, and the `$ref`s being
+/// followed, to reject recursive ones.
+struct Definitions<'a> {
+    root: &'a JsonObject,
+    resolving: Vec<String>,
+}
+
+impl Definitions<'_> {
+    /// The definition a local `$ref` such as `#/$defs/Operation` points to.
+    fn get(&self, reference: &str) -> Option<&JsonObject> {
+        let (keyword, name) = reference
+            .strip_prefix("#/$defs/")
+            .map(|name| ("$defs", name))
+            .or_else(|| {
+                reference
+                    .strip_prefix("#/definitions/")
+                    .map(|name| ("definitions", name))
+            })?;
+        self.root.get(keyword)?.get(name)?.as_object()
+    }
+}
+
+/// The checked keywords of one (sub)schema.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    kind: Option<Value>,
+    allowed: Option<Vec<Value>>,
+    min_length: Option<u64>,
+    max_length: Option<u64>,
+    pattern: Option<Regex>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    required: Vec<String>,
+    properties: BTreeMap<String, Self>,
+    items: Option<Box<Self>>,
+}
+
+impl Node {
+    fn compile(
+        path: &str,
+        schema: &JsonObject,
+        definitions: &mut Definitions,
+    ) -> Result<Self, String> {
+        let location = || {
+            if path.is_empty() {
+                "the schema".to_string()
+            } else {
+                format!("property '{path}'")
+            }
+        };
+        if let Some(keyword) = UNSUPPORTED.iter().find(|k| schema.contains_key(**k)) {
+            return Err(format!(
+                "{} uses '{keyword}', which arguments cannot be checked against",
+                location()
+            ));
+        }
+        if let Some(reference) = schema.get("$ref") {
+            return Self::follow(path, &location(), reference, schema, definitions);
+        }
+
+        let pattern = schema
+            .get("pattern")
+            .and_then(Value::as_str)
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("{} has an invalid pattern: {e}", location()))
+            })
+            .transpose()?;
+
+        let mut properties = BTreeMap::new();
+        for (name, property) in schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(property) = property.as_object() {
+                let path = child(path, name);
+                properties.insert(name.clone(), Self::compile(&path, property, definitions)?);
+            }
+        }
+
+        let items = match schema.get("items") {
+            Some(Value::Object(items)) => Some(Box::new(Self::compile(
+                &format!("{path}[]"),
+                items,
+                definitions,
+            )?)),
+            Some(Value::Array(_)) => {
+                return Err(format!(
+                    "{} uses a list of 'items', which arguments cannot be checked against",
+                    location()
+                ))
+            }
+            _ => None,
+        };
+
+        let number = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+        Ok(Self {
+            kind: schema.get("type").cloned(),
+            allowed: schema.get("enum").and_then(Value::as_array).cloned(),
+            min_length: schema.get("minLength").and_then(Value::as_u64),
+            max_length: schema.get("maxLength").and_then(Value::as_u64),
+            pattern,
+            minimum: number("minimum"),
+            maximum: number("maximum"),
+            exclusive_minimum: number("exclusiveMinimum"),
+            exclusive_maximum: number("exclusiveMaximum"),
+            required: schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            properties,
+            items,
+        })
+    }
+
+    /// Compile the definition `reference` points to, with the keywords next
+    /// to the `$ref` in `schema` applied on top.
+    fn follow(
+        path: &str,
+        location: &str,
+        reference: &Value,
+        schema: &JsonObject,
+        definitions: &mut Definitions,
+    ) -> Result<Self, String> {
+        let reference = reference.as_str().unwrap_or_default();
+        let mut merged = definitions
+            .get(reference)
+            .ok_or_else(|| {
+                format!("{location} uses '$ref' to '{reference}', which is not one of the schema's $defs")
+            })?
+            .clone();
+        if definitions.resolving.iter().any(|r| r == reference) {
+            return Err(format!(
+                "{location} uses '$ref' to '{reference}' recursively"
+            ));
+        }
+        merged.extend(
+            schema
+                .iter()
+                .filter(|(keyword, _)| *keyword != "$ref")
+                .map(|(keyword, value)| (keyword.clone(), value.clone())),
+        );
+        definitions.resolving.push(reference.to_string());
+        let node = Self::compile(path, &merged, definitions);
+        definitions.resolving.pop();
+        node
+    }
+
+    fn check(&self, field: &str, value: &Value, errors: &mut Vec<FieldError>) {
+        if let Some(expected) = &self.kind {
+            if !type_matches(value, expected) {
+                errors.push(FieldError::new(
+                    field,
+                    format!("must be of type {expected}"),
+                ));
+                return;
+            }
+        }
+
+        if let Some(allowed) = &self.allowed {
+            if !allowed.contains(value) {
+                errors.push(FieldError::new(
+                    field,
+                    format!("must be one of {}", Value::from(allowed.clone())),
+                ));
+            }
+        }
+
+        match value {
+            Value::String(text) => self.check_string(field, text, errors),
+            Value::Number(number) => {
+                if let Some(number) = number.as_f64() {
+                    self.check_number(field, number, errors);
+                }
+            }
+            Value::Object(object) => self.check_object(field, object, errors),
+            Value::Array(elements) => {
+                if let Some(items) = &self.items {
+                    for (index, element) in elements.iter().enumerate() {
+                        items.check(&format!("{field}[{index}]"), element, errors);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_object(&self, field: &str, object: &JsonObject, errors: &mut Vec<FieldError>) {
+        for name in &self.required {
+            if !object.contains_key(name) {
+                errors.push(FieldError::new(&child(field, name), "is required"));
+            }
+        }
+        for (name, value) in object {
+            if let Some(property) = self.properties.get(name) {
+                property.check(&child(field, name), value, errors);
+            }
+        }
+    }
+
+    fn check_string(&self, field: &str, text: &str, errors: &mut Vec<FieldError>) {
+        let length = text.chars().count() as u64;
+        if let Some(min) = self.min_length {
+            if length < min {
+                let message = if min == 1 {
+                    "must not be empty".to_string()
+                } else {
+                    format!("must be at least {min} characters long")
+                };
+                errors.push(FieldError::new(field, message));
+            }
+        }
+        if let Some(max) = self.max_length {
+            if length > max {
+                errors.push(FieldError::new(
+                    field,
+                    format!("must be at most {max} characters long"),
+                ));
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(text) {
+                errors.push(FieldError::new(
+                    field,
+                    format!("must match the pattern {}", pattern.as_str()),
+                ));
+            }
+        }
+    }
+
+    fn check_number(&self, field: &str, number: f64, errors: &mut Vec<FieldError>) {
+        if let Some(min) = self.minimum {
+            if number < min {
+                errors.push(FieldError::new(field, format!("must be at least {min}")));
+            }
+        }
+        if let Some(max) = self.maximum {
+            if number > max {
+                errors.push(FieldError::new(field, format!("must be at most {max}")));
+            }
+        }
+        if let Some(min) = self.exclusive_minimum {
+            if number <= min {
+                errors.push(FieldError::new(
+                    field,
+                    format!("must be greater than {min}"),
+                ));
+            }
+        }
+        if let Some(max) = self.exclusive_maximum {
+            if number >= max {
+                errors.push(FieldError::new(field, format!("must be less than {max}")));
+            }
+        }
+    }
+}
+
+/// The path of the field `name` inside the object at `path`.
+fn child(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+/// Whether `value` has the JSON Schema `type` (a name or a list of names).
+fn type_matches(value: &Value, expected: &Value) -> bool {
+    let is = |name: &str| match name {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    };
+    match expected {
+        Value::String(name) => is(name),
+        Value::Array(names) => names.iter().filter_map(Value::as_str).any(is),
+        _ => true,
+    }
+}
+
+/// The `invalid_params` error returned when a call's arguments are invalid,
+/// the same error rmcp returns for arguments it cannot deserialize.
+#[must_use]
+pub fn invalid_arguments(tool: &str, errors: &[FieldError]) -> ErrorData {
+    let summary = errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ");
+    ErrorData::invalid_params(
+        format!("Invalid arguments for tool '{tool}': {summary}"),
+        Some(json!({ "errors": errors })),
+    )
+}
@@ -0,0 +1,200 @@
+//! The tool call audit log: what each record holds, rotation, and the
+//! reader's filters and summary.
+
+mod common;
+
+use std::{io::Write, process::Command, sync::Arc, time::Duration};
+
+use common::Harness;
+use mcp_rust_starter::{
+    audit::{self, AuditLog, Filter, Record, Status, Summary},
+    telemetry::REDACTED,
+    ServerState,
+};
+use pretty_assertions::assert_eq;
+use rmcp::model::{CallToolResult, JsonObject};
+use serde_json::json;
+
+fn record(tool: &str, status: Status, duration_ms: u64) -> Record {
+    let result = match status {
+        Status::Ok => Ok(CallToolResult::success(vec![])),
+        Status::ToolError => Ok(CallToolResult::error(vec![])),
+        Status::Error => Err(rmcp::ErrorData::internal_error("boom", None)),
+    };
+    Record::new(
+        tool,
+        &JsonObject::new(),
+        json!({}),
+        &result,
+        Duration::from_millis(duration_ms),
+    )
+}
+
+#[tokio::test]
+async fn every_tool_call_is_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let state = ServerState {
+        audit: Arc::new(AuditLog::open(&path, audit::DEFAULT_MAX_BYTES, 1).unwrap()),
+        ..ServerState::default()
+    };
+    let harness = Harness::with_state(state).await;
+
+    let arguments = json!({ "prompt": "my password is hunter2", "maxTokens": 10 });
+    harness.call("ask_llm", arguments.clone()).await;
+    harness
+        .try_call("hello", json!({ "name": "" }))
+        .await
+        .unwrap_err();
+    harness.shutdown().await;
+
+    let contents = audit::read(&audit::log_files(&path)).unwrap();
+    assert!(contents.skipped.is_empty());
+    let records = contents.records;
+    assert_eq!(records.len(), 2);
+
+    let asked = &records[0];
+    assert_eq!(asked.tool, "ask_llm");
+    assert_eq!(asked.status, Status::Ok);
+    assert_eq!(
+        asked.arguments,
+        json!({ "prompt": REDACTED, "maxTokens": 10 })
+    );
+    assert_eq!(
+        asked.arguments_sha256,
+        audit::hash_arguments(arguments.as_object().unwrap())
+    );
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("hunter2"));
+    // stdio-style calls have no session id or principal
+    assert_eq!(
+        (asked.session_id.as_deref(), asked.principal.as_deref()),
+        (None, None)
+    );
+
+    let rejected = &records[1];
+    assert_eq!(rejected.tool, "hello");
+    assert_eq!(rejected.status, Status::Error);
+    assert_eq!(rejected.error_code.as_deref(), Some("-32602"));
+    assert!(rejected.timestamp >= asked.timestamp);
+}
+
+#[tokio::test]
+async fn log_rotates_by_size_keeping_the_newest_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let log = AuditLog::open(&path, 300, 2).unwrap();
+
+    for n in 0..6 {
+        log.record(&record(&format!("tool{n}"), Status::Ok, n))
+            .await;
+    }
+
+    let files = audit::log_files(&path);
+    assert_eq!(
+        files,
+        vec![
+            dir.path().join("audit.jsonl.2"),
+            dir.path().join("audit.jsonl.1"),
+            path,
+        ]
+    );
+    let tools: Vec<String> = audit::read(&files)
+        .unwrap()
+        .records
+        .into_iter()
+        .map(|record| record.tool)
+        .collect();
+    assert_eq!(tools, ["tool3", "tool4", "tool5"]);
+}
+
+#[test]
+fn a_log_must_keep_a_rotated_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    assert!(AuditLog::open(&path, 300, 0).is_err());
+    assert!(!path.exists());
+}
+
+#[test]
+fn records_are_filtered_and_summarised() {
+    let records = vec![
+        record("hello", Status::Ok, 2).by(Some("s1"), Some("alice")),
+        record("hello", Status::ToolError, 4).by(Some("s2"), Some("bob")),
+        record("long_task", Status::Error, 10).by(Some("s1"), Some("alice")),
+    ];
+
+    let alice = Filter {
+        principal: Some("alice".into()),
+        ..Filter::default()
+    };
+    let tools: Vec<&str> = records
+        .iter()
+        .filter(|record| alice.matches(record))
+        .map(|record| record.tool.as_str())
+        .collect();
+    assert_eq!(tools, ["hello", "long_task"]);
+    let later = Filter {
+        since: Some(records[2].timestamp + chrono::Duration::seconds(1)),
+        ..Filter::default()
+    };
+    assert!(!records.iter().any(|record| later.matches(record)));
+
+    let summary = Summary::of(&records);
+    assert_eq!(summary.calls, 3);
+    let hello = &summary.tools["hello"];
+    assert_eq!((hello.calls, hello.ok, hello.tool_errors), (2, 1, 1));
+    assert!((hello.mean_duration_ms - 3.0).abs() < f64::EPSILON);
+    assert!((hello.max_duration_ms - 4.0).abs() < f64::EPSILON);
+    assert_eq!(summary.tools["long_task"].errors, 1);
+}
+
+#[tokio::test]
+async fn reader_binary_filters_and_summarises() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let log = AuditLog::open(&path, audit::DEFAULT_MAX_BYTES, 1).unwrap();
+    log.record(&record("hello", Status::Ok, 1)).await;
+    log.record(&record("long_task", Status::ToolError, 5)).await;
+
+    let reader = || {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_mcp-rust-starter-audit"));
+        command.arg(&path).env_remove("MCP_AUDIT_LOG");
+        command
+    };
+
+    let filtered = reader().args(["--status", "tool_error"]).output().unwrap();
+    assert!(filtered.status.success());
+    let lines: Vec<Record> = String::from_utf8(filtered.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].tool, "long_task");
+
+    let summary = reader().arg("--summary").output().unwrap();
+    let summary = String::from_utf8(summary.stdout).unwrap();
+    assert!(summary.starts_with("2 calls from "));
+    assert!(summary.contains("long_task"));
+
+    let invalid = reader().args(["--status", "maybe"]).output().unwrap();
+    assert_eq!(invalid.status.code(), Some(2));
+
+    // A line cut short by a crash is reported, and fails the run
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(b"{\"timestamp\": \"2026-").unwrap();
+    drop(file);
+    let damaged = reader().output().unwrap();
+    assert_eq!(damaged.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(damaged.stdout).unwrap().lines().count(),
+        2
+    );
+    let stderr = String::from_utf8(damaged.stderr).unwrap();
+    assert!(stderr.contains("skipped invalid record at "));
+    assert!(stderr.contains("audit.jsonl:3:"));
+    assert!(stderr.contains("skipped 1 invalid record(s)"));
+}
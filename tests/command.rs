@@ -0,0 +1,222 @@
+//! The command tool backend: argument mapping, structured output, output
+//! limits, the working directory sandbox and progress streaming.
+
+mod common;
+
+use std::{collections::HashSet, path::Path, sync::Arc};
+
+use arc_swap::ArcSwap;
+use common::{Harness, Notification, ScriptedClient};
+use mcp_rust_starter::{
+    command::{self, CommandSpec, Sandbox},
+    manifest::{Catalog, Manifest},
+    McpServer, ServerState,
+};
+use pretty_assertions::assert_eq;
+use rmcp::model::CallToolResult;
+use serde_json::{json, Value};
+
+fn tools(manifest: &str, sandbox: Sandbox) -> Result<Catalog, String> {
+    let manifest: Manifest = toml::from_str(manifest).unwrap();
+    Catalog::new(manifest, &HashSet::new(), sandbox)
+}
+
+async fn harness(manifest: &str, root: &Path) -> Harness {
+    let sandbox = Sandbox::new([root.to_path_buf()]).unwrap();
+    let server = McpServer::with_state(ServerState {
+        manifest: Arc::new(ArcSwap::from_pointee(tools(manifest, sandbox).unwrap())),
+        ..ServerState::default()
+    });
+    Harness::start(server, ScriptedClient::new()).await
+}
+
+fn output(result: &CallToolResult) -> Value {
+    result
+        .structured_content
+        .clone()
+        .expect("structured content")
+}
+
+#[test]
+fn arguments_map_to_argv() {
+    let spec: CommandSpec = toml::from_str(
+        r#"
+command = "grep"
+args = ["-n", "{{ flags }}", "--max-count={{limit}}", "{{pattern}}", "{{files}}", "{{missing}}"]
+"#,
+    )
+    .unwrap();
+    let arguments = json!({
+        "pattern": "fn main",
+        "limit": 3,
+        "flags": null,
+        "files": ["a.rs", "b c.rs"],
+    });
+
+    assert_eq!(
+        command::argv(&spec, arguments.as_object().unwrap()).unwrap(),
+        ["-n", "--max-count=3", "fn main", "a.rs", "b c.rs"]
+    );
+}
+
+#[test]
+fn values_may_not_pass_options() {
+    let spec = |args: &str| -> CommandSpec {
+        toml::from_str(&format!("command = \"grep\"\n{args}")).unwrap()
+    };
+    let argv =
+        |spec: &CommandSpec, arguments: Value| command::argv(spec, arguments.as_object().unwrap());
+
+    let grep = spec(r#"args = ["{{pattern}}", "{{files}}"]"#);
+    let error = argv(&grep, json!({ "pattern": "--output=/etc/passwd" })).unwrap_err();
+    assert_eq!(error.code.as_str(), "invalid_input");
+    assert!(error.message.contains("'pattern' may not start with '-'"));
+    let error = argv(&grep, json!({ "pattern": "x", "files": ["a", "-r"] })).unwrap_err();
+    assert!(error.message.contains("'files'"));
+    // Only a value at the start of an argument can make it an option
+    let suffixed = spec(r#"args = ["--regexp={{pattern}}"]"#);
+    assert_eq!(
+        argv(&suffixed, json!({ "pattern": "-v" })).unwrap(),
+        ["--regexp=-v"]
+    );
+
+    // Unless the manifest allows it, or the value follows `--`
+    let allowed = spec("args = [\"{{flags}}\", \"{{pattern}}\"]\nallow_options = [\"flags\"]");
+    assert_eq!(
+        argv(&allowed, json!({ "flags": "-i", "pattern": "x" })).unwrap(),
+        ["-i", "x"]
+    );
+    let separated = spec(r#"args = ["--", "{{pattern}}"]"#);
+    assert_eq!(
+        argv(&separated, json!({ "pattern": "-v" })).unwrap(),
+        ["--", "-v"]
+    );
+}
+
+#[tokio::test]
+async fn output_and_exit_code_are_structured() {
+    let root = tempfile::tempdir().unwrap();
+    let manifest = r#"
+[[tools]]
+name = "report"
+description = "Print to both streams"
+run = { type = "command", command = "sh", args = ["-c", "echo out; echo err >&2; echo $GREETING; exit $0", "{{code}}"], env = { GREETING = "hi" } }
+"#;
+    let harness = harness(manifest, root.path()).await;
+
+    let listed = harness.client.list_all_tools().await.unwrap();
+    assert!(listed[listed.len() - 1].output_schema.is_some());
+
+    let ok = harness.call("report", json!({ "code": 0 })).await;
+    assert_eq!(ok.is_error, Some(false));
+    assert_eq!(
+        output(&ok),
+        json!({ "exitCode": 0, "stdout": "out\nhi\n", "stderr": "err\n", "truncated": false })
+    );
+
+    let failed = harness.call("report", json!({ "code": 4 })).await;
+    assert_eq!(failed.is_error, Some(true));
+    let payload = failed.structured_content.unwrap();
+    assert_eq!(payload["code"], "command_failed");
+    assert_eq!(payload["exitCode"], 4);
+    assert_eq!(payload["stderr"], "err\n");
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn output_is_capped() {
+    let root = tempfile::tempdir().unwrap();
+    let manifest = r#"
+[[tools]]
+name = "chatty"
+description = "Print a lot"
+run = { type = "command", command = "sh", args = ["-c", "printf '%0100000d' 0"], max_output_bytes = 10 }
+"#;
+    let harness = harness(manifest, root.path()).await;
+
+    let result = harness.call("chatty", json!({})).await;
+    let output = output(&result);
+    assert_eq!(output["stdout"], "0000000000");
+    assert_eq!(output["truncated"], true);
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn working_directory_must_be_allowed() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::create_dir(root.path().join("project")).unwrap();
+    let manifest = r#"
+[[tools]]
+name = "where"
+description = "Print the working directory"
+run = { type = "command", command = "pwd", working_dir = "{{dir}}" }
+"#;
+    let harness = harness(manifest, root.path()).await;
+
+    let inside = harness.call("where", json!({ "dir": "project" })).await;
+    let canonical = root.path().canonicalize().unwrap().join("project");
+    assert_eq!(
+        output(&inside)["stdout"],
+        format!("{}\n", canonical.display())
+    );
+
+    for escape in ["..", "/", "project/../.."] {
+        let outside = harness.call("where", json!({ "dir": escape })).await;
+        assert_eq!(outside.is_error, Some(true), "{escape}");
+        assert_eq!(outside.structured_content.unwrap()["code"], "policy_denied");
+    }
+
+    harness.shutdown().await;
+
+    // A fixed working directory is checked when the manifest loads
+    let fixed = r#"
+[[tools]]
+name = "where"
+description = "Print the working directory"
+run = { type = "command", command = "pwd", working_dir = "/" }
+"#;
+    let sandbox = Sandbox::new([root.path().to_path_buf()]).unwrap();
+    let error = tools(fixed, sandbox).err().unwrap();
+    assert!(error.contains("is not in an allowed directory"));
+}
+
+#[tokio::test]
+async fn stdout_lines_stream_as_progress() {
+    let root = tempfile::tempdir().unwrap();
+    let manifest = r#"
+[[tools]]
+name = "count"
+description = "Count to three"
+run = { type = "command", command = "sh", args = ["-c", "echo one; echo two; printf three"] }
+"#;
+    let harness = harness(manifest, root.path()).await;
+
+    harness
+        .call_with_progress("count", json!({}), "job-1")
+        .await;
+    harness
+        .wait_for_notification(|n| matches!(n, Notification::Progress(p) if p.progress >= 3.0))
+        .await;
+    let messages: Vec<(f64, String)> = harness
+        .notifications()
+        .into_iter()
+        .filter_map(|notification| match notification {
+            Notification::Progress(progress) => {
+                Some((progress.progress, progress.message.unwrap_or_default()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            (1.0, "one".to_string()),
+            (2.0, "two".to_string()),
+            (3.0, "three".to_string())
+        ]
+    );
+
+    harness.shutdown().await;
+}
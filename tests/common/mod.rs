@@ -0,0 +1,415 @@
+//! In-process test harness.
+//!
+//! [`Harness`] runs an [`McpServer`] over an in-memory duplex transport and
+//! connects a [`ScriptedClient`] to it, so tools, resources and prompts can be
+//! exercised end-to-end without spawning the binaries. The client can be
+//! scripted to answer server-initiated sampling and elicitation requests and
+//! to declare roots, and records every notification the server sends.
+
+#![allow(dead_code)]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use mcp_rust_starter::{McpServer, ServerState};
+use rmcp::{
+    model::{
+        CallToolRequestParams, CallToolResult, ClientCapabilities, ClientInfo,
+        CreateElicitationRequestParams, CreateElicitationResult, CreateMessageRequestMethod,
+        CreateMessageRequestParams, CreateMessageResult, ElicitationCapability,
+        FormElicitationCapability, GetPromptRequestParams, GetPromptResult, Implementation,
+        ListRootsResult, LoggingMessageNotificationParam, Meta, NumberOrString,
+        ProgressNotificationParam, ProgressToken, RawContent, ReadResourceRequestParams,
+        ReadResourceResult, ResourceContents, ResourceUpdatedNotificationParam, Root,
+        RootsCapabilities, SamplingCapability, UrlElicitationCapability,
+    },
+    service::{NotificationContext, RequestContext, RunningService},
+    ClientHandler, ErrorData as McpError, RoleClient, RoleServer, Service, ServiceExt,
+};
+use serde_json::Value;
+use tokio::sync::Notify;
+
+/// Scripted answer to a `sampling/createMessage` request.
+pub type SamplingHandler =
+    Arc<dyn Fn(CreateMessageRequestParams) -> Result<CreateMessageResult, McpError> + Send + Sync>;
+
+/// Scripted answer to an `elicitation/create` request.
+pub type ElicitationHandler = Arc<
+    dyn Fn(CreateElicitationRequestParams) -> Result<CreateElicitationResult, McpError>
+        + Send
+        + Sync,
+>;
+
+/// A server-to-client notification observed by the [`ScriptedClient`].
+#[derive(Debug, Clone)]
+pub enum Notification {
+    Progress(ProgressNotificationParam),
+    Logging(LoggingMessageNotificationParam),
+    ToolListChanged,
+    ResourceListChanged,
+    ResourceUpdated(String),
+    PromptListChanged,
+}
+
+#[derive(Default)]
+struct Recorder {
+    notifications: Mutex<Vec<Notification>>,
+    arrived: Notify,
+}
+
+impl Recorder {
+    fn record(&self, notification: Notification) {
+        self.notifications.lock().unwrap().push(notification);
+        self.arrived.notify_waiters();
+    }
+}
+
+/// The roots a [`ScriptedClient`] declares, and how often they were listed.
+#[derive(Default)]
+pub struct ClientRoots {
+    roots: Mutex<Vec<Root>>,
+    listed: AtomicUsize,
+}
+
+impl ClientRoots {
+    /// Replace the declared roots; follow with `notify_roots_list_changed`.
+    pub fn set(&self, uris: &[&str]) {
+        *self.roots.lock().unwrap() = uris.iter().map(|uri| Root::new(*uri)).collect();
+    }
+
+    /// Number of `roots/list` requests answered so far.
+    pub fn listed(&self) -> usize {
+        self.listed.load(Ordering::SeqCst)
+    }
+}
+
+/// An rmcp client whose answers to server-initiated requests are scripted.
+///
+/// Capabilities are advertised only for the handlers that were configured,
+/// so a test can also check how the server behaves with a client that does
+/// not support sampling or elicitation.
+#[derive(Clone, Default)]
+pub struct ScriptedClient {
+    sampling: Option<SamplingHandler>,
+    elicitation: Option<ElicitationHandler>,
+    roots: Option<Arc<ClientRoots>>,
+    recorder: Arc<Recorder>,
+}
+
+impl ScriptedClient {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer sampling requests with `handler`.
+    #[must_use]
+    pub fn on_sampling(
+        mut self,
+        handler: impl Fn(CreateMessageRequestParams) -> Result<CreateMessageResult, McpError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.sampling = Some(Arc::new(handler));
+        self
+    }
+
+    /// Answer elicitation requests with `handler`.
+    #[must_use]
+    pub fn on_elicitation(
+        mut self,
+        handler: impl Fn(CreateElicitationRequestParams) -> Result<CreateElicitationResult, McpError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.elicitation = Some(Arc::new(handler));
+        self
+    }
+
+    /// Declare the roots capability with `uris` as the initial roots.
+    #[must_use]
+    pub fn with_roots(mut self, uris: &[&str]) -> Self {
+        let roots = ClientRoots::default();
+        roots.set(uris);
+        self.roots = Some(Arc::new(roots));
+        self
+    }
+
+    /// The declared roots, if [`ScriptedClient::with_roots`] was used.
+    pub fn roots(&self) -> &ClientRoots {
+        self.roots.as_deref().expect("client declares roots")
+    }
+}
+
+impl ClientHandler for ScriptedClient {
+    fn get_info(&self) -> ClientInfo {
+        let mut capabilities = ClientCapabilities::default();
+        if self.sampling.is_some() {
+            capabilities.sampling = Some(SamplingCapability::default());
+        }
+        if self.elicitation.is_some() {
+            capabilities.elicitation = Some(ElicitationCapability {
+                form: Some(FormElicitationCapability::default()),
+                url: Some(UrlElicitationCapability::default()),
+            });
+        }
+        if self.roots.is_some() {
+            capabilities.roots = Some(RootsCapabilities {
+                list_changed: Some(true),
+            });
+        }
+        ClientInfo::new(
+            capabilities,
+            Implementation::new("scripted-test-client", "0.0.0"),
+        )
+    }
+
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParams,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, McpError> {
+        self.sampling.as_ref().map_or_else(
+            || Err(McpError::method_not_found::<CreateMessageRequestMethod>()),
+            |handler| handler(params),
+        )
+    }
+
+    async fn create_elicitation(
+        &self,
+        request: CreateElicitationRequestParams,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateElicitationResult, McpError> {
+        self.elicitation.as_ref().map_or_else(
+            || {
+                Err(McpError::invalid_request(
+                    "elicitation is not supported by this client",
+                    None,
+                ))
+            },
+            |handler| handler(request),
+        )
+    }
+
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, McpError> {
+        let roots = self.roots.as_ref().map_or_else(Vec::new, |roots| {
+            roots.listed.fetch_add(1, Ordering::SeqCst);
+            roots.roots.lock().unwrap().clone()
+        });
+        Ok(ListRootsResult::new(roots))
+    }
+
+    async fn on_progress(
+        &self,
+        params: ProgressNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.recorder.record(Notification::Progress(params));
+    }
+
+    async fn on_logging_message(
+        &self,
+        params: LoggingMessageNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.recorder.record(Notification::Logging(params));
+    }
+
+    async fn on_tool_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.recorder.record(Notification::ToolListChanged);
+    }
+
+    async fn on_resource_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.recorder.record(Notification::ResourceListChanged);
+    }
+
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        _context: NotificationContext<RoleClient>,
+    ) {
+        self.recorder
+            .record(Notification::ResourceUpdated(params.uri));
+    }
+
+    async fn on_prompt_list_changed(&self, _context: NotificationContext<RoleClient>) {
+        self.recorder.record(Notification::PromptListChanged);
+    }
+}
+
+/// A connected client/server pair.
+///
+/// The server side is usually a bare [`McpServer`], but any service can be
+/// served, e.g. one wrapped in [`mcp_rust_starter::telemetry::Traced`].
+pub struct Harness<S: Service<RoleServer> = McpServer> {
+    pub client: RunningService<RoleClient, ScriptedClient>,
+    pub server: RunningService<RoleServer, S>,
+    recorder: Arc<Recorder>,
+}
+
+impl Harness {
+    /// Connect a default (capability-less) client to a default server.
+    pub async fn connect() -> Self {
+        Self::start(McpServer::new(), ScriptedClient::new()).await
+    }
+
+    /// Connect a default client to a server built from `state`, returning
+    /// once the session is registered for `list_changed` notifications.
+    pub async fn with_state(state: ServerState) -> Self {
+        let peers = state.peers.clone();
+        let harness = Self::start(McpServer::with_state(state), ScriptedClient::new()).await;
+        while peers.live().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        harness
+    }
+}
+
+/// Write `contents` to `file` under `dir`, creating its directories,
+/// returning the file's path.
+pub fn write_file(dir: &Path, file: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+    let path = dir.join(file);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+impl<S: Service<RoleServer>> Harness<S> {
+    /// Connect `client` to `server` over an in-memory duplex pipe.
+    pub async fn start(server: S, client: ScriptedClient) -> Self {
+        let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+        let recorder = client.recorder.clone();
+        let (server, client) = tokio::join!(server.serve(server_io), client.serve(client_io));
+        Self {
+            client: client.expect("client initializes"),
+            server: server.expect("server initializes"),
+            recorder,
+        }
+    }
+
+    /// Call a tool, panicking on protocol-level errors.
+    pub async fn call(&self, name: &str, arguments: Value) -> CallToolResult {
+        self.try_call(name, arguments)
+            .await
+            .unwrap_or_else(|e| panic!("tools/call {name} failed: {e}"))
+    }
+
+    /// Call a tool, returning protocol-level errors to the caller.
+    pub async fn try_call(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, rmcp::ServiceError> {
+        let mut params = CallToolRequestParams::new(name.to_string());
+        if let Value::Object(arguments) = arguments {
+            params = params.with_arguments(arguments);
+        }
+        self.client.call_tool(params).await
+    }
+
+    /// Call a tool with a progress token, panicking on protocol-level errors.
+    pub async fn call_with_progress(
+        &self,
+        name: &str,
+        arguments: Value,
+        token: &str,
+    ) -> CallToolResult {
+        let mut params = CallToolRequestParams::new(name.to_string());
+        if let Value::Object(arguments) = arguments {
+            params = params.with_arguments(arguments);
+        }
+        params.meta = Some(Meta::with_progress_token(ProgressToken(
+            NumberOrString::String(token.to_string().into()),
+        )));
+        self.client
+            .call_tool(params)
+            .await
+            .unwrap_or_else(|e| panic!("tools/call {name} failed: {e}"))
+    }
+
+    /// Read a resource, panicking on errors.
+    pub async fn read(&self, uri: &str) -> ReadResourceResult {
+        self.client
+            .read_resource(ReadResourceRequestParams::new(uri))
+            .await
+            .unwrap_or_else(|e| panic!("resources/read {uri} failed: {e}"))
+    }
+
+    /// Fetch a prompt, returning errors to the caller.
+    pub async fn prompt(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<GetPromptResult, rmcp::ServiceError> {
+        let mut params = GetPromptRequestParams::new(name.to_string());
+        if let Value::Object(arguments) = arguments {
+            params = params.with_arguments(arguments);
+        }
+        self.client.get_prompt(params).await
+    }
+
+    /// Every notification received so far.
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.recorder.notifications.lock().unwrap().clone()
+    }
+
+    /// Wait (up to five seconds) until a notification matching `predicate` arrives.
+    pub async fn wait_for_notification(
+        &self,
+        predicate: impl Fn(&Notification) -> bool + Sync,
+    ) -> Notification {
+        let wait = async {
+            loop {
+                let arrived = self.recorder.arrived.notified();
+                if let Some(found) = self.notifications().into_iter().find(&predicate) {
+                    return found;
+                }
+                arrived.await;
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(5), wait)
+            .await
+            .expect("timed out waiting for notification")
+    }
+
+    /// Shut down both ends of the connection.
+    pub async fn shutdown(self) {
+        self.client.cancel().await.expect("client shuts down");
+        let _ = self.server.waiting().await;
+    }
+}
+
+/// Concatenated text content of a tool result.
+pub fn text(result: &CallToolResult) -> String {
+    result
+        .content
+        .iter()
+        .filter_map(|content| match &content.raw {
+            RawContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parse a tool's text content as JSON.
+pub fn json(result: &CallToolResult) -> Value {
+    serde_json::from_str(&text(result)).expect("tool returned JSON text")
+}
+
+/// Text of the first content item of a resource read.
+pub fn resource_text(result: &ReadResourceResult) -> &str {
+    match result.contents.first().expect("resource has contents") {
+        ResourceContents::TextResourceContents { text, .. } => text,
+        ResourceContents::BlobResourceContents { .. } => panic!("expected text contents"),
+    }
+}
@@ -0,0 +1,168 @@
+//! `file://` resources: globs, pagination, text and blob contents, and
+//! confinement to the configured directory.
+
+mod common;
+
+use std::{path::Path, sync::Arc};
+
+use common::{resource_text, write_file, Harness};
+use mcp_rust_starter::{files::FileResources, resources::InlineLimit, ServerState};
+use pretty_assertions::assert_eq;
+use rmcp::model::{PaginatedRequestParams, ReadResourceRequestParams, ResourceContents};
+use url::Url;
+
+fn uri(root: &Path, file: &str) -> String {
+    Url::from_file_path(root.canonicalize().unwrap().join(file))
+        .unwrap()
+        .into()
+}
+
+async fn harness(files: FileResources) -> Harness {
+    let state = ServerState {
+        files: Arc::new(files),
+        // Every file these tests read inline is smaller than this
+        inline_limit: InlineLimit(64),
+        ..ServerState::default()
+    };
+    Harness::with_state(state).await
+}
+
+#[tokio::test]
+async fn files_are_listed_a_page_at_a_time() {
+    let dir = tempfile::tempdir().unwrap();
+    for file in [
+        "a.md",
+        "b/c.md",
+        "b/d.rs",
+        "e.md",
+        ".env",
+        "notes/.hidden/f.md",
+    ] {
+        write_file(dir.path(), file, b"x");
+    }
+    let files = FileResources::new(
+        dir.path(),
+        &["**/*.md", "**/*.env"],
+        &["**/.*", "**/.*/**"],
+        2,
+    )
+    .unwrap();
+    let harness = harness(files).await;
+
+    // The first page starts with the compiled resources
+    let first = harness.client.list_resources(None).await.unwrap();
+    assert_eq!(first.resources[0].name, "About");
+    let names: Vec<_> = first
+        .resources
+        .iter()
+        .filter(|r| r.uri.starts_with("file://"))
+        .map(|r| r.name.as_str())
+        .collect();
+    assert_eq!(names, ["a.md", "b/c.md"]);
+    let cursor = first.next_cursor.expect("a second page");
+
+    let second = harness
+        .client
+        .list_resources(Some(
+            PaginatedRequestParams::default().with_cursor(Some(cursor)),
+        ))
+        .await
+        .unwrap();
+    let names: Vec<_> = second.resources.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["e.md"]);
+    assert_eq!(second.next_cursor, None);
+
+    let all = harness.client.list_all_resources().await.unwrap();
+    let markdown = all.iter().find(|r| r.name == "b/c.md").unwrap();
+    assert_eq!(markdown.uri, uri(dir.path(), "b/c.md"));
+    assert_eq!(markdown.mime_type.as_deref(), Some("text/markdown"));
+    assert_eq!(markdown.size, Some(1));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn contents_are_text_or_base64() {
+    let dir = tempfile::tempdir().unwrap();
+    write_file(dir.path(), "notes.txt", "héllo".as_bytes());
+    write_file(dir.path(), "pixel.png", [0x89, b'P', b'N', b'G', 0xff]);
+    write_file(dir.path(), "Makefile", b"all:\n");
+    write_file(dir.path(), "archive.zip", [0; 100]);
+    let harness = harness(FileResources::new(dir.path(), &["**"], &[], 100).unwrap()).await;
+
+    let text = harness.read(&uri(dir.path(), "notes.txt")).await;
+    assert_eq!(resource_text(&text), "héllo");
+
+    let makefile = harness.read(&uri(dir.path(), "Makefile")).await;
+    assert_eq!(resource_text(&makefile), "all:\n");
+
+    let image = harness.read(&uri(dir.path(), "pixel.png")).await;
+    let ResourceContents::BlobResourceContents {
+        blob, mime_type, ..
+    } = &image.contents[0]
+    else {
+        panic!("expected a blob: {image:?}");
+    };
+    assert_eq!(blob, "iVBOR/8=");
+    assert_eq!(mime_type.as_deref(), Some("image/png"));
+
+    // Files over the inline limit link back to themselves
+    let archive = uri(dir.path(), "archive.zip");
+    let link = harness.read(&archive).await;
+    assert_eq!(resource_text(&link), archive);
+    let ResourceContents::TextResourceContents {
+        mime_type, meta, ..
+    } = &link.contents[0]
+    else {
+        unreachable!();
+    };
+    assert_eq!(mime_type.as_deref(), Some("text/uri-list"));
+    assert_eq!(
+        serde_json::to_value(meta).unwrap(),
+        serde_json::json!({ "mimeType": "application/zip", "size": 100 })
+    );
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn reads_are_confined_to_the_directory() {
+    let outside = tempfile::tempdir().unwrap();
+    write_file(outside.path(), "secret.txt", b"secret");
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    write_file(&root, "public.txt", b"public");
+    write_file(&root, "skip.log", b"skipped");
+    std::os::unix::fs::symlink(outside.path().join("secret.txt"), root.join("link.txt")).unwrap();
+    let harness = harness(FileResources::new(&root, &["**"], &["*.log"], 100).unwrap()).await;
+
+    let read = |uri: String| {
+        harness
+            .client
+            .read_resource(ReadResourceRequestParams::new(uri))
+    };
+    let root_uri = Url::from_file_path(root.canonicalize().unwrap()).unwrap();
+    for escape in [
+        format!("{root_uri}/../secret.txt"),
+        format!("{root_uri}/%2e%2e/secret.txt"),
+        uri(outside.path(), "secret.txt"),
+        uri(&root, "link.txt"),
+    ] {
+        let error = read(escape.clone()).await.unwrap_err();
+        assert!(
+            error.to_string().contains("outside the resource directory"),
+            "{escape}: {error}"
+        );
+    }
+
+    for hidden in [uri(&root, "skip.log"), uri(&root, "missing.txt")] {
+        let error = read(hidden.clone()).await.unwrap_err();
+        assert!(error.to_string().contains("Resource not found"), "{hidden}");
+    }
+    assert_eq!(
+        resource_text(&harness.read(&uri(&root, "public.txt")).await),
+        "public"
+    );
+
+    harness.shutdown().await;
+}
@@ -0,0 +1,81 @@
+//! Checks that the scripted client answers server-initiated requests.
+
+mod common;
+
+use common::{Harness, ScriptedClient};
+use mcp_rust_starter::McpServer;
+use rmcp::model::{
+    CreateElicitationRequestParams, CreateElicitationResult, CreateMessageRequestParams,
+    CreateMessageResult, ElicitationAction, ElicitationSchema, SamplingMessage,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn scripted_client_answers_sampling() {
+    let client = ScriptedClient::new().on_sampling(|params| {
+        assert_eq!(params.max_tokens, 50);
+        Ok(CreateMessageResult::new(
+            SamplingMessage::assistant_text("scripted answer"),
+            "test-model".into(),
+        ))
+    });
+    let harness = Harness::start(McpServer::new(), client).await;
+
+    let result = harness
+        .server
+        .create_message(CreateMessageRequestParams::new(
+            vec![SamplingMessage::user_text("question")],
+            50,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(result.model, "test-model");
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn scripted_client_answers_elicitation() {
+    let client = ScriptedClient::new().on_elicitation(|_| {
+        Ok(CreateElicitationResult::new(ElicitationAction::Accept)
+            .with_content(json!({ "confirmed": true })))
+    });
+    let harness = Harness::start(McpServer::new(), client).await;
+
+    let schema = ElicitationSchema::builder()
+        .required_bool("confirmed")
+        .build()
+        .unwrap();
+    let result = harness
+        .server
+        .create_elicitation(CreateElicitationRequestParams::FormElicitationParams {
+            meta: None,
+            message: "Proceed?".into(),
+            requested_schema: schema,
+        })
+        .await
+        .unwrap();
+    assert_eq!(result.action, ElicitationAction::Accept);
+    assert_eq!(result.content, Some(json!({ "confirmed": true })));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn capabilities_follow_configured_handlers() {
+    let plain = Harness::connect().await;
+    let info = plain.server.peer_info().unwrap();
+    assert!(info.capabilities.sampling.is_none());
+    assert!(info.capabilities.elicitation.is_none());
+    plain.shutdown().await;
+
+    let scripted = Harness::start(
+        McpServer::new(),
+        ScriptedClient::new()
+            .on_sampling(|_| unreachable!())
+            .on_elicitation(|_| unreachable!()),
+    )
+    .await;
+    let info = scripted.server.peer_info().unwrap();
+    assert!(info.capabilities.sampling.is_some());
+    assert!(info.capabilities.elicitation.is_some());
+    scripted.shutdown().await;
+}
@@ -0,0 +1,112 @@
+//! Liveness and readiness probes.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use mcp_rust_starter::{
+    health::{self, CheckStatus, Health, ReadyStatus},
+    reload, ServerState,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+async fn get(health: Arc<Health>, path: &str) -> (StatusCode, Value) {
+    let app: Router = health::routes(health);
+    let response = app
+        .oneshot(Request::get(path).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn state_checks_pass_and_report_latency() {
+    let health = Health::for_state(&ServerState::default());
+    let (status, body) = get(Arc::new(health), "/health/ready").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ready");
+    let names: Vec<_> = body["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| {
+            assert_eq!(check["status"], "pass");
+            assert!(check["latency_ms"].as_f64().is_some());
+            check["name"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(names, ["tool_manifest", "plugins", "session_store"]);
+}
+
+#[tokio::test]
+async fn a_failed_manifest_reload_makes_server_not_ready() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tools.toml");
+    std::fs::write(&path, "[[tools]]\nname = 42\n").unwrap();
+    let state = ServerState::default();
+    let health = Health::for_state(&state);
+
+    assert!(reload::reload(&path, &state).await.is_err());
+    let readiness = health.readiness().await;
+    assert_eq!(readiness.status, ReadyStatus::NotReady);
+    assert_eq!(readiness.checks[0].name, "tool_manifest");
+    assert_eq!(readiness.checks[0].status, CheckStatus::Fail);
+
+    std::fs::write(&path, "").unwrap();
+    reload::reload(&path, &state).await.unwrap();
+    assert!(health.readiness().await.is_ready());
+}
+
+#[tokio::test]
+async fn failing_check_makes_server_not_ready() {
+    let health = Health::new()
+        .with_check("ok", || async { Ok(()) })
+        .with_check("database", || async { Err("connection refused".into()) });
+
+    let (status, body) = get(Arc::new(health), "/health/ready").await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"][0]["status"], "pass");
+    assert!(body["checks"][0].get("error").is_none());
+    assert_eq!(body["checks"][1]["status"], "fail");
+    assert_eq!(body["checks"][1]["error"], "connection refused");
+}
+
+#[tokio::test(start_paused = true)]
+async fn slow_check_times_out() {
+    let health = Health::new().with_check("slow", || async {
+        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+        Ok(())
+    });
+
+    let readiness = health.readiness().await;
+
+    assert_eq!(readiness.status, ReadyStatus::NotReady);
+    assert_eq!(readiness.checks[0].status, CheckStatus::Fail);
+    assert!(readiness.checks[0]
+        .error
+        .as_deref()
+        .is_some_and(|error| error.contains("timed out")));
+}
+
+#[tokio::test]
+async fn draining_server_is_live_but_not_ready() {
+    let health = Arc::new(Health::new());
+    health.start_draining();
+
+    let (status, body) = get(health.clone(), "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "draining");
+
+    let (status, body) = get(health, "/health/live").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "alive");
+}
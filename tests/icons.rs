@@ -0,0 +1,175 @@
+//! Every entity the server advertises should carry at least one valid icon.
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use common::Harness;
+use mcp_rust_starter::{
+    command::Sandbox,
+    files::FileResources,
+    icons::{self, png_dimensions},
+    manifest::{Catalog, Manifest},
+    McpServer, ServerState,
+};
+use rmcp::model::Icon;
+
+/// Checks that an icon's data URI matches its declared MIME type and size.
+fn assert_valid_icon(owner: &str, icon: &Icon) {
+    let mime_type = icon
+        .mime_type
+        .as_deref()
+        .unwrap_or_else(|| panic!("{owner}: icon has no MIME type"));
+    let sizes = icon
+        .sizes
+        .as_deref()
+        .unwrap_or_else(|| panic!("{owner}: icon has no sizes"));
+    assert_eq!(sizes.len(), 1, "{owner}: expected one size per rendition");
+
+    let prefix = format!("data:{mime_type};base64,");
+    let encoded = icon
+        .src
+        .strip_prefix(&prefix)
+        .unwrap_or_else(|| panic!("{owner}: src is not a {mime_type} data URI"));
+    let bytes = STANDARD
+        .decode(encoded)
+        .unwrap_or_else(|e| panic!("{owner}: invalid base64: {e}"));
+
+    match mime_type {
+        "image/png" => {
+            let (width, height) = png_dimensions(&bytes);
+            assert_eq!(sizes[0], format!("{width}x{height}"), "{owner}: wrong size");
+        }
+        "image/svg+xml" => {
+            assert_eq!(sizes[0], "any", "{owner}: SVG should be sized 'any'");
+            assert!(
+                String::from_utf8_lossy(&bytes).contains("<svg"),
+                "{owner}: not an SVG document"
+            );
+        }
+        other => panic!("{owner}: unexpected MIME type {other}"),
+    }
+}
+
+fn assert_has_icons(owner: &str, icons: Option<&Vec<Icon>>) {
+    let icons = icons.unwrap_or_else(|| panic!("{owner}: no icons"));
+    assert!(!icons.is_empty(), "{owner}: empty icon list");
+    for icon in icons {
+        assert_valid_icon(owner, icon);
+    }
+}
+
+/// Declarations that name no icon, so they must get a default one.
+const MANIFEST: &str = r#"
+[[tools]]
+name = "plain_tool"
+description = "A tool without an icon"
+run = { type = "template", template = "ok" }
+
+[[prompts]]
+name = "plain_prompt"
+messages = [{ text = "hi" }]
+
+[[resources]]
+uri = "doc://plain"
+name = "Plain"
+text = "no icon"
+"#;
+
+#[tokio::test]
+async fn every_listed_entity_has_a_valid_icon() {
+    let manifest: Manifest = toml::from_str(MANIFEST).unwrap();
+    let catalog = Catalog::new(
+        manifest,
+        &McpServer::builtin_tool_names(),
+        Sandbox::default(),
+    )
+    .unwrap();
+    let harness = Harness::with_state(ServerState {
+        manifest: Arc::new(ArcSwap::from_pointee(catalog)),
+        ..ServerState::default()
+    })
+    .await;
+    let client = &harness.client;
+
+    let tools = client.list_all_tools().await.expect("tools/list");
+    assert!(!tools.is_empty());
+    for tool in &tools {
+        assert_has_icons(&format!("tool {}", tool.name), tool.icons.as_ref());
+    }
+
+    let resources = client.list_all_resources().await.expect("resources/list");
+    assert!(!resources.is_empty());
+    for resource in &resources {
+        assert_has_icons(
+            &format!("resource {}", resource.uri),
+            resource.icons.as_ref(),
+        );
+    }
+
+    let templates = client
+        .list_all_resource_templates()
+        .await
+        .expect("resources/templates/list");
+    assert!(!templates.is_empty());
+    for template in &templates {
+        assert_has_icons(
+            &format!("resource template {}", template.uri_template),
+            template.icons.as_ref(),
+        );
+    }
+
+    let prompts = client.list_all_prompts().await.expect("prompts/list");
+    for declared in [
+        "tool plain_tool",
+        "prompt plain_prompt",
+        "resource doc://plain",
+    ] {
+        let (kind, name) = declared.split_once(' ').unwrap();
+        let listed = match kind {
+            "tool" => tools.iter().any(|tool| tool.name == name),
+            "prompt" => prompts.iter().any(|prompt| prompt.name == name),
+            _ => resources.iter().any(|resource| resource.uri == name),
+        };
+        assert!(listed, "{declared} is not listed");
+    }
+    assert!(!prompts.is_empty());
+    for prompt in &prompts {
+        assert_has_icons(&format!("prompt {}", prompt.name), prompt.icons.as_ref());
+    }
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn file_resources_have_an_icon_for_their_kind() {
+    let dir = tempfile::tempdir().unwrap();
+    for file in ["notes.md", "data.json", "archive.zip"] {
+        std::fs::write(dir.path().join(file), b"x").unwrap();
+    }
+    let state = ServerState {
+        files: Arc::new(FileResources::new(dir.path(), &["**"], &[], 100).unwrap()),
+        ..ServerState::default()
+    };
+    let harness = Harness::with_state(state).await;
+
+    let resources = harness.client.list_all_resources().await.unwrap();
+    let files: Vec<_> = resources
+        .iter()
+        .filter(|resource| resource.uri.starts_with("file://"))
+        .collect();
+    assert_eq!(files.len(), 3);
+    for file in files {
+        assert_has_icons(&format!("file {}", file.name), file.icons.as_ref());
+        let expected = match file.name.as_str() {
+            "notes.md" => icons::speech(),
+            "data.json" => icons::abacus(),
+            _ => icons::package(),
+        };
+        assert_eq!(file.icons.as_ref(), Some(&expected), "{}", file.name);
+    }
+
+    harness.shutdown().await;
+}
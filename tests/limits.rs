@@ -0,0 +1,214 @@
+//! Rate limits and the in-flight tool call cap.
+
+mod common;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    middleware,
+    routing::post,
+    Extension, Router,
+};
+use common::Harness;
+use mcp_rust_starter::{
+    limits::{self, Admitted, Caller, Rate, RateLimiter, RateLimits, Scope},
+    ServerState,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn state(limits: RateLimits) -> ServerState {
+    ServerState {
+        limits: Arc::new(RateLimiter::new(limits)),
+        ..ServerState::default()
+    }
+}
+
+const fn caller<'a>(session: &'a str, tool: &'a str) -> Caller<'a> {
+    Caller {
+        session: Some(session),
+        principal: None,
+        tool,
+    }
+}
+
+#[test]
+fn rates_parse_from_config() {
+    assert_eq!("60/min".parse(), Ok(Rate::per_minute(60)));
+    assert_eq!("5/s".parse(), Ok(Rate::per_second(5)));
+    assert!("0/min".parse::<Rate>().is_err());
+    assert!("10/fortnight".parse::<Rate>().is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn session_bucket_refills_over_time() {
+    let limiter = RateLimiter::new(RateLimits {
+        per_session: Some(Rate::per_second(2)),
+        ..RateLimits::default()
+    });
+
+    assert!(limiter.check(&caller("a", "hello")).is_ok());
+    assert!(limiter.check(&caller("a", "hello")).is_ok());
+    let rejection = limiter.check(&caller("a", "hello")).unwrap_err();
+    assert_eq!(rejection.scope, Scope::Session);
+    assert_eq!(rejection.retry_after, Duration::from_millis(500));
+    assert!(
+        limiter.check(&caller("b", "hello")).is_ok(),
+        "sessions have separate buckets"
+    );
+
+    tokio::time::advance(Duration::from_millis(500)).await;
+    assert!(limiter.check(&caller("a", "hello")).is_ok());
+}
+
+#[tokio::test(start_paused = true)]
+async fn principal_bucket_is_shared_across_sessions() {
+    let limiter = RateLimiter::new(RateLimits {
+        per_principal: Some(Rate::per_minute(1)),
+        ..RateLimits::default()
+    });
+    let alice = |session| Caller {
+        session: Some(session),
+        principal: Some("alice"),
+        tool: "hello",
+    };
+
+    assert!(limiter.check(&alice("one")).is_ok());
+    let rejection = limiter.check(&alice("two")).unwrap_err();
+    assert_eq!(rejection.scope, Scope::Principal);
+    assert_eq!(rejection.retry_after_secs(), 60);
+    assert!(
+        limiter.check(&caller("three", "hello")).is_ok(),
+        "unauthenticated callers have no principal bucket"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn rejected_call_takes_no_tokens() {
+    let limiter = RateLimiter::new(RateLimits {
+        per_session: Some(Rate::per_minute(2)),
+        per_tool: HashMap::from([("long_task".to_string(), Rate::per_minute(1))]),
+        ..RateLimits::default()
+    });
+
+    assert!(limiter.check(&caller("a", "long_task")).is_ok());
+    assert_eq!(
+        limiter.check(&caller("a", "long_task")).unwrap_err().scope,
+        Scope::Tool
+    );
+    // The session still has its second token
+    assert!(limiter.check(&caller("a", "hello")).is_ok());
+}
+
+#[tokio::test]
+async fn limited_tool_call_returns_retryable_tool_error() {
+    let limits = RateLimits {
+        per_tool: HashMap::from([("hello".to_string(), Rate::per_minute(1))]),
+        ..RateLimits::default()
+    };
+    let harness = Harness::with_state(state(limits)).await;
+
+    let first = harness.call("hello", json!({ "name": "A" })).await;
+    assert_ne!(first.is_error, Some(true));
+
+    let second = harness.call("hello", json!({ "name": "B" })).await;
+    assert_eq!(second.is_error, Some(true));
+    assert_eq!(
+        second.structured_content,
+        Some(json!({
+            "code": "rate_limited",
+            "message": "Rate limit exceeded (tool); retry after 60s",
+            "retryable": true,
+            "scope": "tool",
+            "retryAfterSeconds": 60,
+        }))
+    );
+    assert!(common::text(&second).contains("retry after 60s"));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn concurrency_cap_rejects_calls_while_full() {
+    let limits = RateLimits {
+        max_concurrent_tool_calls: Some(1),
+        ..RateLimits::default()
+    };
+    let harness = Harness::with_state(state(limits)).await;
+
+    let slow = harness.call("long_task", json!({ "taskName": "hold", "steps": 3 }));
+    let rejected = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        harness.call("hello", json!({ "name": "A" })).await
+    };
+    let (slow, rejected) = tokio::join!(slow, rejected);
+
+    assert_ne!(slow.is_error, Some(true));
+    assert_eq!(rejected.is_error, Some(true));
+    assert_eq!(
+        rejected.structured_content.unwrap()["scope"],
+        json!("concurrency")
+    );
+
+    // The slot is released once the slow call finishes
+    let after = harness.call("hello", json!({ "name": "B" })).await;
+    assert_ne!(after.is_error, Some(true));
+
+    harness.shutdown().await;
+}
+
+fn tool_call(id: u64, tool: &str) -> Request<Body> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": { "name": tool, "arguments": {} }
+    });
+    Request::post("/mcp")
+        .header("mcp-session-id", "session-1")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn http_middleware_answers_429_with_retry_after() {
+    let state = state(RateLimits {
+        per_session: Some(Rate::per_minute(1)),
+        ..RateLimits::default()
+    });
+    let app = Router::new()
+        .route(
+            "/mcp",
+            post(|admitted: Option<Extension<Admitted>>| async move {
+                if admitted.is_some() {
+                    "admitted"
+                } else {
+                    "unchecked"
+                }
+            }),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            limits::enforce,
+        ));
+
+    let response = app.clone().oneshot(tool_call(1, "hello")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"admitted");
+
+    let response = app.oneshot(tool_call(2, "hello")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+    let body: Value =
+        serde_json::from_slice(&to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+    assert_eq!(body["id"], 2);
+    assert_eq!(body["error"]["data"]["scope"], "session");
+
+    assert!(state
+        .metrics
+        .render()
+        .contains("mcp_rate_limited_total{scope=\"session\"} 1"));
+}
@@ -0,0 +1,157 @@
+//! Structured request logs and argument redaction.
+
+mod common;
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use common::{Harness, ScriptedClient};
+use mcp_rust_starter::{
+    telemetry::{self, LogFormat, Traced, REDACTED},
+    McpServer,
+};
+use serde_json::{json, Value};
+use tracing_subscriber::{fmt::MakeWriter, prelude::*};
+
+/// Collects everything the log layer writes.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Call each tool on a traced server and return the `request completed` lines.
+async fn request_logs(calls: &[(&str, Value)]) -> Vec<Value> {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::registry()
+        .with(telemetry::log_layer(LogFormat::Json, captured.clone()));
+    // The current-thread test runtime keeps every rmcp task on this thread.
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let harness = Harness::start(Traced::new(McpServer::new()), ScriptedClient::new()).await;
+    for (tool, arguments) in calls {
+        let _ = harness.try_call(tool, arguments.clone()).await;
+    }
+    harness.shutdown().await;
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    output
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("each log line is JSON"))
+        .filter(|line| line["message"] == "request completed" && line["method"] == "tools/call")
+        .collect()
+}
+
+#[tokio::test]
+async fn completed_requests_are_logged_with_stable_fields() {
+    let logs = request_logs(&[("hello", json!({ "name": "Logger" }))]).await;
+
+    let [line] = logs.as_slice() else {
+        panic!("expected one tools/call log line, got {logs:?}");
+    };
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["tool"], "hello");
+    assert_eq!(line["outcome"], "ok");
+    assert!(line["request_id"].is_string());
+    assert!(line["duration_ms"].as_f64().is_some_and(|ms| ms >= 0.0));
+    assert!(line.get("error_code").is_none());
+    let arguments: Value = serde_json::from_str(line["arguments"].as_str().unwrap()).unwrap();
+    assert_eq!(arguments, json!({ "name": "Logger" }));
+}
+
+#[tokio::test]
+async fn sensitive_arguments_are_redacted() {
+    let logs = request_logs(&[(
+        "ask_llm",
+        json!({ "prompt": "my password is hunter2", "maxTokens": 10 }),
+    )])
+    .await;
+
+    let [line] = logs.as_slice() else {
+        panic!("expected one tools/call log line, got {logs:?}");
+    };
+    let raw = line.to_string();
+    assert!(!raw.contains("hunter2"), "secret leaked into log: {raw}");
+    let arguments: Value = serde_json::from_str(line["arguments"].as_str().unwrap()).unwrap();
+    assert_eq!(arguments, json!({ "prompt": REDACTED, "maxTokens": 10 }));
+}
+
+#[tokio::test]
+async fn unknown_tool_arguments_are_fully_redacted_and_error_code_logged() {
+    let logs = request_logs(&[("no_such_tool", json!({ "token": "s3cret" }))]).await;
+
+    let [line] = logs.as_slice() else {
+        panic!("expected one tools/call log line, got {logs:?}");
+    };
+    assert_eq!(line["outcome"], "error");
+    assert!(line["error_code"].is_i64());
+    assert!(!line.to_string().contains("s3cret"));
+}
+
+#[test]
+fn sensitive_fields_inside_objects_and_arrays_are_redacted() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "account": {
+                "type": "object",
+                "properties": {
+                    "user": { "type": "string" },
+                    "password": { "type": "string", "x-sensitive": true },
+                },
+            },
+            "headers": { "type": "array", "items": { "$ref": "#/$defs/Header" } },
+            "either": { "anyOf": [{ "type": "string" }, { "type": "integer" }] },
+        },
+        "$defs": {
+            "Header": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "value": { "type": "string", "x-sensitive": true },
+                },
+            },
+        },
+    });
+    let arguments = json!({
+        "account": { "user": "ada", "password": "hunter2" },
+        "headers": [{ "name": "Authorization", "value": "Bearer s3cret" }],
+        "either": "maybe secret",
+    });
+
+    let redacted = telemetry::redact(arguments.as_object().unwrap(), schema.as_object());
+
+    assert_eq!(
+        redacted,
+        json!({
+            "account": { "user": "ada", "password": REDACTED },
+            "headers": [{ "name": "Authorization", "value": REDACTED }],
+            "either": REDACTED,
+        })
+    );
+}
+
+#[test]
+fn log_format_parses_config_values() {
+    assert_eq!("json".parse(), Ok(LogFormat::Json));
+    assert_eq!("TEXT".parse(), Ok(LogFormat::Text));
+    assert!("xml".parse::<LogFormat>().is_err());
+}
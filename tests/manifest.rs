@@ -0,0 +1,225 @@
+//! Declarations from a manifest file: loading, listing next to the compiled
+//! tools, prompts and resources, and calling template and command tools.
+
+mod common;
+
+use std::{collections::HashSet, sync::Arc};
+
+use arc_swap::ArcSwap;
+use common::{resource_text, text, write_file, Harness, ScriptedClient};
+use mcp_rust_starter::{
+    command::Sandbox,
+    manifest::{self, Catalog},
+    McpServer, ServerState,
+};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+const MANIFEST: &str = r#"
+[[tools]]
+name = "greet_team"
+title = "Greet Team"
+description = "Greet a team by name"
+icon = "waving_hand"
+annotations = { read_only = true, open_world = false }
+input_schema = { type = "object", required = ["team"], properties = { team = { type = "string", maxLength = 20 } } }
+run = { type = "template", template = "Hello, team {{team}}!" }
+
+[[tools]]
+name = "echo_args"
+description = "Echo arguments back"
+timeout_ms = 5000
+input_schema = { type = "object", properties = { words = { type = "string" } } }
+run = { type = "command", command = "echo", args = ["-n", "{{words}}"] }
+"#;
+
+fn server(tools: Catalog) -> McpServer {
+    McpServer::with_state(ServerState {
+        manifest: Arc::new(ArcSwap::from_pointee(tools)),
+        ..ServerState::default()
+    })
+}
+
+#[tokio::test]
+async fn declared_tools_are_listed_and_called() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_file(dir.path(), "tools.toml", MANIFEST);
+    let tools = Catalog::load(&path, &McpServer::builtin_tool_names(), Sandbox::default()).unwrap();
+    let harness = Harness::start(server(tools), ScriptedClient::new()).await;
+
+    let listed = harness.client.list_all_tools().await.unwrap();
+    let greet = listed
+        .iter()
+        .find(|tool| tool.name == "greet_team")
+        .unwrap();
+    assert_eq!(greet.title.as_deref(), Some("Greet Team"));
+    assert_eq!(
+        greet.annotations.as_ref().unwrap().read_only_hint,
+        Some(true)
+    );
+    assert!(greet.icons.is_some());
+    assert!(listed.iter().any(|tool| tool.name == "hello"));
+
+    let greeting = harness.call("greet_team", json!({ "team": "Rust" })).await;
+    assert_eq!(text(&greeting), "Hello, team Rust!");
+
+    // A shell would split or expand this; a direct exec passes it through
+    let echoed = harness
+        .call("echo_args", json!({ "words": "a b; $(whoami)" }))
+        .await;
+    assert_eq!(echoed.is_error, Some(false));
+    assert_eq!(text(&echoed), "a b; $(whoami)");
+
+    // Declared schemas are validated like compiled ones
+    let error = harness
+        .try_call("greet_team", json!({ "team": "x".repeat(21) }))
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("team: must be at most 20 characters long"));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn failing_commands_are_tool_errors() {
+    let manifest = r#"
+tools:
+  - name: fail
+    description: Always fails
+    run:
+      type: command
+      command: sh
+      args: ["-c", "echo broken >&2; exit 3"]
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_file(dir.path(), "tools.yaml", manifest);
+    let tools = Catalog::load(&path, &HashSet::new(), Sandbox::default()).unwrap();
+    let harness = Harness::start(server(tools), ScriptedClient::new()).await;
+
+    let result = harness.call("fail", json!({})).await;
+    assert_eq!(result.is_error, Some(true));
+    assert!(text(&result).ends_with("broken"));
+    assert_eq!(result.structured_content.unwrap()["code"], "command_failed");
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn declared_prompts_and_resources_are_served() {
+    let manifest = r#"
+[[prompts]]
+name = "summarize"
+title = "Summarize"
+description = "Summarize a text"
+arguments = [{ name = "text", required = true }, { name = "style" }]
+messages = [
+    { text = "Summarize this {{style}}:\n\n{{text}}" },
+    { role = "assistant", text = "Here is a summary:" },
+]
+
+[[resources]]
+uri = "doc://handbook"
+name = "Handbook"
+mime_type = "text/markdown"
+file = "docs/handbook.md"
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("docs")).unwrap();
+    write_file(dir.path(), "docs/handbook.md", "# Handbook\n");
+    let path = write_file(dir.path(), "tools.toml", manifest);
+    let catalog = Catalog::load(&path, &HashSet::new(), Sandbox::default()).unwrap();
+    let harness = Harness::start(server(catalog), ScriptedClient::new()).await;
+
+    let prompts = harness.client.list_all_prompts().await.unwrap();
+    assert_eq!(
+        prompts.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+        ["greet", "code_review", "summarize"]
+    );
+    let prompt = harness
+        .prompt("summarize", json!({ "text": "MCP", "style": "briefly" }))
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(&prompt.messages).unwrap(),
+        json!([
+            { "role": "user", "content": { "type": "text", "text": "Summarize this briefly:\n\nMCP" } },
+            { "role": "assistant", "content": { "type": "text", "text": "Here is a summary:" } },
+        ])
+    );
+    let error = harness.prompt("summarize", json!({})).await.unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("Missing required 'text' argument"));
+
+    let resources = harness.client.list_all_resources().await.unwrap();
+    let handbook = resources.last().unwrap();
+    assert_eq!(handbook.uri, "doc://handbook");
+    assert_eq!(handbook.size, Some(11));
+    let read = harness.read("doc://handbook").await;
+    assert_eq!(resource_text(&read), "# Handbook\n");
+
+    harness.shutdown().await;
+}
+
+#[test]
+fn invalid_manifests_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let reserved = McpServer::builtin_tool_names();
+    let load = |file: &str, contents: &str| {
+        Catalog::load(
+            &write_file(dir.path(), file, contents),
+            &reserved,
+            Sandbox::default(),
+        )
+        .err()
+        .unwrap()
+    };
+    let tool = |name: &str, extra: &str| {
+        format!(
+            "[[tools]]\nname = \"{name}\"\ndescription = \"d\"\n{extra}run = {{ type = \"template\", template = \"\" }}\n"
+        )
+    };
+
+    assert!(load("a.toml", &tool("hello", "")).contains("tool 'hello' is already defined"));
+    assert!(load("b.toml", &(tool("x", "") + &tool("x", ""))).contains("declared more than once"));
+    assert!(load("c.toml", &tool("x", "icon = \"nope\"\n")).contains("unknown icon 'nope'"));
+    assert!(load(
+        "d.toml",
+        &tool("x", "input_schema = { type = \"string\" }\n")
+    )
+    .contains("input_schema must have"));
+    assert!(load(
+        "d2.toml",
+        &tool(
+            "x",
+            "input_schema = { type = \"object\", anyOf = [{ required = [\"a\"] }] }\n"
+        )
+    )
+    .contains("tool 'x': input_schema: the schema uses 'anyOf'"));
+    assert!(load("e.toml", &tool("x", "colour = \"red\"\n")).contains("unknown field"));
+    assert!(load("f.json", "{}").contains("expected a .toml, .yaml or .yml file"));
+    // The format is checked before the file is read
+    assert!(manifest::Manifest::load(&dir.path().join("missing.json"))
+        .unwrap_err()
+        .ends_with("expected a .toml, .yaml or .yml file"));
+
+    let prompt = "[[prompts]]\nname = \"greet\"\nmessages = [{ text = \"hi\" }]\n";
+    assert!(load("g.toml", prompt).contains("prompt 'greet' is already defined"));
+    let resource =
+        |uri: &str, extra: &str| format!("[[resources]]\nuri = \"{uri}\"\nname = \"n\"\n{extra}");
+    assert!(load("h.toml", &resource("item://7", "text = \"\"\n")).contains("already defined"));
+    assert!(load("i.toml", &resource("doc://x", "")).contains("exactly one of text and file"));
+    assert!(load("j.toml", &resource("doc://x", "file = \"missing.md\"\n")).contains("missing.md"));
+}
+
+#[test]
+fn templates_render_arguments() {
+    let arguments = json!({ "name": "Ada", "count": 3, "tags": ["a"] });
+    let arguments = arguments.as_object().unwrap();
+    assert_eq!(
+        manifest::render("{{ name }} has {{count}} {{tags}}{{missing}} {{", arguments),
+        "Ada has 3 [\"a\"] {{"
+    );
+}
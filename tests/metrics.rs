@@ -0,0 +1,48 @@
+//! Tool, resource and prompt traffic is recorded in the shared metrics.
+
+mod common;
+
+use common::Harness;
+use mcp_rust_starter::ServerState;
+use serde_json::json;
+
+#[tokio::test]
+async fn handlers_record_metrics() {
+    let state = ServerState::default();
+    let harness = Harness::with_state(state.clone()).await;
+
+    harness.call("hello", json!({ "name": "Ada" })).await;
+    harness.call("hello", json!({ "name": "Grace" })).await;
+    assert!(harness.try_call("no_such_tool", json!({})).await.is_err());
+    harness.read("greeting://Ada").await;
+    harness.read("greeting://Grace").await;
+    assert!(harness.prompt("greet", json!({})).await.is_err());
+    harness.shutdown().await;
+
+    let rendered = state.metrics.render();
+    for line in [
+        r#"mcp_tool_calls_total{tool="hello"} 2"#,
+        r#"mcp_tool_calls_total{tool="unknown"} 1"#,
+        r#"mcp_tool_errors_total{tool="unknown"} 1"#,
+        r#"mcp_tool_call_duration_seconds_count{tool="hello"} 2"#,
+        r#"mcp_resource_reads_total{outcome="ok",resource="greeting://{name}"} 2"#,
+        r#"mcp_prompt_fetches_total{outcome="error",prompt="greet"} 1"#,
+    ] {
+        assert!(rendered.contains(line), "missing `{line}` in:\n{rendered}");
+    }
+    assert!(!rendered.contains(r#"mcp_tool_errors_total{tool="hello"}"#));
+}
+
+#[tokio::test]
+async fn sessions_share_metrics_through_state() {
+    let state = ServerState::default();
+    for name in ["Ada", "Grace"] {
+        let harness = Harness::with_state(state.clone()).await;
+        harness.call("hello", json!({ "name": name })).await;
+        harness.shutdown().await;
+    }
+    assert!(state
+        .metrics
+        .render()
+        .contains(r#"mcp_tool_calls_total{tool="hello"} 2"#));
+}
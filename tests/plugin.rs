@@ -0,0 +1,303 @@
+//! WebAssembly plugin tools: loading, calling under fuel and memory limits,
+//! and `tools/list_changed` as plugins come and go.
+
+mod common;
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{text, write_file, Harness, Notification};
+use mcp_rust_starter::{
+    icons,
+    plugin::{PluginHost, PluginLimits},
+    McpServer, ServerState,
+};
+use pretty_assertions::assert_eq;
+use rmcp::model::JsonObject;
+use serde_json::json;
+
+const TOOLS: &str = r#"[
+    {"name": "wasm_echo", "description": "Echo the arguments back",
+     "inputSchema": {"type": "object", "required": ["text"], "properties": {"text": {"type": "string"}}},
+     "annotations": {"readOnlyHint": true, "openWorldHint": false}},
+    {"name": "wasm_spin", "description": "Loop forever", "inputSchema": {"type": "object"}},
+    {"name": "wasm_grow", "description": "Grow memory", "inputSchema": {"type": "object"}}
+]"#;
+
+const PREFIX: &str = r#"{"content":[],"structuredContent":"#;
+
+/// A plugin whose tools are dispatched on the sixth byte of their name:
+/// `wasm_echo` returns its arguments as structured content, `wasm_spin`
+/// never returns and `wasm_grow` asks for 128 MiB of memory.
+fn plugin_wat(tools: &str) -> String {
+    let escape = |text: &str| text.replace('"', "\\\"").replace('\n', "\\n");
+    format!(
+        r#"(module
+  (memory (export "memory") 1)
+  (global $next (mut i32) (i32.const 8192))
+  (data (i32.const 1024) "{tools}")
+  (data (i32.const 512) "{prefix}")
+  (func $alloc (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+  (func $pack (param $ptr i32) (param $len i32) (result i64)
+    (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+            (i64.extend_i32_u (local.get $len))))
+  (func (export "tools") (result i64)
+    (call $pack (i32.const 1024) (i32.const {tools_len})))
+  (func (export "call") (param $name i32) (param $name_len i32) (param $args i32) (param $args_len i32) (result i64)
+    (local $kind i32)
+    (local $out i32)
+    (local.set $kind (i32.load8_u (i32.add (local.get $name) (i32.const 5))))
+    (if (i32.eq (local.get $kind) (i32.const 115))
+      (then (loop $spin (br $spin))))
+    (if (i32.eq (local.get $kind) (i32.const 103))
+      (then (drop (memory.grow (i32.const 2048)))))
+    (local.set $out (call $alloc (i32.add (local.get $args_len) (i32.const {out_extra}))))
+    (memory.copy (local.get $out) (i32.const 512) (i32.const {prefix_len}))
+    (memory.copy (i32.add (local.get $out) (i32.const {prefix_len})) (local.get $args) (local.get $args_len))
+    (i32.store8 (i32.add (local.get $out) (i32.add (i32.const {prefix_len}) (local.get $args_len))) (i32.const 125))
+    (call $pack (local.get $out) (i32.add (local.get $args_len) (i32.const {out_extra})))))
+"#,
+        tools = escape(tools),
+        tools_len = tools.len(),
+        prefix = escape(PREFIX),
+        prefix_len = PREFIX.len(),
+        out_extra = PREFIX.len() + 1,
+    )
+}
+
+fn host() -> Arc<PluginHost> {
+    Arc::new(PluginHost::new(PluginLimits {
+        fuel: 10_000_000,
+        memory_bytes: 1024 * 1024,
+    }))
+}
+
+async fn harness(plugins: Arc<PluginHost>) -> Harness {
+    let state = ServerState {
+        plugins,
+        ..ServerState::default()
+    };
+    Harness::with_state(state).await
+}
+
+#[tokio::test]
+async fn plugin_tools_are_listed_and_called() {
+    let dir = tempfile::tempdir().unwrap();
+    write_file(dir.path(), "demo.wat", plugin_wat(TOOLS));
+    let plugins = host();
+    assert!(plugins.sync(dir.path(), &McpServer::builtin_tool_names()));
+    let harness = harness(plugins).await;
+
+    let listed = harness.client.list_all_tools().await.unwrap();
+    let echo = listed.iter().find(|tool| tool.name == "wasm_echo").unwrap();
+    assert_eq!(
+        echo.annotations.as_ref().unwrap().read_only_hint,
+        Some(true)
+    );
+    assert_eq!(echo.icons, Some(icons::package()));
+
+    let echoed = harness.call("wasm_echo", json!({ "text": "hi" })).await;
+    assert_eq!(echoed.structured_content, Some(json!({ "text": "hi" })));
+
+    // Plugin schemas are validated like compiled ones
+    let error = harness.try_call("wasm_echo", json!({})).await.unwrap_err();
+    assert!(error.to_string().contains("text: is required"));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn calls_are_limited_by_fuel_and_memory() {
+    let dir = tempfile::tempdir().unwrap();
+    write_file(dir.path(), "demo.wat", plugin_wat(TOOLS));
+    let plugins = host();
+    plugins.sync(dir.path(), &HashSet::new());
+    let harness = harness(plugins).await;
+
+    let spun = harness.call("wasm_spin", json!({})).await;
+    assert_eq!(spun.is_error, Some(true));
+    assert!(text(&spun).contains("ran out of fuel (10000000 units)"));
+
+    let grown = harness.call("wasm_grow", json!({})).await;
+    assert_eq!(grown.is_error, Some(true));
+    assert_eq!(grown.structured_content.unwrap()["code"], "internal");
+
+    // A failed call leaves nothing behind for the next one
+    let echoed = harness.call("wasm_echo", json!({ "text": "again" })).await;
+    assert_eq!(echoed.is_error, Some(false));
+
+    harness.shutdown().await;
+}
+
+#[test]
+fn calls_that_are_given_up_on_stop_running() {
+    // One blocking thread: the second call can only run once the first stops
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .max_blocking_threads(1)
+        .build()
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    write_file(dir.path(), "demo.wat", plugin_wat(TOOLS));
+    let plugins = Arc::new(PluginHost::new(PluginLimits {
+        fuel: u64::MAX,
+        memory_bytes: 1024 * 1024,
+    }));
+    plugins.sync(dir.path(), &HashSet::new());
+
+    runtime.block_on(async {
+        let nothing = JsonObject::new();
+        let spin = plugins.call("wasm_spin", &nothing);
+        assert!(tokio::time::timeout(Duration::from_millis(100), spin)
+            .await
+            .is_err());
+
+        let arguments = json!({ "text": "next" });
+        let echo = plugins.call("wasm_echo", arguments.as_object().unwrap());
+        let echoed = tokio::time::timeout(Duration::from_secs(5), echo)
+            .await
+            .expect("the abandoned call stopped");
+        assert_eq!(echoed.0.unwrap().is_error, Some(false));
+    });
+}
+
+#[test]
+fn invalid_plugins_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let plugins = host();
+    let reserved = McpServer::builtin_tool_names();
+
+    write_file(
+        dir.path(),
+        "clash.wat",
+        plugin_wat(r#"[{"name": "hello", "inputSchema": {"type": "object"}}]"#),
+    );
+    let error = plugins
+        .load(&dir.path().join("clash.wat"), &reserved)
+        .unwrap_err();
+    assert_eq!(error, "tool 'hello' is already defined");
+
+    write_file(
+        dir.path(),
+        "host.wat",
+        r#"(module (import "env" "fs_read" (func)) (memory (export "memory") 1))"#,
+    );
+    let error = plugins
+        .load(&dir.path().join("host.wat"), &reserved)
+        .unwrap_err();
+    assert!(error.contains("imports env::fs_read"));
+
+    write_file(dir.path(), "junk.wasm", "not wasm");
+    assert!(!plugins.sync(dir.path(), &reserved));
+    assert!(plugins.tools().is_empty());
+
+    // Two files for one plugin: neither wins
+    let other = tempfile::tempdir().unwrap();
+    write_file(other.path(), "demo.wat", plugin_wat(TOOLS));
+    write_file(other.path(), "demo.wasm", "also demo");
+    assert!(!plugins.sync(other.path(), &reserved));
+    assert!(plugins.tools().is_empty());
+    assert!(plugins.rejected().contains(&"demo".to_string()));
+}
+
+#[tokio::test]
+async fn rescans_reserve_the_tool_names_current_then() {
+    let dir = tempfile::tempdir().unwrap();
+    let plugins = host();
+    let state = ServerState {
+        plugins: plugins.clone(),
+        ..ServerState::default()
+    };
+    let peers = state.peers.clone();
+    let harness = Harness::with_state(state).await;
+    // Stands in for a manifest that declares wasm_echo, then drops it
+    let reserved = Arc::new(Mutex::new(HashSet::from(["wasm_echo".to_string()])));
+    let watcher = plugins
+        .clone()
+        .watch(
+            dir.path().to_path_buf(),
+            {
+                let reserved = reserved.clone();
+                move || reserved.lock().unwrap().clone()
+            },
+            peers,
+            Duration::from_millis(20),
+        )
+        .unwrap();
+
+    write_file(dir.path(), "demo.wat", plugin_wat(TOOLS));
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while plugins.rejected().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("the clashing plugin is rejected");
+
+    reserved.lock().unwrap().clear();
+    plugins.rescan();
+    harness
+        .wait_for_notification(|n| matches!(n, Notification::ToolListChanged))
+        .await;
+    assert!(plugins.get("wasm_echo").is_some());
+    assert!(plugins.rejected().is_empty());
+
+    watcher.abort();
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn loading_and_unloading_notify_sessions() {
+    let dir = tempfile::tempdir().unwrap();
+    let plugins = host();
+    let state = ServerState {
+        plugins: plugins.clone(),
+        ..ServerState::default()
+    };
+    let peers = state.peers.clone();
+    let harness = Harness::with_state(state).await;
+    let watcher = plugins
+        .clone()
+        .watch(
+            dir.path().to_path_buf(),
+            HashSet::new,
+            peers,
+            Duration::from_millis(20),
+        )
+        .unwrap();
+    let changes = || {
+        harness
+            .notifications()
+            .iter()
+            .filter(|n| matches!(n, Notification::ToolListChanged))
+            .count()
+    };
+
+    write_file(dir.path(), "demo.wat", plugin_wat(TOOLS));
+    harness
+        .wait_for_notification(|n| matches!(n, Notification::ToolListChanged))
+        .await;
+    let listed = harness.client.list_all_tools().await.unwrap();
+    assert!(listed.iter().any(|tool| tool.name == "wasm_echo"));
+
+    std::fs::remove_file(dir.path().join("demo.wat")).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while changes() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("unload is announced");
+    let listed = harness.client.list_all_tools().await.unwrap();
+    assert!(!listed.iter().any(|tool| tool.name == "wasm_echo"));
+
+    watcher.abort();
+    harness.shutdown().await;
+}
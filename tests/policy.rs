@@ -0,0 +1,204 @@
+//! The annotation-driven safety policy: rule parsing and evaluation in
+//! isolation, then enforcement in `call_tool`.
+
+mod common;
+
+use std::{collections::HashSet, sync::Arc};
+
+use arc_swap::ArcSwap;
+use common::{text, Harness, ScriptedClient};
+use mcp_rust_starter::{
+    command::Sandbox,
+    limits::{RateLimiter, RateLimits},
+    manifest::{Catalog, Manifest},
+    policy::{Action, Decision, Policy, Rule, Target},
+    McpServer, ServerState,
+};
+use pretty_assertions::assert_eq;
+use rmcp::model::{CreateElicitationResult, ElicitationAction, JsonObject, Tool, ToolAnnotations};
+use serde_json::json;
+
+fn tool(annotations: ToolAnnotations) -> Tool {
+    Tool::new("t", "test tool", JsonObject::new()).with_annotations(annotations)
+}
+
+fn server(rules: &str) -> McpServer {
+    let rules = rules.split(',').map(|rule| rule.parse().unwrap()).collect();
+    McpServer::with_state(ServerState {
+        policy: Arc::new(Policy::new(rules)),
+        ..ServerState::default()
+    })
+}
+
+#[test]
+fn rules_parse_and_display() {
+    let rule: Rule = "deny destructive unauthenticated".parse().unwrap();
+    assert_eq!(
+        rule,
+        Rule::new(Action::Deny, Target::Destructive).unauthenticated()
+    );
+    assert_eq!(rule.to_string(), "deny destructive unauthenticated");
+    assert_eq!(
+        "confirm open_world".parse::<Rule>(),
+        Ok(Rule::new(Action::Confirm, Target::OpenWorld))
+    );
+    assert!("allow destructive".parse::<Rule>().is_err());
+    assert!("deny everything".parse::<Rule>().is_err());
+    assert!("deny destructive sometimes".parse::<Rule>().is_err());
+}
+
+#[test]
+fn evaluation_follows_annotations_and_authentication() {
+    let policy = Policy::new(vec![
+        Rule::new(Action::Deny, Target::Destructive).unauthenticated(),
+        Rule::new(Action::Confirm, Target::Destructive),
+    ]);
+    let destructive = tool(ToolAnnotations::new().read_only(false).destructive(true));
+    let read_only = tool(ToolAnnotations::new().read_only(true));
+
+    assert_eq!(
+        policy.evaluate(&destructive, false),
+        Decision::Deny(policy.rules[0])
+    );
+    assert_eq!(
+        policy.evaluate(&destructive, true),
+        Decision::Confirm(policy.rules[1])
+    );
+    assert_eq!(policy.evaluate(&read_only, false), Decision::Allow);
+    // Unset hints take the MCP defaults: destructive and open-world
+    assert_eq!(
+        policy.evaluate(&tool(ToolAnnotations::new()), true),
+        Decision::Confirm(policy.rules[1])
+    );
+    assert_eq!(
+        Policy::default().evaluate(&destructive, false),
+        Decision::Allow
+    );
+}
+
+#[tokio::test]
+async fn offline_policy_denies_open_world_tools() {
+    let harness = Harness::start(server("deny open_world"), ScriptedClient::new()).await;
+
+    let denied = harness
+        .call("get_feedback", json!({ "question": "How was it?" }))
+        .await;
+    assert_eq!(denied.is_error, Some(true));
+    assert_eq!(
+        denied.structured_content,
+        Some(json!({
+            "code": "policy_denied",
+            "message": "Tool 'get_feedback' is not allowed by policy (deny open_world)",
+            "retryable": false,
+            "tool": "get_feedback",
+            "rule": "deny open_world",
+        }))
+    );
+
+    // Closed-world tools are unaffected
+    let hello = harness.call("hello", json!({ "name": "Ada" })).await;
+    assert_eq!(hello.is_error, Some(false));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn confirm_rule_asks_the_user_first() {
+    let client = ScriptedClient::new().on_elicitation(|request| {
+        let confirm = format!("{request:?}").contains("get_feedback");
+        Ok(CreateElicitationResult::new(ElicitationAction::Accept)
+            .with_content(json!({ "confirm": confirm })))
+    });
+    let harness = Harness::start(server("confirm open_world"), client).await;
+
+    let result = harness
+        .call("get_feedback", json!({ "question": "How was it?" }))
+        .await;
+    assert_eq!(result.is_error, Some(false));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_call_runs_the_manifest_it_was_confirmed_against() {
+    let manifest: Manifest = toml::from_str(
+        r#"
+[[tools]]
+name = "fetch_page"
+description = "Pretend to fetch a page"
+annotations = { open_world = true }
+run = { type = "template", template = "fetched" }
+"#,
+    )
+    .unwrap();
+    let catalog = Catalog::new(manifest, &HashSet::new(), Sandbox::default()).unwrap();
+    let rules = vec!["confirm open_world".parse().unwrap()];
+    let state = ServerState {
+        policy: Arc::new(Policy::new(rules)),
+        manifest: Arc::new(ArcSwap::from_pointee(catalog)),
+        ..ServerState::default()
+    };
+    // The manifest is reloaded without the tool while the user confirms
+    let manifest = state.manifest.clone();
+    let client = ScriptedClient::new().on_elicitation(move |_| {
+        manifest.store(Arc::new(Catalog::default()));
+        Ok(CreateElicitationResult::new(ElicitationAction::Accept)
+            .with_content(json!({ "confirm": true })))
+    });
+    let harness = Harness::start(McpServer::with_state(state), client).await;
+
+    let result = harness.call("fetch_page", json!({})).await;
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(text(&result), "fetched");
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn a_call_waiting_for_confirmation_holds_no_concurrency_slot() {
+    let limits = Arc::new(RateLimiter::new(RateLimits {
+        max_concurrent_tool_calls: Some(1),
+        ..RateLimits::default()
+    }));
+    let state = ServerState {
+        policy: Arc::new(Policy::new(vec!["confirm open_world".parse().unwrap()])),
+        limits: limits.clone(),
+        ..ServerState::default()
+    };
+    let client = ScriptedClient::new().on_elicitation(move |_| {
+        // The only slot is still free while the user is asked
+        assert!(matches!(limits.acquire(), Ok(Some(_))));
+        Ok(CreateElicitationResult::new(ElicitationAction::Accept)
+            .with_content(json!({ "confirm": true })))
+    });
+    let harness = Harness::start(McpServer::with_state(state), client).await;
+
+    let result = harness
+        .call("get_feedback", json!({ "question": "How was it?" }))
+        .await;
+    assert_eq!(result.is_error, Some(false));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn confirm_rule_refuses_when_declined_or_impossible() {
+    let declining = ScriptedClient::new()
+        .on_elicitation(|_| Ok(CreateElicitationResult::new(ElicitationAction::Decline)));
+    let harness = Harness::start(server("confirm open_world"), declining).await;
+    let declined = harness
+        .call("get_feedback", json!({ "question": "How was it?" }))
+        .await;
+    assert_eq!(declined.is_error, Some(true));
+    assert!(text(&declined).ends_with("but the user did not confirm it"));
+    harness.shutdown().await;
+
+    // A client without elicitation support cannot confirm anything
+    let harness = Harness::start(server("confirm open_world"), ScriptedClient::new()).await;
+    let impossible = harness
+        .call("get_feedback", json!({ "question": "How was it?" }))
+        .await;
+    assert_eq!(impossible.is_error, Some(true));
+    assert!(text(&impossible).ends_with("but the client cannot ask the user to confirm it"));
+    harness.shutdown().await;
+}
@@ -0,0 +1,64 @@
+//! End-to-end tests for prompt templates.
+
+mod common;
+
+use common::Harness;
+use rmcp::model::{PromptMessageContent, PromptMessageRole};
+use serde_json::json;
+
+fn first_text(result: &rmcp::model::GetPromptResult) -> &str {
+    let message = result.messages.first().expect("prompt has a message");
+    assert_eq!(message.role, PromptMessageRole::User);
+    match &message.content {
+        PromptMessageContent::Text { text } => text,
+        other => panic!("expected text content, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn greet_supports_each_style() {
+    let harness = Harness::connect().await;
+    for (style, expected) in [
+        (
+            "formal",
+            "Please compose a formal, professional greeting for Ada.",
+        ),
+        (
+            "enthusiastic",
+            "Create an excited, enthusiastic greeting for Ada!",
+        ),
+        ("casual", "Write a casual, friendly hello to Ada."),
+    ] {
+        let result = harness
+            .prompt("greet", json!({ "name": "Ada", "style": style }))
+            .await
+            .unwrap();
+        assert_eq!(first_text(&result), expected);
+    }
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn greet_requires_name() {
+    let harness = Harness::connect().await;
+    assert!(harness.prompt("greet", json!({})).await.is_err());
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn code_review_wraps_code_in_fence() {
+    let harness = Harness::connect().await;
+    let result = harness
+        .prompt("code_review", json!({ "code": "fn main() {}" }))
+        .await
+        .unwrap();
+    assert!(first_text(&result).contains("```\nfn main() {}\n```"));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn unknown_prompt_is_rejected() {
+    let harness = Harness::connect().await;
+    assert!(harness.prompt("no_such_prompt", json!({})).await.is_err());
+    harness.shutdown().await;
+}
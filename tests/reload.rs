@@ -0,0 +1,148 @@
+//! Hot reload of the manifest: valid edits are swapped in and announced with
+//! the matching `list_changed` notifications (and `resources/updated` to
+//! subscribers), invalid ones are ignored.
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use common::{resource_text, write_file, Harness, Notification};
+use mcp_rust_starter::{command::Sandbox, manifest::Catalog, reload, McpServer, ServerState};
+use pretty_assertions::assert_eq;
+use rmcp::model::SubscribeRequestParams;
+
+const TOOL: &str = r#"
+[[tools]]
+name = "greet_team"
+description = "Greet a team"
+run = { type = "template", template = "Hello, team!" }
+"#;
+
+const PROMPT: &str = r#"
+[[prompts]]
+name = "summarize"
+messages = [{ text = "Summarize {{text}}" }]
+"#;
+
+const RESOURCE: &str = r#"
+[[resources]]
+uri = "doc://notes"
+name = "Notes"
+file = "notes.txt"
+"#;
+
+fn count(harness: &Harness, kind: fn(&Notification) -> bool) -> usize {
+    harness.notifications().iter().filter(|n| kind(n)).count()
+}
+
+/// Wait until `kind` has been notified `expected` times in total.
+async fn changes(harness: &Harness, kind: fn(&Notification) -> bool, expected: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while count(harness, kind) < expected {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("change is announced");
+}
+
+const fn tools(n: &Notification) -> bool {
+    matches!(n, Notification::ToolListChanged)
+}
+
+const fn prompts(n: &Notification) -> bool {
+    matches!(n, Notification::PromptListChanged)
+}
+
+const fn resources(n: &Notification) -> bool {
+    matches!(n, Notification::ResourceListChanged)
+}
+
+fn updated(n: &Notification) -> bool {
+    matches!(n, Notification::ResourceUpdated(uri) if uri == "doc://notes")
+}
+
+#[tokio::test]
+async fn edits_are_reloaded_and_announced() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("manifest.toml");
+    write_file(dir.path(), "manifest.toml", TOOL);
+    let state = ServerState::default();
+    let harness = Harness::with_state(state.clone()).await;
+    let watcher = reload::watch(&path, state, Duration::from_millis(20)).unwrap();
+
+    // Adding a prompt announces prompts only
+    write_file(dir.path(), "manifest.toml", format!("{TOOL}{PROMPT}"));
+    changes(&harness, prompts, 1).await;
+    assert_eq!((count(&harness, tools), count(&harness, resources)), (1, 0));
+    let listed = harness.client.list_all_prompts().await.unwrap();
+    assert!(listed.iter().any(|prompt| prompt.name == "summarize"));
+
+    // Resource files are watched alongside the manifest
+    write_file(dir.path(), "notes.txt", "first");
+    write_file(
+        dir.path(),
+        "manifest.toml",
+        format!("{TOOL}{PROMPT}{RESOURCE}"),
+    );
+    changes(&harness, resources, 1).await;
+    harness
+        .client
+        .subscribe(SubscribeRequestParams::new("doc://notes"))
+        .await
+        .unwrap();
+    write_file(dir.path(), "notes.txt", "second draft");
+    changes(&harness, resources, 2).await;
+    assert_eq!(
+        resource_text(&harness.read("doc://notes").await),
+        "second draft"
+    );
+    // Subscribers also hear which resource changed
+    changes(&harness, updated, 1).await;
+
+    // Removing the tool announces tools
+    write_file(dir.path(), "manifest.toml", format!("{PROMPT}{RESOURCE}"));
+    changes(&harness, tools, 2).await;
+    let listed = harness.client.list_all_tools().await.unwrap();
+    assert!(!listed.iter().any(|tool| tool.name == "greet_team"));
+
+    watcher.abort();
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn invalid_edits_keep_the_previous_definitions() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("manifest.toml");
+    write_file(dir.path(), "manifest.toml", TOOL);
+    let catalog =
+        Catalog::load(&path, &McpServer::builtin_tool_names(), Sandbox::default()).unwrap();
+    let state = ServerState {
+        manifest: Arc::new(ArcSwap::from_pointee(catalog)),
+        ..ServerState::default()
+    };
+    let harness = Harness::with_state(state.clone()).await;
+
+    for broken in [
+        "[[tools]\n",
+        "[[tools]]\nname = \"hello\"\ndescription = \"d\"\nrun = { type = \"template\", template = \"\" }\n",
+        &format!("{TOOL}{TOOL}"),
+    ] {
+        write_file(dir.path(), "manifest.toml", broken);
+        assert!(reload::reload(&path, &state).await.is_err());
+        let listed = harness.client.list_all_tools().await.unwrap();
+        assert!(listed.iter().any(|tool| tool.name == "greet_team"));
+    }
+    std::fs::remove_file(&path).unwrap();
+    assert!(reload::reload(&path, &state).await.is_err());
+    assert!(harness.notifications().is_empty());
+
+    // Fixing the file recovers
+    write_file(dir.path(), "manifest.toml", PROMPT);
+    reload::reload(&path, &state).await.unwrap();
+    changes(&harness, tools, 1).await;
+    changes(&harness, prompts, 1).await;
+
+    harness.shutdown().await;
+}
@@ -0,0 +1,116 @@
+//! End-to-end tests for static resources, resource templates and icon
+//! images.
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use common::{resource_text, Harness};
+use mcp_rust_starter::{icons, resources::InlineLimit, ServerState};
+use rmcp::model::{ReadResourceRequestParams, ResourceContents};
+use serde_json::json;
+
+/// Between the 48px icons (under 5 KB) and the full-size ones (over 30 KB).
+const MAX_INLINE_BYTES: usize = 10_000;
+
+#[tokio::test]
+async fn static_resources_are_readable() {
+    let harness = Harness::connect().await;
+    let about = harness.read("about://server").await;
+    assert!(resource_text(&about).starts_with("MCP Rust Starter"));
+    let doc = harness.read("doc://example").await;
+    assert!(resource_text(&doc).starts_with("# Example Document"));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn greeting_template_uses_name() {
+    let harness = Harness::connect().await;
+    let greeting = harness.read("greeting://Ada").await;
+    assert_eq!(
+        resource_text(&greeting),
+        "Hello, Ada! Welcome to the MCP Rust Starter Server."
+    );
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn item_template_returns_json() {
+    let harness = Harness::connect().await;
+    let item = harness.read("item://42").await;
+    let item: serde_json::Value = serde_json::from_str(resource_text(&item)).unwrap();
+    assert_eq!(item["id"], "42");
+    assert_eq!(item["name"], "Item 42");
+    let ResourceContents::TextResourceContents { mime_type, .. } =
+        &harness.read("item://42").await.contents[0]
+    else {
+        panic!("expected text contents");
+    };
+    assert_eq!(mime_type.as_deref(), Some("application/json"));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn unknown_resource_is_not_found() {
+    let harness = Harness::connect().await;
+    let result = harness
+        .client
+        .read_resource(ReadResourceRequestParams::new("nope://missing"))
+        .await;
+    assert!(result.is_err());
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn icons_are_blobs_until_too_large_to_inline() {
+    let state = ServerState {
+        inline_limit: InlineLimit(MAX_INLINE_BYTES),
+        ..ServerState::default()
+    };
+    let harness = Harness::with_state(state).await;
+
+    let listed = harness.client.list_all_resources().await.unwrap();
+    let robot = listed
+        .iter()
+        .find(|r| r.uri == "icon://robot_48.png")
+        .expect("icons are listed");
+    assert_eq!(robot.mime_type.as_deref(), Some("image/png"));
+    assert_eq!(robot.size, Some(4403));
+
+    let small = harness.read("icon://robot_48.png").await;
+    let ResourceContents::BlobResourceContents {
+        blob, mime_type, ..
+    } = &small.contents[0]
+    else {
+        panic!("expected a blob: {small:?}");
+    };
+    assert_eq!(mime_type.as_deref(), Some("image/png"));
+    assert_eq!(
+        BASE64.decode(blob).unwrap(),
+        icons::file("robot_48.png").unwrap().bytes
+    );
+
+    let large = harness.read("icon://robot.png").await;
+    let ResourceContents::TextResourceContents {
+        text,
+        mime_type,
+        meta,
+        ..
+    } = &large.contents[0]
+    else {
+        panic!("expected a link: {large:?}");
+    };
+    assert_eq!(mime_type.as_deref(), Some("text/uri-list"));
+    assert_eq!(text, "icon://robot.png");
+    assert_eq!(
+        serde_json::to_value(meta).unwrap(),
+        json!({ "mimeType": "image/png", "size": 43362 })
+    );
+
+    // SVG icons are not exposed as resources
+    let svg = harness
+        .client
+        .read_resource(ReadResourceRequestParams::new("icon://speech.svg"))
+        .await;
+    assert!(svg.is_err());
+    harness.shutdown().await;
+}
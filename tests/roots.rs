@@ -0,0 +1,145 @@
+//! Client roots: fetched after initialization and on `list_changed`, and
+//! confining file resources and command tools.
+
+mod common;
+
+use std::{collections::HashSet, path::Path, sync::Arc, time::Duration};
+
+use arc_swap::ArcSwap;
+use common::{Harness, Notification, ScriptedClient};
+use mcp_rust_starter::{
+    command::Sandbox,
+    files::FileResources,
+    manifest::{Catalog, Manifest},
+    McpServer, ServerState,
+};
+use pretty_assertions::assert_eq;
+use rmcp::model::ReadResourceRequestParams;
+use serde_json::json;
+use url::Url;
+
+fn uri(path: &Path) -> String {
+    Url::from_file_path(path.canonicalize().unwrap())
+        .unwrap()
+        .into()
+}
+
+/// A tree with one file in each of `a/` and `b/`, served as file resources.
+fn tree() -> (tempfile::TempDir, ServerState) {
+    let dir = tempfile::tempdir().unwrap();
+    for file in ["a/one.txt", "b/two.txt"] {
+        let path = dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, file).unwrap();
+    }
+    let files = FileResources::new(dir.path(), &["**"], &[], 100).unwrap();
+    let state = ServerState {
+        files: Arc::new(files),
+        ..ServerState::default()
+    };
+    (dir, state)
+}
+
+async fn files(harness: &Harness) -> Vec<String> {
+    let listed = harness.client.list_all_resources().await.unwrap();
+    listed
+        .into_iter()
+        .filter(|r| r.uri.starts_with("file://"))
+        .map(|r| r.name.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn roots_are_fetched_and_confine_file_resources() {
+    let (dir, state) = tree();
+    let client = ScriptedClient::new().with_roots(&[&uri(&dir.path().join("a"))]);
+    let harness = Harness::start(McpServer::with_state(state), client).await;
+
+    // Asked for once the client has initialized, before any request
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while harness.client.service().roots().listed() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("roots are listed");
+
+    assert_eq!(files(&harness).await, ["a/one.txt"]);
+    harness.read(&uri(&dir.path().join("a/one.txt"))).await;
+    let error = harness
+        .client
+        .read_resource(ReadResourceRequestParams::new(uri(&dir
+            .path()
+            .join("b/two.txt"))))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("outside the client's roots"));
+
+    // New roots are fetched when the client says they changed
+    harness
+        .client
+        .service()
+        .roots()
+        .set(&[&uri(&dir.path().join("b"))]);
+    harness.client.notify_roots_list_changed().await.unwrap();
+    harness
+        .wait_for_notification(|n| matches!(n, Notification::ResourceListChanged))
+        .await;
+    assert_eq!(files(&harness).await, ["b/two.txt"]);
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn only_clients_with_roots_are_confined() {
+    // Without the capability, every exposed file is available
+    let (_dir, state) = tree();
+    let harness = Harness::with_state(state).await;
+    assert_eq!(files(&harness).await, ["a/one.txt", "b/two.txt"]);
+    harness.shutdown().await;
+
+    // With it, no roots (or only non-file ones) means no files
+    let (_dir, state) = tree();
+    let client = ScriptedClient::new().with_roots(&["https://example.com/repo"]);
+    let harness = Harness::start(McpServer::with_state(state), client).await;
+    assert_eq!(files(&harness).await, Vec::<String>::new());
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn commands_run_only_inside_roots() {
+    let root = tempfile::tempdir().unwrap();
+    for dir in ["project", "other"] {
+        std::fs::create_dir(root.path().join(dir)).unwrap();
+    }
+    let manifest: Manifest = toml::from_str(
+        r#"
+[[tools]]
+name = "where"
+description = "Print the working directory"
+run = { type = "command", command = "pwd", working_dir = "{{dir}}" }
+"#,
+    )
+    .unwrap();
+    let sandbox = Sandbox::new([root.path().to_path_buf()]).unwrap();
+    let catalog = Catalog::new(manifest, &HashSet::new(), sandbox).unwrap();
+    let server = McpServer::with_state(ServerState {
+        manifest: Arc::new(ArcSwap::from_pointee(catalog)),
+        ..ServerState::default()
+    });
+    let client = ScriptedClient::new().with_roots(&[&uri(&root.path().join("project"))]);
+    let harness = Harness::start(server, client).await;
+
+    let inside = harness.call("where", json!({ "dir": "project" })).await;
+    assert_eq!(inside.is_error, Some(false));
+
+    let outside = harness.call("where", json!({ "dir": "other" })).await;
+    let error = outside.structured_content.unwrap();
+    assert_eq!(error["code"], "policy_denied");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("not in the client's roots"));
+
+    harness.shutdown().await;
+}
@@ -0,0 +1,642 @@
+//! Stateless HTTP: any instance behind a round-robin load balancer can serve
+//! any request, where stateful sessions are pinned to the instance that
+//! created them unless the instances share a session store, from which
+//! sessions are restored after a restart or on another replica. A client
+//! whose stream drops resumes it from the session's event log.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{
+    body::{to_bytes, Body, BodyDataStream},
+    http::{header, Request, Response, StatusCode},
+    Router,
+};
+use futures::StreamExt;
+use mcp_rust_starter::{
+    events::{EventLog, EventStream, Retention},
+    sessions::{self, Mode, PersistentSessionManager},
+    store::{MemoryStore, RedbStore, SessionRecord, SessionStore, Sessions},
+    ServerState,
+};
+use pretty_assertions::assert_eq;
+use rmcp::transport::streamable_http_server::session::ServerSseMessage;
+use serde_json::{json, Value};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tower::ServiceExt;
+
+const SESSION_ID: &str = "mcp-session-id";
+
+/// Two independent server processes, taking turns.
+struct RoundRobin {
+    instances: [Router; 2],
+    next: AtomicUsize,
+}
+
+/// What one instance answered.
+struct Reply {
+    status: StatusCode,
+    session: Option<String>,
+    messages: Vec<Value>,
+}
+
+/// A server process storing its sessions in `sessions`.
+fn instance(mode: Mode, sessions: Arc<Sessions>) -> Router {
+    let state = ServerState {
+        sessions,
+        ..ServerState::default()
+    };
+    let manager = Arc::new(PersistentSessionManager::new(state));
+    Router::new().nest_service("/mcp", sessions::service(manager, mode.config()))
+}
+
+impl RoundRobin {
+    fn new(mode: Mode) -> Self {
+        let instance = || instance(mode, Arc::default());
+        Self {
+            instances: [instance(), instance()],
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// POST `message` to the next instance in turn.
+    async fn post(&self, message: Value, session: Option<&str>) -> Reply {
+        let turn = self.next.fetch_add(1, Ordering::SeqCst) % self.instances.len();
+        post(&self.instances[turn], message, session).await
+    }
+}
+
+async fn post(app: &Router, message: Value, session: Option<&str>) -> Reply {
+    let mut request = Request::post("/mcp")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT, "application/json, text/event-stream");
+    if let Some(session) = session {
+        request = request.header(SESSION_ID, session);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::from(message.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let session = response
+        .headers()
+        .get(SESSION_ID)
+        .map(|id| id.to_str().unwrap().to_string());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    // Each SSE `data:` line carries one JSON-RPC message
+    let messages = String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter(|data| !data.trim().is_empty())
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    Reply {
+        status,
+        session,
+        messages,
+    }
+}
+
+async fn delete(app: &Router, session: &str) -> StatusCode {
+    let request = Request::delete("/mcp")
+        .header(SESSION_ID, session)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+fn initialize() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {},
+            "clientInfo": { "name": "round-robin-test", "version": "0.0.0" }
+        }
+    })
+}
+
+fn request(id: u32, method: &str, params: &Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn result(reply: &Reply) -> &Value {
+    assert_eq!(reply.status, StatusCode::OK);
+    let response = reply.messages.last().expect("a response");
+    assert!(response.get("error").is_none(), "{response}");
+    &response["result"]
+}
+
+#[tokio::test]
+async fn stateless_requests_are_served_by_either_instance() {
+    let balancer = RoundRobin::new(Mode::Stateless);
+
+    let init = balancer.post(initialize(), None).await;
+    assert_eq!(init.session, None);
+    let capabilities = &result(&init)["capabilities"];
+    assert_eq!(capabilities["tools"], json!({}));
+    assert_eq!(capabilities["resources"], json!({}));
+
+    // Without a session, every request stands alone, wherever it lands
+    for id in 1..=4 {
+        let tools = balancer
+            .post(request(id, "tools/list", &json!({})), None)
+            .await;
+        assert!(result(&tools)["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|tool| tool["name"] == "hello"));
+
+        let hello = balancer
+            .post(
+                request(
+                    id,
+                    "tools/call",
+                    &json!({ "name": "hello", "arguments": { "name": "Ada" } }),
+                ),
+                None,
+            )
+            .await;
+        assert_eq!(
+            result(&hello)["content"][0]["text"],
+            "Hello, Ada! Welcome to the MCP Rust Starter Server."
+        );
+        assert_eq!(hello.session, None);
+    }
+}
+
+#[tokio::test]
+async fn stateful_sessions_only_exist_on_their_own_instance() {
+    let balancer = RoundRobin::new(Mode::Stateful);
+    let [first, second] = &balancer.instances;
+
+    let init = post(first, initialize(), None).await;
+    let session = init.session.clone().expect("a session id");
+    assert_eq!(result(&init)["capabilities"]["tools"]["listChanged"], true);
+    let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    assert_eq!(
+        post(first, initialized, Some(&session)).await.status,
+        StatusCode::ACCEPTED
+    );
+
+    let elsewhere = post(second, request(1, "tools/list", &json!({})), Some(&session)).await;
+    assert_eq!(elsewhere.status, StatusCode::NOT_FOUND);
+    let home = post(first, request(2, "tools/list", &json!({})), Some(&session)).await;
+    assert!(result(&home)["tools"].is_array());
+}
+
+/// Initialize a session on `app`, returning its ID.
+async fn open_session(app: &Router) -> String {
+    let init = post(app, initialize(), None).await;
+    let session = init.session.clone().expect("a session id");
+    result(&init);
+    let initialized = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    assert_eq!(
+        post(app, initialized, Some(&session)).await.status,
+        StatusCode::ACCEPTED
+    );
+    session
+}
+
+#[tokio::test]
+async fn sessions_are_restored_from_a_shared_store() {
+    let sessions = Arc::new(Sessions::default());
+    let first = instance(Mode::Stateful, sessions.clone());
+    let session = open_session(&first).await;
+
+    let stored = sessions.get(&session).await.expect("a stored session");
+    assert_eq!(stored.client.client_info.name, "round-robin-test");
+    let load = post(
+        &first,
+        request(1, "tools/call", &json!({ "name": "load_bonus_tool" })),
+        Some(&session),
+    )
+    .await;
+    result(&load);
+    let subscribe = post(
+        &first,
+        request(
+            2,
+            "resources/subscribe",
+            &json!({ "uri": "about://server" }),
+        ),
+        Some(&session),
+    )
+    .await;
+    result(&subscribe);
+    let stored = sessions.get(&session).await.unwrap();
+    assert!(stored.data.tools.contains("bonus_calculator"));
+    assert!(stored.data.subscriptions.contains("about://server"));
+
+    // Another instance (or this one, restarted) has never seen the session,
+    // but takes it up with its loaded tool
+    let second = instance(Mode::Stateful, sessions.clone());
+    let tools = post(
+        &second,
+        request(3, "tools/list", &json!({})),
+        Some(&session),
+    )
+    .await;
+    assert!(result(&tools)["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|tool| tool["name"] == "bonus_calculator"));
+    let sum = post(
+        &second,
+        request(
+            4,
+            "tools/call",
+            &json!({
+                "name": "bonus_calculator",
+                "arguments": { "a": 2, "b": 3, "operation": "add" }
+            }),
+        ),
+        Some(&session),
+    )
+    .await;
+    let text = result(&sum)["content"][0]["text"].as_str().unwrap();
+    assert_eq!(serde_json::from_str::<Value>(text).unwrap()["result"], 5.0);
+
+    // Its changes are stored too
+    let unsubscribe = post(
+        &second,
+        request(
+            5,
+            "resources/unsubscribe",
+            &json!({ "uri": "about://server" }),
+        ),
+        Some(&session),
+    )
+    .await;
+    result(&unsubscribe);
+    assert!(sessions
+        .get(&session)
+        .await
+        .unwrap()
+        .data
+        .subscriptions
+        .is_empty());
+
+    // Deleting the session forgets it everywhere
+    assert!(delete(&second, &session).await.is_success());
+    assert_eq!(sessions.get(&session).await, None);
+    let third = instance(Mode::Stateful, sessions);
+    let gone = post(&third, request(6, "tools/list", &json!({})), Some(&session)).await;
+    assert_eq!(gone.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_requests_restore_a_session_once() {
+    let sessions = Arc::new(Sessions::default());
+    let first = open_session(&instance(Mode::Stateful, sessions.clone())).await;
+    let second = open_session(&instance(Mode::Stateful, sessions.clone())).await;
+
+    let state = ServerState {
+        sessions,
+        ..ServerState::default()
+    };
+    let manager = Arc::new(PersistentSessionManager::new(state));
+    let app = Router::new().nest_service(
+        "/mcp",
+        sessions::service(manager.clone(), Mode::Stateful.config()),
+    );
+    let replies = futures::future::join_all((0..8).map(|n| {
+        let session = if n % 2 == 0 { &first } else { &second };
+        post(&app, request(n, "tools/list", &json!({})), Some(session))
+    }))
+    .await;
+    for reply in &replies {
+        assert!(result(reply)["tools"].is_array());
+    }
+    assert_eq!(manager.live().await, 2);
+}
+
+#[tokio::test]
+async fn expired_sessions_are_not_restored() {
+    let sessions = Arc::new(Sessions::default());
+    let session = open_session(&instance(Mode::Stateful, sessions.clone())).await;
+    // Last seen longer ago than the TTL
+    let mut record = sessions.get(&session).await.unwrap();
+    record.last_seen -= sessions.ttl() * 2;
+    sessions.put(&session, &record).await;
+
+    let elsewhere = instance(Mode::Stateful, sessions);
+    let reply = post(
+        &elsewhere,
+        request(1, "tools/list", &json!({})),
+        Some(&session),
+    )
+    .await;
+    assert_eq!(reply.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn sessions_idle_for_the_ttl_are_closed() {
+    let sessions = Arc::new(Sessions::new(
+        MemoryStore::default(),
+        Duration::from_millis(300),
+    ));
+    let state = ServerState {
+        sessions: sessions.clone(),
+        ..ServerState::default()
+    };
+    let manager = Arc::new(PersistentSessionManager::new(state));
+    let app = Router::new().nest_service(
+        "/mcp",
+        sessions::service(manager.clone(), Mode::Stateful.config()),
+    );
+    let session = open_session(&app).await;
+    result(&post(&app, request(1, "tools/list", &json!({})), Some(&session)).await);
+    assert_eq!(manager.live().await, 1);
+
+    // The client goes away without deleting its session
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while manager.live().await > 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the idle session is closed");
+    assert_eq!(sessions.get(&session).await, None);
+}
+
+#[tokio::test]
+async fn the_redb_store_keeps_sessions_on_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sessions.redb");
+    let client = serde_json::from_value(initialize()["params"].clone()).unwrap();
+    let mut record = SessionRecord::new(client);
+    record.data.tools.insert("bonus_calculator".to_string());
+
+    let db = RedbStore::open(&path).unwrap();
+    db.save("one", &record).unwrap();
+    db.save("two", &record).unwrap();
+    db.remove("two").unwrap();
+    drop(db);
+
+    // As after a restart
+    let db = RedbStore::open(&path).unwrap();
+    assert_eq!(db.ids().unwrap(), ["one"]);
+    assert_eq!(db.load("one").unwrap(), Some(record));
+    assert_eq!(db.load("two").unwrap(), None);
+
+    // Expired sessions are pruned when the server starts
+    let sessions = Sessions::new(db, Duration::ZERO);
+    assert_eq!(sessions.prune().await, 1);
+    assert_eq!(sessions.get("one").await, None);
+}
+
+/// One event of an SSE response.
+#[derive(Debug)]
+struct Event {
+    id: String,
+    message: Option<Value>,
+}
+
+/// An SSE response, read event by event as it arrives.
+struct Events {
+    body: BodyDataStream,
+    buffer: String,
+}
+
+impl Events {
+    fn new(response: Response<Body>) -> Self {
+        assert_eq!(response.status(), StatusCode::OK);
+        Self {
+            body: response.into_body().into_data_stream(),
+            buffer: String::new(),
+        }
+    }
+
+    /// The next event with an ID, or `None` once the stream has ended.
+    async fn next(&mut self) -> Option<Event> {
+        loop {
+            while let Some(end) = self.buffer.find("\n\n") {
+                let block: String = self.buffer.drain(..end + 2).collect();
+                let field = |name: &str| {
+                    block
+                        .lines()
+                        .find_map(|line| line.strip_prefix(name))
+                        .map(|value| value.trim().to_string())
+                };
+                // Keep-alive comments have no ID
+                if let Some(id) = field("id:") {
+                    let message = field("data:")
+                        .filter(|data| !data.is_empty())
+                        .map(|data| serde_json::from_str(&data).unwrap());
+                    return Some(Event { id, message });
+                }
+            }
+            let chunk = tokio::time::timeout(Duration::from_secs(5), self.body.next())
+                .await
+                .expect("an event within 5s")?
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    /// The messages of every event until the stream ends.
+    async fn rest(mut self) -> Vec<Value> {
+        let mut messages = Vec::new();
+        while let Some(event) = self.next().await {
+            messages.extend(event.message);
+        }
+        messages
+    }
+}
+
+async fn send(app: &Router, request: axum::http::request::Builder, body: Body) -> Response<Body> {
+    app.clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap()
+}
+
+/// Open the standalone stream of `session`, or resume from `last_event_id`.
+async fn get(app: &Router, session: &str, last_event_id: Option<&str>) -> Response<Body> {
+    let mut request = Request::get("/mcp")
+        .header(header::ACCEPT, "text/event-stream")
+        .header(SESSION_ID, session);
+    if let Some(id) = last_event_id {
+        request = request.header("last-event-id", id);
+    }
+    send(app, request, Body::empty()).await
+}
+
+fn progress(message: &Value) -> Option<f64> {
+    (message["method"] == "notifications/progress")
+        .then(|| message["params"]["progress"].as_f64().unwrap())
+}
+
+#[tokio::test]
+async fn a_dropped_stream_is_resumed_from_the_event_log() {
+    let app = instance(Mode::Stateful, Arc::default());
+    let session = open_session(&app).await;
+
+    let call = request(
+        1,
+        "tools/call",
+        &json!({
+            "name": "long_task",
+            "arguments": { "taskName": "build", "steps": 5 },
+            "_meta": { "progressToken": "build-1" }
+        }),
+    );
+    let request = Request::post("/mcp")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ACCEPT, "application/json, text/event-stream")
+        .header(SESSION_ID, &session);
+    let mut events = Events::new(send(&app, request, Body::from(call.to_string())).await);
+
+    // Read two steps of progress, then lose the connection
+    let mut last_event_id = None;
+    let mut seen = Vec::new();
+    while seen.len() < 2 {
+        let event = events.next().await.expect("more events");
+        seen.extend(event.message.as_ref().and_then(progress));
+        last_event_id = Some(event.id);
+    }
+    drop(events);
+    assert_eq!(seen, [1.0, 2.0]);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    // Whatever was missed is replayed, then the stream runs to its end
+    let last_event_id = last_event_id.unwrap();
+    let resumed = Events::new(get(&app, &session, Some(&last_event_id)).await)
+        .rest()
+        .await;
+    let (result, notifications) = resumed.split_last().expect("the response");
+    let rest: Vec<f64> = notifications.iter().filter_map(progress).collect();
+    assert_eq!(rest, [3.0, 4.0, 5.0]);
+    assert_eq!(result["id"], 1);
+    assert!(result["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .ends_with("Task 'build' completed successfully!"));
+
+    // Resuming again from the same point replays the same events
+    let again = Events::new(get(&app, &session, Some(&last_event_id)).await)
+        .rest()
+        .await;
+    assert_eq!(&again, &resumed);
+
+    // An ID the log does not know opens the standalone stream instead
+    assert_eq!(
+        get(&app, &session, Some("unknown")).await.status(),
+        StatusCode::OK
+    );
+}
+
+/// A notification numbered `n`.
+fn message(n: u32) -> ServerSseMessage {
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/message",
+        "params": { "level": "info", "data": n }
+    });
+    ServerSseMessage {
+        event_id: None,
+        message: Some(Arc::new(serde_json::from_value(notification).unwrap())),
+        retry: None,
+    }
+}
+
+/// The IDs and numbers of the messages on `stream` until it ends.
+async fn drain(stream: EventStream) -> Vec<(String, Option<u64>)> {
+    stream
+        .map(|event| {
+            let number = event.message.map(|message| {
+                serde_json::to_value(message.as_ref()).unwrap()["params"]["data"]
+                    .as_u64()
+                    .unwrap()
+            });
+            (event.event_id.unwrap(), number)
+        })
+        .collect()
+        .await
+}
+
+/// A log keeping events as `retention` allows, with one stream of `n`
+/// messages that has ended.
+async fn finished_stream(retention: Retention, n: u32) -> EventLog {
+    let log = Arc::new(EventLog::new(retention));
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let live = log.attach(UnboundedReceiverStream::new(rx));
+    for i in 1..=n {
+        tx.send(message(i)).unwrap();
+    }
+    drop(tx);
+    // Connected all along, the client gets every message
+    assert_eq!(drain(live).await.len(), 1 + n as usize);
+    Arc::into_inner(log).expect("the stream has ended")
+}
+
+fn event(id: &str, n: u64) -> (String, Option<u64>) {
+    (id.to_string(), Some(n))
+}
+
+#[tokio::test]
+async fn the_event_log_keeps_only_the_latest_events() {
+    let hour = Duration::from_hours(1);
+    let log = finished_stream(Retention::default(), 3).await;
+    assert_eq!(
+        drain(log.resume("0-1").unwrap()).await,
+        [event("0-2", 2), event("0-3", 3)]
+    );
+    assert_eq!(drain(log.resume("0-3").unwrap()).await, []);
+    assert!(log.resume("1-0").is_none());
+    assert!(log.resume("junk").is_none());
+
+    let retention = Retention {
+        max_events: 2,
+        max_age: hour,
+    };
+    let log = finished_stream(retention, 4).await;
+    assert_eq!(
+        drain(log.resume("0-0").unwrap()).await,
+        [event("0-3", 3), event("0-4", 4)]
+    );
+
+    // Once nothing of a finished stream is kept, the stream is forgotten
+    let retention = Retention {
+        max_events: 256,
+        max_age: Duration::ZERO,
+    };
+    let log = finished_stream(retention, 2).await;
+    assert!(log.resume("0-0").is_none());
+}
+
+#[tokio::test]
+async fn resuming_a_running_stream_takes_it_over() {
+    let log = Arc::new(EventLog::new(Retention::default()));
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut first = log.attach(UnboundedReceiverStream::new(rx));
+    assert_eq!(first.next().await.unwrap().event_id.as_deref(), Some("0-0"));
+    tx.send(message(1)).unwrap();
+    assert_eq!(first.next().await.unwrap().event_id.as_deref(), Some("0-1"));
+    tx.send(message(2)).unwrap();
+
+    // The second client replays what it missed, then follows the stream
+    // while the first is cut off
+    let mut second = log.resume("0-1").unwrap();
+    assert_eq!(
+        second.next().await.unwrap().event_id.as_deref(),
+        Some("0-2")
+    );
+    tx.send(message(3)).unwrap();
+    drop(tx);
+    assert_eq!(drain(second).await, [event("0-3", 3)]);
+    let first: Vec<_> = drain(first).await;
+    assert!(first.len() <= 1, "{first:?}");
+}
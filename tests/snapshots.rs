@@ -0,0 +1,76 @@
+//! Golden snapshots of everything the server advertises.
+//!
+//! Any change to tool names, input/output schemas, annotations, icons,
+//! resources or prompts shows up as a snapshot diff. Review and accept
+//! intentional changes with `cargo snapshots` (requires `cargo install
+//! cargo-insta`), or `INSTA_UPDATE=always cargo test --test snapshots`
+//! followed by `git diff tests/snapshots`.
+
+mod common;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use common::Harness;
+use insta::{assert_json_snapshot, dynamic_redaction, Settings};
+
+/// Replace inline icon data with a short, stable digest of its contents so
+/// snapshots stay readable while still changing whenever an icon does.
+fn summarize_data_uri(src: &str) -> String {
+    let Some((header, data)) = src.split_once(',') else {
+        return src.to_string();
+    };
+    let bytes = STANDARD.decode(data).unwrap_or_default();
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{header},<{} bytes, fnv1a {hash:016x}>", bytes.len())
+}
+
+fn settings() -> Settings {
+    let mut settings = Settings::clone_current();
+    settings.set_sort_maps(true);
+    settings.add_redaction(
+        ".**.src",
+        dynamic_redaction(|value, _path| summarize_data_uri(value.as_str().unwrap_or_default())),
+    );
+    settings
+}
+
+#[tokio::test]
+async fn initialize_result() {
+    let harness = Harness::connect().await;
+    let info = harness.client.peer_info().expect("server info").clone();
+    settings().bind(|| assert_json_snapshot!("initialize", info));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn tools_list() {
+    let harness = Harness::connect().await;
+    let tools = harness.client.list_all_tools().await.unwrap();
+    settings().bind(|| assert_json_snapshot!("tools_list", tools));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn resources_list() {
+    let harness = Harness::connect().await;
+    let resources = harness.client.list_all_resources().await.unwrap();
+    settings().bind(|| assert_json_snapshot!("resources_list", resources));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn resource_templates_list() {
+    let harness = Harness::connect().await;
+    let templates = harness.client.list_all_resource_templates().await.unwrap();
+    settings().bind(|| assert_json_snapshot!("resource_templates_list", templates));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn prompts_list() {
+    let harness = Harness::connect().await;
+    let prompts = harness.client.list_all_prompts().await.unwrap();
+    settings().bind(|| assert_json_snapshot!("prompts_list", prompts));
+    harness.shutdown().await;
+}
@@ -0,0 +1,111 @@
+//! Tracing: one span per MCP request, continuing the caller's W3C trace.
+
+mod common;
+
+use common::{Harness, ScriptedClient};
+use mcp_rust_starter::{telemetry::Traced, McpServer};
+use opentelemetry::{
+    trace::{SpanId, TraceId, TracerProvider as _},
+    Value,
+};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use rmcp::model::{CallToolRequestParams, Meta};
+use serde_json::json;
+use tracing_subscriber::prelude::*;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+/// Run `calls` against a traced server and return the exported request spans.
+async fn request_spans(calls: Vec<CallToolRequestParams>) -> Vec<SpanData> {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    // The current-thread test runtime keeps every rmcp task on this thread.
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let harness = Harness::start(Traced::new(McpServer::new()), ScriptedClient::new()).await;
+    for call in calls {
+        let _ = harness.client.call_tool(call).await;
+    }
+    harness.shutdown().await;
+
+    exporter
+        .get_finished_spans()
+        .expect("spans exported")
+        .into_iter()
+        .filter(|span| span.name.starts_with("tools/call"))
+        .collect()
+}
+
+fn attribute(span: &SpanData, key: &str) -> Option<Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| kv.value.clone())
+}
+
+fn hello() -> CallToolRequestParams {
+    CallToolRequestParams::new("hello").with_arguments(
+        json!({ "name": "Tracer" })
+            .as_object()
+            .cloned()
+            .unwrap_or_default(),
+    )
+}
+
+#[tokio::test]
+async fn tool_call_span_records_method_tool_and_outcome() {
+    let spans = request_spans(vec![hello()]).await;
+
+    let [span] = spans.as_slice() else {
+        panic!("expected one tools/call span, got {}", spans.len());
+    };
+    assert_eq!(span.name, "tools/call hello");
+    assert_eq!(
+        attribute(span, "mcp.method"),
+        Some(Value::from("tools/call"))
+    );
+    assert_eq!(attribute(span, "mcp.tool"), Some(Value::from("hello")));
+    assert_eq!(attribute(span, "mcp.outcome"), Some(Value::from("ok")));
+}
+
+#[tokio::test]
+async fn traceparent_in_meta_becomes_span_parent() {
+    let mut call = hello();
+    let mut meta = Meta::new();
+    meta.0.insert(
+        "traceparent".into(),
+        json!(format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-01")),
+    );
+    call.meta = Some(meta);
+
+    let spans = request_spans(vec![call]).await;
+
+    let [span] = spans.as_slice() else {
+        panic!("expected one tools/call span, got {}", spans.len());
+    };
+    assert_eq!(
+        span.span_context.trace_id(),
+        TraceId::from_hex(TRACE_ID).unwrap()
+    );
+    assert_eq!(
+        span.parent_span_id,
+        SpanId::from_hex(PARENT_SPAN_ID).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn failed_request_records_error_outcome_and_code() {
+    let spans = request_spans(vec![CallToolRequestParams::new("no_such_tool")]).await;
+
+    let [span] = spans.as_slice() else {
+        panic!("expected one tools/call span, got {}", spans.len());
+    };
+    assert_eq!(attribute(span, "mcp.outcome"), Some(Value::from("error")));
+    assert!(attribute(span, "mcp.error_code").is_some());
+    assert_eq!(span.status, opentelemetry::trace::Status::error(""));
+}
@@ -0,0 +1,85 @@
+//! Per-tool deadlines, run on tokio's paused clock.
+
+mod common;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use common::{Harness, ScriptedClient};
+use mcp_rust_starter::{tools, McpServer, ServerState};
+use serde_json::json;
+use tokio::time::Instant;
+
+#[tokio::test(start_paused = true)]
+async fn long_task_is_cancelled_at_its_timeout() {
+    // 30 steps take 3s, three times the deadline given to long_task here
+    let server = McpServer::with_state(ServerState {
+        timeouts: Arc::new(HashMap::from([(
+            "long_task".to_string(),
+            Duration::from_secs(1),
+        )])),
+        ..ServerState::default()
+    });
+    let harness = Harness::start(server, ScriptedClient::new()).await;
+    let started = Instant::now();
+
+    let result = harness
+        .call("long_task", json!({ "taskName": "slow", "steps": 30 }))
+        .await;
+
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(
+        result.structured_content,
+        Some(json!({
+            "code": "timeout",
+            "message": "Tool 'long_task' timed out after 1s and was cancelled",
+            "retryable": true,
+            "timeoutMs": 1_000,
+        }))
+    );
+    assert!(common::text(&result).contains("timed out after 1s"));
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed >= Duration::from_secs(1) && elapsed < Duration::from_secs(2),
+        "cancelled after {elapsed:?}"
+    );
+
+    // The session is still usable after the cancelled call
+    let hello = harness.call("hello", json!({ "name": "again" })).await;
+    assert_ne!(hello.is_error, Some(true));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn long_task_always_completes_within_its_timeout() {
+    let harness = Harness::connect().await;
+
+    // The most steps the schema accepts take 9 of the 10 seconds
+    let result = harness
+        .call("long_task", json!({ "taskName": "longest", "steps": 90 }))
+        .await;
+
+    assert_ne!(result.is_error, Some(true));
+    assert!(common::text(&result).contains("completed successfully"));
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn timeouts_are_advertised_in_tool_meta() {
+    let harness = Harness::connect().await;
+    let tools = harness.client.list_all_tools().await.unwrap();
+    let timeout = |name: &str| {
+        tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .map(tools::timeout_of)
+            .unwrap()
+    };
+
+    assert_eq!(timeout("long_task"), Duration::from_secs(10));
+    assert_eq!(timeout("confirm_action"), Duration::from_mins(5));
+    assert_eq!(timeout("hello"), tools::DEFAULT_TIMEOUT);
+
+    harness.shutdown().await;
+}
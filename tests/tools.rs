@@ -0,0 +1,200 @@
+//! End-to-end tests for every tool, driven through the in-process harness.
+
+mod common;
+
+use common::{json, text, Harness, Notification};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+#[tokio::test]
+async fn hello_greets_by_name() {
+    let harness = Harness::connect().await;
+    let result = harness.call("hello", json!({ "name": "Ada" })).await;
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(
+        text(&result),
+        "Hello, Ada! Welcome to the MCP Rust Starter Server."
+    );
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn get_weather_returns_weather_for_city() {
+    let harness = Harness::connect().await;
+    let weather = json(&harness.call("get_weather", json!({ "city": "Oslo" })).await);
+    assert_eq!(weather["location"], "Oslo");
+    assert_eq!(weather["unit"], "celsius");
+    assert!((15..35).contains(&weather["temperature"].as_i64().unwrap()));
+    assert!((40..80).contains(&weather["humidity"].as_i64().unwrap()));
+    assert!(
+        ["sunny", "cloudy", "rainy", "windy"].contains(&weather["conditions"].as_str().unwrap())
+    );
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn long_task_reports_each_step() {
+    let harness = Harness::connect().await;
+    let result = harness
+        .call("long_task", json!({ "taskName": "build", "steps": 2 }))
+        .await;
+    assert_eq!(
+        text(&result),
+        "Starting task 'build' with 2 steps:\n\
+         Step 1/2 completed\n\
+         Step 2/2 completed\n\
+         Task 'build' completed successfully!"
+    );
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn long_task_sends_progress_for_each_step() {
+    let harness = Harness::connect().await;
+    harness
+        .call_with_progress(
+            "long_task",
+            json!({ "taskName": "build", "steps": 3 }),
+            "build-1",
+        )
+        .await;
+    harness
+        .wait_for_notification(|n| matches!(n, Notification::Progress(p) if p.progress >= 3.0))
+        .await;
+    let progress: Vec<(f64, Option<f64>, String)> = harness
+        .notifications()
+        .into_iter()
+        .filter_map(|notification| match notification {
+            Notification::Progress(p) => Some((p.progress, p.total, p.message.unwrap_or_default())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        progress,
+        [
+            (1.0, Some(3.0), "Step 1/3 completed".to_string()),
+            (2.0, Some(3.0), "Step 2/3 completed".to_string()),
+            (3.0, Some(3.0), "Step 3/3 completed".to_string())
+        ]
+    );
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn load_bonus_tool_describes_dynamic_loading() {
+    let harness = Harness::connect().await;
+    let result = json(&harness.call("load_bonus_tool", json!({})).await);
+    assert!(result["note"]
+        .as_str()
+        .unwrap()
+        .contains("dynamic tool loading"));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn load_bonus_tool_adds_bonus_calculator_to_the_session() {
+    let harness = Harness::connect().await;
+    let listed = harness.client.list_all_tools().await.unwrap();
+    assert!(!listed.iter().any(|tool| tool.name == "bonus_calculator"));
+
+    harness.call("load_bonus_tool", json!({})).await;
+    harness
+        .wait_for_notification(|n| matches!(n, Notification::ToolListChanged))
+        .await;
+    let listed = harness.client.list_all_tools().await.unwrap();
+    assert!(listed.iter().any(|tool| tool.name == "bonus_calculator"));
+
+    let product = harness
+        .call(
+            "bonus_calculator",
+            json!({ "a": 6, "b": 7, "operation": "multiply" }),
+        )
+        .await;
+    assert_eq!(json(&product)["result"], 42.0);
+    let divided = harness
+        .call(
+            "bonus_calculator",
+            json!({ "a": 1, "b": 0, "operation": "divide" }),
+        )
+        .await;
+    assert_eq!(divided.structured_content.unwrap()["code"], "invalid_input");
+
+    // Loading again changes nothing
+    let again = json(&harness.call("load_bonus_tool", json!({})).await);
+    assert!(again["note"].as_str().unwrap().contains("already loaded"));
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn ask_llm_echoes_prompt_and_token_budget() {
+    let harness = Harness::connect().await;
+    let result = json(&harness.call("ask_llm", json!({ "prompt": "Why?" })).await);
+    assert_eq!(result["prompt"], "Why?");
+    assert_eq!(result["max_tokens"], 100);
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn confirm_action_echoes_action() {
+    let harness = Harness::connect().await;
+    let result = json(
+        &harness
+            .call(
+                "confirm_action",
+                json!({ "action": "wipe cache", "destructive": true }),
+            )
+            .await,
+    );
+    assert_eq!(result["action"], "wipe cache");
+    assert_eq!(result["destructive"], true);
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn get_feedback_echoes_question() {
+    let harness = Harness::connect().await;
+    let result = json(
+        &harness
+            .call("get_feedback", json!({ "question": "How was it?" }))
+            .await,
+    );
+    assert_eq!(result["question"], "How was it?");
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn unknown_tool_is_a_protocol_error() {
+    let harness = Harness::connect().await;
+    assert!(harness.try_call("no_such_tool", json!({})).await.is_err());
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn missing_required_argument_is_rejected() {
+    let harness = Harness::connect().await;
+    assert!(harness.try_call("hello", json!({})).await.is_err());
+    harness.shutdown().await;
+}
+
+#[test]
+fn tool_errors_render_as_is_error_results() {
+    use mcp_rust_starter::tools::{ToolError, ToolResponse};
+    use rmcp::handler::server::tool::IntoCallToolResult;
+
+    let error = ToolError::unavailable("Weather provider is down")
+        .retryable(false)
+        .with_detail("provider", "simulated");
+    let result = ToolResponse::from(error).into_call_tool_result().unwrap();
+
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(text(&result), "Weather provider is down");
+    assert_eq!(
+        result.structured_content,
+        Some(json!({
+            "code": "unavailable",
+            "message": "Weather provider is down",
+            "retryable": false,
+            "provider": "simulated",
+        }))
+    );
+}
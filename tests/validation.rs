@@ -0,0 +1,220 @@
+//! Declarative argument constraints: advertised in the input schema and
+//! enforced before the tool body runs.
+
+mod common;
+
+use common::Harness;
+use mcp_rust_starter::validation::{FieldError, Validator};
+use pretty_assertions::assert_eq;
+use rmcp::{model::ErrorCode, ErrorData, ServiceError};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn constraints_are_advertised_in_the_input_schema() {
+    let harness = Harness::connect().await;
+    let tools = harness.client.list_all_tools().await.unwrap();
+    let property = |tool: &str, field: &str| {
+        let tool = tools.iter().find(|t| t.name == tool).unwrap();
+        tool.input_schema["properties"][field].clone()
+    };
+
+    let steps = property("long_task", "steps");
+    assert_eq!(
+        (steps["minimum"].clone(), steps["maximum"].clone()),
+        (json!(1), json!(90))
+    );
+    let name = property("hello", "name");
+    assert_eq!(name["minLength"], 1);
+    assert_eq!(name["maxLength"], 100);
+    assert!(name["pattern"].is_string());
+    assert_eq!(property("ask_llm", "maxTokens")["minimum"], 1);
+
+    harness.shutdown().await;
+}
+
+/// The `invalid_params` error a call is rejected with.
+async fn rejection(harness: &Harness, tool: &str, arguments: Value) -> ErrorData {
+    match harness.try_call(tool, arguments).await {
+        Err(ServiceError::McpError(error)) => error,
+        other => panic!("expected invalid_params, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn invalid_arguments_are_rejected_naming_each_field() {
+    let harness = Harness::connect().await;
+
+    let error = rejection(
+        &harness,
+        "long_task",
+        json!({ "taskName": "", "steps": -3 }),
+    )
+    .await;
+
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert_eq!(
+        error.message,
+        "Invalid arguments for tool 'long_task': \
+         steps: must be at least 1; taskName: must not be empty"
+    );
+    assert_eq!(
+        error.data,
+        Some(json!({
+            "errors": [
+                { "field": "steps", "message": "must be at least 1" },
+                { "field": "taskName", "message": "must not be empty" },
+            ],
+        }))
+    );
+
+    harness.shutdown().await;
+}
+
+#[tokio::test]
+async fn oversized_and_control_character_strings_are_rejected() {
+    let harness = Harness::connect().await;
+
+    let long = rejection(&harness, "hello", json!({ "name": "a".repeat(1_000_000) })).await;
+    assert!(long
+        .message
+        .ends_with("name: must be at most 100 characters long"));
+
+    let control = rejection(&harness, "hello", json!({ "name": "Ada\u{0}" })).await;
+    assert!(control.message.contains("name: must match the pattern"));
+
+    let tokens = rejection(
+        &harness,
+        "ask_llm",
+        json!({ "prompt": "hi", "maxTokens": -1 }),
+    )
+    .await;
+    assert!(tokens.message.ends_with("maxTokens: must be at least 1"));
+
+    // Valid arguments still reach the tool
+    let hello = harness.call("hello", json!({ "name": "Ada" })).await;
+    assert_eq!(hello.is_error, Some(false));
+
+    harness.shutdown().await;
+}
+
+#[test]
+fn missing_and_mistyped_fields_are_reported() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "prompt": { "type": "string" },
+            "maxTokens": { "type": "integer", "maximum": 4096 },
+        },
+        "required": ["prompt"],
+    });
+    let arguments = json!({ "maxTokens": "lots" });
+    let validator = Validator::new(schema.as_object().unwrap()).unwrap();
+
+    let errors = validator.validate(arguments.as_object()).unwrap_err();
+
+    assert_eq!(
+        errors,
+        vec![
+            FieldError {
+                field: "prompt".into(),
+                message: "is required".into(),
+            },
+            FieldError {
+                field: "maxTokens".into(),
+                message: "must be of type \"integer\"".into(),
+            },
+        ]
+    );
+    assert!(validator
+        .validate(json!({ "prompt": "hi", "maxTokens": 4096 }).as_object())
+        .is_ok());
+}
+
+#[test]
+fn fields_inside_objects_and_arrays_are_checked() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "address": {
+                "type": "object",
+                "properties": { "city": { "type": "string", "minLength": 1 } },
+                "required": ["city", "country"],
+            },
+            "tags": {
+                "type": "array",
+                "items": { "type": "string", "pattern": "^[a-z]+$" },
+            },
+            "unit": { "$ref": "#/$defs/Unit", "description": "Unit of the result" },
+        },
+        "$defs": { "Unit": { "type": "string", "enum": ["m", "km"] } },
+    });
+    let validator = Validator::new(schema.as_object().unwrap()).unwrap();
+    let arguments = json!({
+        "address": { "city": "" },
+        "tags": ["ok", "Not ok", 3],
+        "unit": "mi",
+    });
+
+    let errors = validator.validate(arguments.as_object()).unwrap_err();
+
+    let fields: Vec<(&str, &str)> = errors
+        .iter()
+        .map(|error| (error.field.as_str(), error.message.as_str()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("address.country", "is required"),
+            ("address.city", "must not be empty"),
+            ("tags[1]", "must match the pattern ^[a-z]+$"),
+            ("tags[2]", "must be of type \"string\""),
+            ("unit", "must be one of [\"m\",\"km\"]"),
+        ]
+    );
+    assert!(validator
+        .validate(
+            json!({ "address": { "city": "Oslo", "country": "NO" }, "tags": ["a"] }).as_object()
+        )
+        .is_ok());
+}
+
+#[test]
+fn schemas_that_cannot_be_checked_are_rejected() {
+    let remote = json!({
+        "type": "object",
+        "properties": { "point": { "$ref": "https://example.com/point.json" } },
+    });
+    assert_eq!(
+        Validator::new(remote.as_object().unwrap()).unwrap_err(),
+        "property 'point' uses '$ref' to 'https://example.com/point.json', \
+         which is not one of the schema's $defs"
+    );
+
+    let recursive = json!({
+        "type": "object",
+        "properties": { "tree": { "$ref": "#/$defs/Tree" } },
+        "$defs": {
+            "Tree": {
+                "type": "object",
+                "properties": { "children": { "type": "array", "items": { "$ref": "#/$defs/Tree" } } },
+            },
+        },
+    });
+    assert!(Validator::new(recursive.as_object().unwrap())
+        .unwrap_err()
+        .ends_with("uses '$ref' to '#/$defs/Tree' recursively"));
+
+    let either =
+        json!({ "type": "object", "anyOf": [{ "required": ["a"] }, { "required": ["b"] }] });
+    assert!(Validator::new(either.as_object().unwrap())
+        .unwrap_err()
+        .starts_with("the schema uses 'anyOf'"));
+
+    let pattern = json!({
+        "type": "object",
+        "properties": { "tags": { "type": "array", "items": { "type": "string", "pattern": "(" } } },
+    });
+    assert!(Validator::new(pattern.as_object().unwrap())
+        .unwrap_err()
+        .starts_with("property 'tags[]' has an invalid pattern"));
+}